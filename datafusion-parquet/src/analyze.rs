@@ -0,0 +1,273 @@
+//! Query analysis: runs a query and reports how much of the Parquet data it had to read.
+//!
+//! File and byte counts come from a [`MeteredStore`], which has to be registered as the
+//! object store of the queried tables, e.g. for the `file` scheme, before they are
+//! registered. Only the execution of the query is metered, not its planning, which opens
+//! every file of a table to collect statistics. Files count as skipped when they were
+//! listed below the location of a table the query scans, but not opened while executing
+//! it. Row counts, row groups pruned by statistics and compute time come from the metrics
+//! of the executed physical plan.
+
+use crate::hive::HiveTable;
+use crate::policy::MaskedTable;
+use async_trait::async_trait;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::listing::ListingTable;
+use datafusion::datasource::object_store::{
+    FileMetaStream, ListEntryStream, ObjectReader, ObjectStore, SizedFile,
+};
+use datafusion::datasource::TableProvider;
+use datafusion::error::Result;
+use datafusion::logical_plan::LogicalPlan;
+use datafusion::physical_plan::{collect, DisplayFormatType, ExecutionPlan};
+use datafusion::prelude::*;
+use futures::{AsyncRead, StreamExt};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// An object store that remembers the files it listed and opened and the bytes it read
+#[derive(Debug)]
+pub struct MeteredStore {
+    inner: Arc<dyn ObjectStore>,
+    listed: Arc<Mutex<HashSet<String>>>,
+    opened: Mutex<HashSet<String>>,
+    bytes_read: Arc<AtomicU64>,
+}
+
+impl MeteredStore {
+    pub fn new(inner: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            inner,
+            listed: Arc::new(Mutex::new(HashSet::new())),
+            opened: Mutex::new(HashSet::new()),
+            bytes_read: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Forgets the files opened and the bytes read so far, listed files are kept
+    pub fn reset(&self) {
+        self.opened.lock().unwrap().clear();
+        self.bytes_read.store(0, Ordering::SeqCst);
+    }
+
+    pub fn listed_files(&self) -> usize {
+        self.listed.lock().unwrap().len()
+    }
+
+    /// Files listed so far that are `root` or below it
+    pub fn listed_files_under(&self, root: &str) -> usize {
+        let root = root.trim_end_matches('/');
+        self.listed
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|path| {
+                path.as_str() == root || path.strip_prefix(root).is_some_and(|rest| rest.starts_with('/'))
+            })
+            .count()
+    }
+
+    pub fn opened_files(&self) -> usize {
+        self.opened.lock().unwrap().len()
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for MeteredStore {
+    async fn list_file(&self, prefix: &str) -> Result<FileMetaStream> {
+        let listed = self.listed.clone();
+        let files = self.inner.list_file(prefix).await?;
+        Ok(Box::pin(files.map(move |file| {
+            if let Ok(file) = &file {
+                listed.lock().unwrap().insert(file.path().to_owned());
+            }
+            file
+        })))
+    }
+
+    async fn list_dir(&self, prefix: &str, delimiter: Option<String>) -> Result<ListEntryStream> {
+        self.inner.list_dir(prefix, delimiter).await
+    }
+
+    fn file_reader(&self, file: SizedFile) -> Result<Arc<dyn ObjectReader>> {
+        self.opened.lock().unwrap().insert(file.path.clone());
+        Ok(Arc::new(MeteredReader {
+            inner: self.inner.file_reader(file)?,
+            bytes_read: self.bytes_read.clone(),
+        }))
+    }
+}
+
+struct MeteredReader {
+    inner: Arc<dyn ObjectReader>,
+    bytes_read: Arc<AtomicU64>,
+}
+
+#[async_trait]
+impl ObjectReader for MeteredReader {
+    async fn chunk_reader(&self, start: u64, length: usize) -> Result<Box<dyn AsyncRead>> {
+        // counted as requested, the Parquet reader only uses the synchronous readers
+        self.bytes_read.fetch_add(length as u64, Ordering::SeqCst);
+        self.inner.chunk_reader(start, length).await
+    }
+
+    fn sync_chunk_reader(&self, start: u64, length: usize) -> Result<Box<dyn Read + Send + Sync>> {
+        Ok(Box::new(CountingRead {
+            inner: self.inner.sync_chunk_reader(start, length)?,
+            bytes_read: self.bytes_read.clone(),
+        }))
+    }
+
+    fn length(&self) -> u64 {
+        self.inner.length()
+    }
+}
+
+struct CountingRead {
+    inner: Box<dyn Read + Send + Sync>,
+    bytes_read: Arc<AtomicU64>,
+}
+
+impl Read for CountingRead {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes_read.fetch_add(read as u64, Ordering::SeqCst);
+        Ok(read)
+    }
+}
+
+/// The metrics of one operator of the physical plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorMetrics {
+    /// Depth in the plan, 0 for the root
+    pub depth: usize,
+    /// One line description, e.g. `FilterExec: #country = China`
+    pub operator: String,
+    pub output_rows: Option<usize>,
+    pub elapsed_compute: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryAnalysis {
+    /// Rows in the result
+    pub rows: usize,
+    /// Wall clock time of planning and execution
+    pub elapsed: Duration,
+    pub files_scanned: usize,
+    pub files_skipped: usize,
+    pub row_groups_pruned: usize,
+    pub bytes_read: u64,
+    /// In plan order, parents before their children
+    pub operators: Vec<OperatorMetrics>,
+}
+
+impl Display for QueryAnalysis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for operator in &self.operators {
+            write!(f, "{:indent$}{}", "", operator.operator, indent = operator.depth * 2)?;
+            if let Some(rows) = operator.output_rows {
+                write!(f, "  rows={}", rows)?;
+            }
+            if let Some(elapsed) = operator.elapsed_compute {
+                write!(f, "  compute={:.3}ms", elapsed.as_secs_f64() * 1000.0)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "files: {} scanned, {} skipped", self.files_scanned, self.files_skipped)?;
+        writeln!(f, "row groups pruned by statistics: {}", self.row_groups_pruned)?;
+        writeln!(f, "bytes read: {}", self.bytes_read)?;
+        writeln!(f, "{} row(s) in {:.3}s", self.rows, self.elapsed.as_secs_f64())
+    }
+}
+
+/// Plans and runs `sql`, returning its results and how the tables of `store` were read
+pub async fn analyze(
+    ctx: &mut ExecutionContext,
+    store: &MeteredStore,
+    sql: &str,
+) -> Result<(Vec<RecordBatch>, QueryAnalysis)> {
+    let start = Instant::now();
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let mut roots = vec![];
+    table_roots(&plan, &mut roots);
+    let plan = ctx.create_physical_plan(&plan).await?;
+    // collecting statistics while planning opens every file
+    store.reset();
+    let batches = collect(plan.clone()).await?;
+
+    let listed: usize = roots.iter().map(|root| store.listed_files_under(root)).sum();
+    let mut analysis = QueryAnalysis {
+        rows: batches.iter().map(|batch| batch.num_rows()).sum(),
+        elapsed: start.elapsed(),
+        files_scanned: store.opened_files(),
+        files_skipped: listed.saturating_sub(store.opened_files()),
+        bytes_read: store.bytes_read(),
+        ..Default::default()
+    };
+    visit(&plan, 0, &mut analysis);
+    Ok((batches, analysis))
+}
+
+/// The distinct locations of the tables that `plan` scans
+fn table_roots(plan: &LogicalPlan, roots: &mut Vec<String>) {
+    if let LogicalPlan::TableScan { source, .. } = plan {
+        if let Some(root) = table_root(source.as_ref()) {
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+    }
+    for input in plan.inputs() {
+        table_roots(input, roots);
+    }
+}
+
+fn table_root(table: &dyn TableProvider) -> Option<String> {
+    let table = table.as_any();
+    if let Some(table) = table.downcast_ref::<ListingTable>() {
+        Some(table.table_path().to_owned())
+    } else if let Some(table) = table.downcast_ref::<HiveTable>() {
+        Some(table.root().to_owned())
+    } else {
+        table_root(table.downcast_ref::<MaskedTable>()?.inner().as_ref())
+    }
+}
+
+fn visit(plan: &Arc<dyn ExecutionPlan>, depth: usize, analysis: &mut QueryAnalysis) {
+    let metrics = plan.metrics();
+    if let Some(metrics) = &metrics {
+        // reported by the Parquet scan per file
+        analysis.row_groups_pruned += metrics
+            .sum(|metric| metric.value().name() == "row_groups_pruned")
+            .map_or(0, |pruned| pruned.as_usize());
+    }
+    analysis.operators.push(OperatorMetrics {
+        depth,
+        operator: OneLine(plan.as_ref()).to_string(),
+        output_rows: metrics.as_ref().and_then(|metrics| metrics.output_rows()),
+        elapsed_compute: metrics
+            .as_ref()
+            .and_then(|metrics| metrics.elapsed_compute())
+            .map(|nanos| Duration::from_nanos(nanos as u64)),
+    });
+    for child in plan.children() {
+        visit(&child, depth + 1, analysis);
+    }
+}
+
+struct OneLine<'a>(&'a dyn ExecutionPlan);
+
+impl Display for OneLine<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_as(DisplayFormatType::Default, f)
+    }
+}
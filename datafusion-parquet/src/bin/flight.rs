@@ -0,0 +1,39 @@
+//! Arrow Flight SQL server over Parquet listing tables, see `datafusion_parquet::flight_sql`.
+//!
+//! ```text
+//! flight [--listen <addr>] <tables>
+//! ```
+//!
+//! The tables are given as for every binary, see `datafusion_parquet::Args`. Addresses
+//! other than localhost are only served with `--policy` and `--role`.
+
+use datafusion::error::{DataFusionError, Result};
+use datafusion::prelude::*;
+use datafusion_parquet::flight_sql::{FlightSqlServer, FlightSqlService};
+use datafusion_parquet::Args;
+use std::env;
+use std::net::SocketAddr;
+use tonic::transport::Server;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse(env::args().skip(1), &["--listen"])?;
+    let listen: SocketAddr = match args.options.get("--listen") {
+        Some(listen) => listen
+            .parse()
+            .map_err(|_| DataFusionError::Plan(format!("invalid address '{}'", listen)))?,
+        None => ([127, 0, 0, 1], 50051).into(),
+    };
+    args.check_listen(listen)?;
+    let mut ctx = ExecutionContext::new();
+    args.register(&mut ctx).await?;
+
+    println!("Flight SQL listening on {}", listen);
+    Server::builder()
+        .add_service(FlightSqlServer::new(FlightSqlService::new(ctx)))
+        .serve_with_shutdown(listen, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|e| DataFusionError::Execution(e.to_string()))
+}
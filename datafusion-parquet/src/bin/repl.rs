@@ -0,0 +1,236 @@
+//! Interactive SQL shell over Parquet listing tables.
+//!
+//! ```text
+//! repl [<table>=<path or url>]... [--schema <table>=<schema file>]... [--catalog <toml file>]
+//!      [--policy <toml file> --role <role>]
+//! ```
+//!
+//! Without tables or a catalog, `my_table` is registered over `./data/`. Local files are
+//! read through a metered object store, so `\analyze` can report how much a query read.
+//! With a role, the columns its policy masks cannot be read unmasked, and no tables can be
+//! created.
+
+use datafusion::arrow::util::pretty;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::{csv, json};
+use datafusion::datasource::object_store::local::LocalFileSystem;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_plan::LogicalPlan;
+use datafusion::prelude::*;
+use datafusion_parquet::analyze::{analyze, MeteredStore};
+use datafusion_parquet::export::parse_copy;
+use datafusion_parquet::Args;
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+const HELP: &str = "\
+\\d                         list tables
+\\d <table>                 describe the columns of a table
+\\timing                    toggle query timing
+\\analyze                   toggle reporting files, row groups and bytes read and per
+                           operator rows and compute time after each query
+\\format <table|csv|json>   switch the output format
+\\?                         show this help
+\\q                         quit
+SQL statements end with ';' and may span several lines. Results are written to files with
+COPY (<query>) TO '<path>' [(FORMAT <parquet|csv|ndjson>, PARTITION_BY '<columns>', ...)];";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+struct Shell {
+    ctx: ExecutionContext,
+    store: Arc<MeteredStore>,
+    format: OutputFormat,
+    timing: bool,
+    analyze: bool,
+    /// Whether a masking policy applies
+    masked: bool,
+}
+
+impl Shell {
+    /// Runs a backslash command, returns false when the shell should quit
+    async fn meta_command(&mut self, command: &str) -> Result<bool> {
+        let mut words = command.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("\\q"), _) => return Ok(false),
+            (Some("\\?"), _) => println!("{}", HELP),
+            (Some("\\d"), None) => self.run("SHOW TABLES").await?,
+            (Some("\\d"), Some(table)) => self.run(&format!("SHOW COLUMNS FROM {}", table)).await?,
+            (Some("\\timing"), _) => {
+                self.timing = !self.timing;
+                println!("timing is {}", if self.timing { "on" } else { "off" });
+            }
+            (Some("\\analyze"), _) => {
+                self.analyze = !self.analyze;
+                println!("analyze is {}", if self.analyze { "on" } else { "off" });
+            }
+            (Some("\\format"), format) => {
+                self.format = match format {
+                    Some("table") => OutputFormat::Table,
+                    Some("csv") => OutputFormat::Csv,
+                    Some("json") => OutputFormat::Json,
+                    _ => {
+                        return Err(DataFusionError::Plan(
+                            "expected \\format table, csv or json".to_owned(),
+                        ))
+                    }
+                };
+            }
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "unknown command '{}', \\? lists the commands",
+                    command
+                )))
+            }
+        }
+        Ok(true)
+    }
+
+    /// Runs a SQL statement and prints its results in the current output format
+    async fn run(&mut self, sql: &str) -> Result<()> {
+        let start = Instant::now();
+        if let Some(copy) = parse_copy(sql) {
+            let copy = copy?;
+            let rows = copy.execute(&mut self.ctx).await?;
+            println!("{} row(s) written to {}", rows, copy.path.display());
+            if self.timing {
+                println!("{:.3}s", start.elapsed().as_secs_f64());
+            }
+            return Ok(());
+        }
+
+        if self.masked {
+            if let LogicalPlan::CreateExternalTable { .. } = self.ctx.create_logical_plan(sql)? {
+                return Err(DataFusionError::Plan(
+                    "tables cannot be created under a masking policy".to_owned(),
+                ));
+            }
+        }
+
+        if self.analyze {
+            let (batches, analysis) = analyze(&mut self.ctx, &self.store, sql).await?;
+            self.print(&batches)?;
+            print!("{}", analysis);
+            return Ok(());
+        }
+
+        let df = self.ctx.sql(sql).await?;
+        let batches = df.collect().await?;
+        let elapsed = start.elapsed();
+
+        self.print(&batches)?;
+        if self.timing {
+            let rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+            println!("{} row(s) in {:.3}s", rows, elapsed.as_secs_f64());
+        }
+        Ok(())
+    }
+
+    /// Prints results in the current output format
+    fn print(&self, batches: &[RecordBatch]) -> Result<()> {
+        match self.format {
+            // the same formatting as `df.show()`
+            OutputFormat::Table => pretty::print_batches(batches)?,
+            OutputFormat::Csv => {
+                let mut writer = csv::Writer::new(io::stdout());
+                for batch in batches {
+                    writer.write(batch)?;
+                }
+            }
+            OutputFormat::Json => {
+                let mut writer = json::LineDelimitedWriter::new(io::stdout());
+                writer.write_batches(batches)?;
+                writer.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn history_path() -> PathBuf {
+    let file = ".datafusion_parquet_history";
+    match env::var("HOME") {
+        Ok(home) => PathBuf::from(home).join(file),
+        Err(_) => PathBuf::from(file),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let config = ExecutionConfig::new().with_information_schema(true);
+    let mut ctx = ExecutionContext::with_config(config);
+    let store = Arc::new(MeteredStore::new(Arc::new(LocalFileSystem)));
+    ctx.register_object_store("file", store.clone());
+    let args = Args::parse(env::args().skip(1), &[])?;
+    args.register(&mut ctx).await?;
+    let mut shell = Shell {
+        ctx,
+        store,
+        format: OutputFormat::Table,
+        timing: false,
+        analyze: false,
+        masked: args.role.is_some(),
+    };
+
+    let mut editor = Editor::<()>::new();
+    let history = history_path();
+    // there is no history before the first session
+    let _ = editor.load_history(&history);
+
+    let mut statement = String::new();
+    loop {
+        let prompt = if statement.is_empty() { "> " } else { "| " };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                let trimmed = line.trim();
+                if statement.is_empty() {
+                    if trimmed.is_empty() {
+                        continue;
+                    }
+                    if trimmed.starts_with('\\') {
+                        editor.add_history_entry(trimmed);
+                        match shell.meta_command(trimmed).await {
+                            Ok(true) => continue,
+                            Ok(false) => break,
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                continue;
+                            }
+                        }
+                    }
+                }
+                statement.push_str(&line);
+                statement.push('\n');
+                if trimmed.ends_with(';') {
+                    let sql = statement.trim().to_owned();
+                    statement.clear();
+                    editor.add_history_entry(sql.as_str());
+                    if let Err(e) = shell.run(&sql).await {
+                        eprintln!("{}", e);
+                    }
+                }
+            }
+            // Ctrl-C discards the statement being typed
+            Err(ReadlineError::Interrupted) => statement.clear(),
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("{}", e);
+                break;
+            }
+        }
+    }
+
+    editor
+        .save_history(&history)
+        .map_err(|e| DataFusionError::Execution(e.to_string()))
+}
@@ -0,0 +1,48 @@
+//! HTTP query service over Parquet listing tables, see `datafusion_parquet::query_server`.
+//!
+//! ```text
+//! server [--listen <addr>] [--timeout <secs>] [--max-concurrent <n>] <tables>
+//! ```
+//!
+//! The tables are given as for every binary, see `datafusion_parquet::Args`. Addresses
+//! other than localhost are only served with `--policy` and `--role`, so personal data
+//! is masked for every client that can reach the server.
+
+use datafusion::error::{DataFusionError, Result};
+use datafusion::prelude::*;
+use datafusion_parquet::query_server::{QueryService, ServerOptions};
+use datafusion_parquet::Args;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::Server;
+use std::convert::Infallible;
+use std::env;
+use std::sync::Arc;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse(env::args().skip(1), &["--listen", "--timeout", "--max-concurrent"])?;
+    let options = ServerOptions::from_args(&args)?;
+    let mut ctx = ExecutionContext::new();
+    args.register(&mut ctx).await?;
+
+    let service = Arc::new(QueryService::new(ctx, options.timeout, options.max_concurrent));
+    let make_service = make_service_fn(move |_| {
+        let service = service.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let service = service.clone();
+                async move { Ok::<_, Infallible>(service.handle(request).await) }
+            }))
+        }
+    });
+
+    println!("listening on http://{}", options.listen);
+    Server::try_bind(&options.listen)
+        .map_err(|e| DataFusionError::Execution(e.to_string()))?
+        .serve(make_service)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|e| DataFusionError::Execution(e.to_string()))
+}
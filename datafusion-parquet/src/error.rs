@@ -0,0 +1,63 @@
+use datafusion::error::DataFusionError;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Errors while loading a table catalog.
+/// PartialEq is to enable testing for specific error types
+#[derive(Debug, PartialEq)]
+pub enum CatalogError {
+    /// Wrapper for errors while reading the catalog file
+    Io(String),
+    /// Returned when the catalog is not valid TOML or has unknown keys
+    Parse(String),
+    /// Returned when entries are not valid, with one line per problem
+    Invalid(String),
+}
+
+impl Display for CatalogError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatalogError::Io(desc) => write!(f, "Cannot read catalog: {}", desc),
+            CatalogError::Parse(desc) => write!(f, "Cannot parse catalog: {}", desc),
+            CatalogError::Invalid(desc) => write!(f, "Invalid catalog:\n{}", desc),
+        }
+    }
+}
+
+impl Error for CatalogError {}
+
+/// An unusable catalog is a planning error of the binaries that load it
+impl From<CatalogError> for DataFusionError {
+    fn from(err: CatalogError) -> Self {
+        DataFusionError::Plan(err.to_string())
+    }
+}
+
+/// Errors while loading a masking policy
+#[derive(Debug, PartialEq)]
+pub enum PolicyError {
+    /// Wrapper for errors while reading the policy file
+    Io(String),
+    /// Returned when the policy is not valid TOML or has unknown keys
+    Parse(String),
+    /// Returned when roles are not valid, with one line per problem
+    Invalid(String),
+}
+
+impl Display for PolicyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyError::Io(desc) => write!(f, "Cannot read policy: {}", desc),
+            PolicyError::Parse(desc) => write!(f, "Cannot parse policy: {}", desc),
+            PolicyError::Invalid(desc) => write!(f, "Invalid policy:\n{}", desc),
+        }
+    }
+}
+
+impl Error for PolicyError {}
+
+impl From<PolicyError> for DataFusionError {
+    fn from(err: PolicyError) -> Self {
+        DataFusionError::Plan(err.to_string())
+    }
+}
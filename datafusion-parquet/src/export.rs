@@ -0,0 +1,429 @@
+//! Writing query results to Parquet, CSV or NDJSON files, optionally partitioned into
+//! `<column>=<value>` directories, and the `COPY (<query>) TO '<path>'` statement:
+//!
+//! ```sql
+//! COPY (SELECT * FROM my_table WHERE salary > 100000)
+//! TO './out/' (FORMAT parquet, PARTITION_BY 'country', COMPRESSION zstd);
+//! ```
+//!
+//! Without `FORMAT`, the format follows the extension of the path and defaults to Parquet.
+//! All other options configure the Parquet writer, see [`writer_properties`].
+
+use datafusion::arrow::array::BooleanArray;
+use datafusion::arrow::compute::filter_record_batch;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::error::Result as ArrowResult;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::display::array_value_to_string;
+use datafusion::arrow::{csv, json};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::parquet::arrow::ArrowWriter;
+use datafusion::parquet::basic::Compression;
+use datafusion::parquet::file::properties::WriterProperties;
+use datafusion::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Directory name of null and empty partition values, as used by Hive and Spark
+pub const NULL_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+#[derive(Debug, Clone)]
+pub enum ExportFormat {
+    Parquet(WriterProperties),
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Parquet(_) => "parquet",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Columns to partition the output by, in directory order
+    pub partition_by: Vec<String>,
+}
+
+/// Writes the result of `df` to `path` and returns the number of rows written.
+///
+/// Without partition columns `path` is the output file. Otherwise it is a directory with
+/// one `<column>=<value>/.../part-0.<extension>` file per distinct combination of the
+/// partition columns, which are not repeated in the files. The query runs once and its
+/// result is split in memory, values are escaped as by Hive, see [`escape_partition_value`].
+/// The directory must be new or empty, so no partitions of an earlier export are mixed in.
+pub async fn export(df: Arc<dyn DataFrame>, path: &Path, options: &ExportOptions) -> Result<usize> {
+    if options.partition_by.is_empty() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        return write_file(df, path, &options.format).await;
+    }
+
+    for column in &options.partition_by {
+        df.schema().field_with_unqualified_name(column).map_err(|_| {
+            DataFusionError::Plan(format!("partition column '{}' is not part of the result", column))
+        })?;
+    }
+    let data_columns: Vec<&str> = df
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .filter(|name| !options.partition_by.iter().any(|column| column == name))
+        .collect();
+    if data_columns.is_empty() {
+        return Err(DataFusionError::Plan(
+            "at least one column must not be a partition column".to_owned(),
+        ));
+    }
+
+    let indices = |columns: &[&str]| -> Vec<usize> {
+        columns
+            .iter()
+            .filter_map(|column| df.schema().fields().iter().position(|field| field.name() == column))
+            .collect()
+    };
+    if fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(DataFusionError::Execution(format!(
+            "'{}' is not empty, a partitioned export needs a new or empty directory",
+            path.display()
+        )));
+    }
+
+    let partition_columns: Vec<&str> = options.partition_by.iter().map(String::as_str).collect();
+    let (partition_indices, data_indices) = (indices(&partition_columns), indices(&data_columns));
+
+    // the rows of each partition, by the display values of the partition columns; empty
+    // values go to the null partition, as a directory name cannot be empty
+    let mut partitions: BTreeMap<Vec<Option<String>>, Vec<RecordBatch>> = BTreeMap::new();
+    for batch in df.collect().await? {
+        let keys = (0..batch.num_rows())
+            .map(|row| {
+                partition_indices
+                    .iter()
+                    .map(|index| {
+                        let column = batch.column(*index);
+                        if column.is_null(row) {
+                            Ok(None)
+                        } else {
+                            array_value_to_string(column, row).map(|value| Some(value).filter(|value| !value.is_empty()))
+                        }
+                    })
+                    .collect::<ArrowResult<Vec<_>>>()
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        let distinct: BTreeSet<&Vec<Option<String>>> = keys.iter().collect();
+        for key in distinct {
+            let rows = BooleanArray::from(keys.iter().map(|row| row == key).collect::<Vec<_>>());
+            let partition = filter_record_batch(&batch, &rows)?.project(&data_indices)?;
+            partitions.entry(key.clone()).or_default().push(partition);
+        }
+    }
+
+    let mut rows = 0;
+    for (key, batches) in &partitions {
+        let mut dir = path.to_path_buf();
+        for (column, value) in options.partition_by.iter().zip(key) {
+            let value = value.as_deref().map_or_else(|| NULL_PARTITION.to_owned(), escape_partition_value);
+            dir.push(format!("{}={}", column, value));
+        }
+        fs::create_dir_all(&dir)?;
+        let file = dir.join(format!("part-0.{}", options.format.extension()));
+        rows += write_batches(batches[0].schema(), batches, &file, &options.format)?;
+    }
+    Ok(rows)
+}
+
+/// Escapes the characters of a partition value that Hive escapes in directory names as
+/// `%<hex>`, e.g. `/` as `%2F`
+pub fn escape_partition_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_ascii_control() || "\"#%'*/:=?\\{[]^".contains(c) {
+            escaped.push_str(&format!("%{:02X}", c as u8));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// The partition value of an escaped directory name, see [`escape_partition_value`]
+pub fn unescape_partition_value(escaped: &str) -> String {
+    let mut value = String::with_capacity(escaped.len());
+    let mut rest = escaped;
+    while let Some(index) = rest.find('%') {
+        value.push_str(&rest[..index]);
+        let hex = rest
+            .get(index + 1..index + 3)
+            .filter(|hex| hex.bytes().all(|digit| digit.is_ascii_hexdigit()));
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) if byte.is_ascii() => {
+                value.push(byte as char);
+                rest = &rest[index + 3..];
+            }
+            _ => {
+                value.push('%');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    value.push_str(rest);
+    value
+}
+
+async fn write_file(df: Arc<dyn DataFrame>, path: &Path, format: &ExportFormat) -> Result<usize> {
+    let schema: SchemaRef = Arc::new(df.schema().clone().into());
+    let batches = df.collect().await?;
+    // the batches carry the exact schema the writers check against
+    let schema = batches.first().map_or(schema, |batch| batch.schema());
+    write_batches(schema, &batches, path, format)
+}
+
+fn write_batches(schema: SchemaRef, batches: &[RecordBatch], path: &Path, format: &ExportFormat) -> Result<usize> {
+    let file = File::create(path)?;
+
+    match format {
+        ExportFormat::Parquet(properties) => {
+            let mut writer = ArrowWriter::try_new(file, schema, Some(properties.clone()))?;
+            for batch in batches {
+                writer.write(batch)?;
+            }
+            writer.close()?;
+        }
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::new(file);
+            for batch in batches {
+                writer.write(batch)?;
+            }
+        }
+        ExportFormat::Ndjson => {
+            let mut writer = json::LineDelimitedWriter::new(file);
+            writer.write_batches(batches)?;
+            writer.finish()?;
+        }
+    }
+    Ok(batches.iter().map(|batch| batch.num_rows()).sum())
+}
+
+/// Parquet writer properties from `(option, value)` pairs:
+///
+/// - `compression`: `none`, `snappy`, `gzip`, `lzo`, `brotli`, `lz4` or `zstd`
+/// - `max_row_group_size`: rows per row group
+/// - `data_page_size`: bytes per data page
+/// - `dictionary` and `statistics`: `true` or `false`
+pub fn writer_properties(options: &[(String, String)]) -> Result<WriterProperties> {
+    let mut builder = WriterProperties::builder();
+    for (option, value) in options {
+        builder = match option.to_ascii_lowercase().as_str() {
+            "compression" => builder.set_compression(match value.to_ascii_lowercase().as_str() {
+                "none" | "uncompressed" => Compression::UNCOMPRESSED,
+                "snappy" => Compression::SNAPPY,
+                "gzip" => Compression::GZIP,
+                "lzo" => Compression::LZO,
+                "brotli" => Compression::BROTLI,
+                "lz4" => Compression::LZ4,
+                "zstd" => Compression::ZSTD,
+                _ => return Err(invalid_option(option, value)),
+            }),
+            "max_row_group_size" => builder.set_max_row_group_size(parse_option(option, value)?),
+            "data_page_size" => builder.set_data_pagesize_limit(parse_option(option, value)?),
+            "dictionary" => builder.set_dictionary_enabled(parse_option(option, value)?),
+            "statistics" => builder.set_statistics_enabled(parse_option(option, value)?),
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "unknown Parquet writer option '{}'",
+                    option
+                )))
+            }
+        };
+    }
+    Ok(builder.build())
+}
+
+fn parse_option<T: std::str::FromStr>(option: &str, value: &str) -> Result<T> {
+    value.parse().map_err(|_| invalid_option(option, value))
+}
+
+fn invalid_option(option: &str, value: &str) -> DataFusionError {
+    DataFusionError::Plan(format!("invalid value '{}' for option '{}'", value, option))
+}
+
+/// A parsed `COPY (<query>) TO '<path>' [(<option> <value>, ...)]` statement
+#[derive(Debug, Clone)]
+pub struct CopyStatement {
+    pub query: String,
+    pub path: PathBuf,
+    pub options: ExportOptions,
+}
+
+impl CopyStatement {
+    /// Runs the query and writes its result, returns the number of rows written
+    pub async fn execute(&self, ctx: &mut ExecutionContext) -> Result<usize> {
+        let df = ctx.sql(&self.query).await?;
+        export(df, &self.path, &self.options).await
+    }
+}
+
+/// Parses `sql` as a `COPY` statement, `None` when it is another statement
+pub fn parse_copy(sql: &str) -> Option<Result<CopyStatement>> {
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+    let rest = strip_keyword(sql, "COPY")?;
+    Some(parse_copy_body(rest))
+}
+
+fn parse_copy_body(rest: &str) -> Result<CopyStatement> {
+    let syntax = || DataFusionError::Plan("expected COPY (<query>) TO '<path>' [(<option> <value>, ...)]".to_owned());
+
+    let (query, rest) = parenthesized(rest.trim_start()).ok_or_else(syntax)?;
+    let rest = strip_keyword(rest.trim_start(), "TO").ok_or_else(syntax)?;
+    let (path, rest) = quoted(rest.trim_start()).ok_or_else(syntax)?;
+    let rest = rest.trim();
+    let options = if rest.is_empty() {
+        vec![]
+    } else {
+        match parenthesized(rest) {
+            Some((options, tail)) if tail.trim().is_empty() => parse_options(options)?,
+            _ => return Err(syntax()),
+        }
+    };
+
+    let mut format = None;
+    let mut partition_by = vec![];
+    let mut parquet_options = vec![];
+    for (option, value) in options {
+        match option.to_ascii_lowercase().as_str() {
+            "format" => format = Some(value.to_ascii_lowercase()),
+            "partition_by" => {
+                partition_by = value
+                    .split(',')
+                    .map(|column| column.trim().to_owned())
+                    .filter(|column| !column.is_empty())
+                    .collect()
+            }
+            _ => parquet_options.push((option, value)),
+        }
+    }
+    let format = format.unwrap_or_else(|| match Path::new(&path).extension().and_then(|ext| ext.to_str()) {
+        Some("csv") => "csv".to_owned(),
+        Some("json") | Some("ndjson") => "ndjson".to_owned(),
+        _ => "parquet".to_owned(),
+    });
+    if format != "parquet" && !parquet_options.is_empty() {
+        return Err(DataFusionError::Plan(format!(
+            "option '{}' is only valid for Parquet",
+            parquet_options[0].0
+        )));
+    }
+    let format = match format.as_str() {
+        "parquet" => ExportFormat::Parquet(writer_properties(&parquet_options)?),
+        "csv" => ExportFormat::Csv,
+        "json" | "ndjson" => ExportFormat::Ndjson,
+        _ => return Err(invalid_option("format", &format)),
+    };
+
+    Ok(CopyStatement {
+        query: query.to_owned(),
+        path: PathBuf::from(path),
+        options: ExportOptions { format, partition_by },
+    })
+}
+
+/// `text` without the leading case-insensitive `keyword`, which must end at a word boundary
+fn strip_keyword<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    let head = text.get(..keyword.len())?;
+    let rest = &text[keyword.len()..];
+    let boundary = rest.starts_with(|c: char| c.is_whitespace() || c == '(' || c == '\'');
+    if head.eq_ignore_ascii_case(keyword) && boundary {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+/// The contents of the parentheses `text` starts with and the text after them, skipping
+/// parentheses inside quotes
+fn parenthesized(text: &str) -> Option<(&str, &str)> {
+    if !text.starts_with('(') {
+        return None;
+    }
+    let mut depth = 0;
+    let mut quote = None;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((&text[1..index], &text[index + 1..]));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The single quoted string `text` starts with, `''` being an escaped quote
+fn quoted(text: &str) -> Option<(String, &str)> {
+    let mut chars = text.char_indices().peekable();
+    if chars.next()?.1 != '\'' {
+        return None;
+    }
+    let mut value = String::new();
+    while let Some((index, c)) = chars.next() {
+        if c == '\'' {
+            if let Some((_, '\'')) = chars.peek() {
+                chars.next();
+                value.push('\'');
+            } else {
+                return Some((value, &text[index + 1..]));
+            }
+        } else {
+            value.push(c);
+        }
+    }
+    None
+}
+
+/// `<option> <value>` pairs separated by commas outside of quotes
+fn parse_options(text: &str) -> Result<Vec<(String, String)>> {
+    let mut options = vec![];
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let end = rest.find(char::is_whitespace).ok_or_else(|| {
+            DataFusionError::Plan(format!("missing value for COPY option '{}'", rest))
+        })?;
+        let option = rest[..end].to_owned();
+        rest = rest[end..].trim_start();
+
+        let value = if rest.starts_with('\'') {
+            let (value, tail) = quoted(rest)
+                .ok_or_else(|| DataFusionError::Plan(format!("unterminated value for option '{}'", option)))?;
+            rest = tail;
+            value
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let value = rest[..end].trim().to_owned();
+            rest = &rest[end..];
+            value
+        };
+        options.push((option, value));
+
+        rest = rest.trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+    Ok(options)
+}
@@ -0,0 +1,773 @@
+//! An Arrow Flight SQL service over the tables of an [`ExecutionContext`].
+//!
+//! Commands are handled through `GetFlightInfo`, `GetSchema` and `DoGet`:
+//!
+//! * `CommandGetTables`, with catalog, `LIKE` patterns and table type filters
+//! * `CommandStatementQuery`
+//! * `CommandPreparedStatementQuery`, for statements created with the
+//!   `CreatePreparedStatement` action and released with `ClosePreparedStatement`.
+//!   Prepared statements take no parameters.
+//!
+//! Results are streamed batch by batch as the plan produces them, without collecting them
+//! first. Served through [`FlightSqlServer`], `DoGet` streams zero-copy: only the IPC header
+//! of a batch is encoded, its body is made of the array buffers themselves, which are handed
+//! to the transport as they are. Batches with dictionaries or arrays sliced at an offset are
+//! encoded by `flight_data_from_arrow_batch` instead, which copies them. At most
+//! [`MAX_PREPARED_STATEMENTS`] prepared statements are open, creating another one closes
+//! the oldest. Statements that would register tables are rejected.
+//!
+//! arrow-flight 6 predates the Flight SQL definitions, so the messages are declared in
+//! [`protocol`], following `FlightSql.proto`.
+
+// the service reports every error as a tonic `Status`, as large as it is
+#![allow(clippy::result_large_err)]
+
+use crate::DEFAULT_CATALOG;
+use arrow_flight::flight_descriptor::DescriptorType;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::utils::flight_data_from_arrow_batch;
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, IpcMessage, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+};
+use bytes::Bytes;
+use datafusion::arrow::array::{ArrayData, ArrayRef, BinaryArray, StringArray};
+use datafusion::arrow::buffer::{Buffer, MutableBuffer};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::error::Result as ArrowResult;
+use datafusion::arrow::ipc;
+use datafusion::arrow::ipc::writer::IpcWriteOptions;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::bit_util;
+use datafusion::datasource::datasource::TableType;
+use datafusion::error::DataFusionError;
+use datafusion::logical_plan::LogicalPlan;
+use datafusion::physical_plan::{execute_stream, ExecutionPlan};
+use datafusion::prelude::*;
+use flatbuffers::FlatBufferBuilder;
+use futures::future::BoxFuture;
+use futures::{stream, Stream, StreamExt};
+use hyper::body::HttpBody;
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::service::Service;
+use hyper::{Body, HeaderMap};
+use prost::encoding::{self, WireType};
+use prost::Message;
+use protocol::*;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codegen::Never;
+use tonic::transport::NamedService;
+use tonic::{Code, Request, Response, Status, Streaming};
+
+pub const CREATE_PREPARED_STATEMENT: &str = "CreatePreparedStatement";
+pub const CLOSE_PREPARED_STATEMENT: &str = "ClosePreparedStatement";
+/// Prepared statements open at once, see [`FlightSqlService`]
+pub const MAX_PREPARED_STATEMENTS: usize = 256;
+
+pub mod protocol {
+    //! The messages of `FlightSql.proto` that the service handles, and their packing into
+    //! `google.protobuf.Any` as Flight SQL sends them
+
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct CommandGetTables {
+        #[prost(string, optional, tag = "1")]
+        pub catalog: Option<String>,
+        #[prost(string, optional, tag = "2")]
+        pub db_schema_filter_pattern: Option<String>,
+        #[prost(string, optional, tag = "3")]
+        pub table_name_filter_pattern: Option<String>,
+        #[prost(string, repeated, tag = "4")]
+        pub table_types: Vec<String>,
+        #[prost(bool, tag = "5")]
+        pub include_schema: bool,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct CommandStatementQuery {
+        #[prost(string, tag = "1")]
+        pub query: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct TicketStatementQuery {
+        #[prost(bytes = "vec", tag = "1")]
+        pub statement_handle: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct ActionCreatePreparedStatementRequest {
+        #[prost(string, tag = "1")]
+        pub query: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct ActionCreatePreparedStatementResult {
+        #[prost(bytes = "vec", tag = "1")]
+        pub prepared_statement_handle: Vec<u8>,
+        /// IPC encapsulated schema of the results
+        #[prost(bytes = "vec", tag = "2")]
+        pub dataset_schema: Vec<u8>,
+        /// Empty, parameters are not supported
+        #[prost(bytes = "vec", tag = "3")]
+        pub parameter_schema: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct ActionClosePreparedStatementRequest {
+        #[prost(bytes = "vec", tag = "1")]
+        pub prepared_statement_handle: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct CommandPreparedStatementQuery {
+        #[prost(bytes = "vec", tag = "1")]
+        pub prepared_statement_handle: Vec<u8>,
+    }
+
+    /// A Flight SQL message, named as in the protocol
+    pub trait SqlMessage: Message + Default {
+        const NAME: &'static str;
+
+        fn type_url() -> String {
+            format!("type.googleapis.com/arrow.flight.protocol.sql.{}", Self::NAME)
+        }
+
+        /// The message wrapped in `google.protobuf.Any`, as the command of a descriptor,
+        /// a ticket or the body of an action
+        fn pack(&self) -> Vec<u8> {
+            prost_types::Any {
+                type_url: Self::type_url(),
+                value: self.encode_to_vec(),
+            }
+            .encode_to_vec()
+        }
+
+        /// The message in `bytes`, `None` when they hold another message
+        fn unpack(bytes: &[u8]) -> Option<Self> {
+            let any = prost_types::Any::decode(bytes).ok()?;
+            if any.type_url != Self::type_url() {
+                return None;
+            }
+            Self::decode(any.value.as_slice()).ok()
+        }
+    }
+
+    impl SqlMessage for CommandGetTables {
+        const NAME: &'static str = "CommandGetTables";
+    }
+
+    impl SqlMessage for CommandStatementQuery {
+        const NAME: &'static str = "CommandStatementQuery";
+    }
+
+    impl SqlMessage for TicketStatementQuery {
+        const NAME: &'static str = "TicketStatementQuery";
+    }
+
+    impl SqlMessage for ActionCreatePreparedStatementRequest {
+        const NAME: &'static str = "ActionCreatePreparedStatementRequest";
+    }
+
+    impl SqlMessage for ActionCreatePreparedStatementResult {
+        const NAME: &'static str = "ActionCreatePreparedStatementResult";
+    }
+
+    impl SqlMessage for ActionClosePreparedStatementRequest {
+        const NAME: &'static str = "ActionClosePreparedStatementRequest";
+    }
+
+    impl SqlMessage for CommandPreparedStatementQuery {
+        const NAME: &'static str = "CommandPreparedStatementQuery";
+    }
+}
+
+/// A command of a descriptor or ticket
+enum Command {
+    GetTables(CommandGetTables),
+    StatementQuery(CommandStatementQuery),
+    TicketStatementQuery(TicketStatementQuery),
+    PreparedStatementQuery(CommandPreparedStatementQuery),
+}
+
+impl Command {
+    fn unpack(bytes: &[u8]) -> Result<Self, Status> {
+        CommandGetTables::unpack(bytes)
+            .map(Command::GetTables)
+            .or_else(|| CommandStatementQuery::unpack(bytes).map(Command::StatementQuery))
+            .or_else(|| TicketStatementQuery::unpack(bytes).map(Command::TicketStatementQuery))
+            .or_else(|| CommandPreparedStatementQuery::unpack(bytes).map(Command::PreparedStatementQuery))
+            .ok_or_else(|| Status::unimplemented("unsupported Flight SQL command"))
+    }
+}
+
+type BoxedStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send + Sync + 'static>>;
+type BatchStream = Pin<Box<dyn Stream<Item = ArrowResult<RecordBatch>> + Send + Sync + 'static>>;
+
+#[derive(Clone)]
+pub struct FlightSqlService {
+    ctx: ExecutionContext,
+    /// The query of each open prepared statement, by handle. Handles are big-endian
+    /// counters, so the first entry is the oldest statement.
+    prepared: Arc<Mutex<BTreeMap<Vec<u8>, String>>>,
+    next_handle: Arc<AtomicU64>,
+}
+
+impl FlightSqlService {
+    pub fn new(ctx: ExecutionContext) -> Self {
+        Self {
+            ctx,
+            prepared: Arc::new(Mutex::new(BTreeMap::new())),
+            next_handle: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    async fn plan(&self, sql: &str) -> Result<Arc<dyn ExecutionPlan>, Status> {
+        let plan = self.ctx.create_logical_plan(sql).map_err(invalid)?;
+        if let LogicalPlan::CreateExternalTable { .. } = plan {
+            return Err(Status::invalid_argument("only queries are accepted"));
+        }
+        let plan = self.ctx.optimize(&plan).map_err(invalid)?;
+        self.ctx.create_physical_plan(&plan).await.map_err(invalid)
+    }
+
+    fn prepared_query(&self, handle: &[u8]) -> Result<String, Status> {
+        self.prepared
+            .lock()
+            .unwrap()
+            .get(handle)
+            .cloned()
+            .ok_or_else(|| Status::not_found("no such prepared statement"))
+    }
+
+    /// The query of a statement or prepared statement command
+    fn query(&self, command: &Command) -> Result<Option<String>, Status> {
+        Ok(match command {
+            Command::StatementQuery(command) => Some(command.query.clone()),
+            Command::TicketStatementQuery(ticket) => Some(
+                String::from_utf8(ticket.statement_handle.clone())
+                    .map_err(|_| Status::invalid_argument("invalid statement handle"))?,
+            ),
+            Command::PreparedStatementQuery(command) => Some(self.prepared_query(&command.prepared_statement_handle)?),
+            Command::GetTables(_) => None,
+        })
+    }
+
+    /// The schema of the results of `command`
+    async fn schema(&self, command: &Command) -> Result<SchemaRef, Status> {
+        match (command, self.query(command)?) {
+            (Command::GetTables(command), _) => Ok(Arc::new(tables_schema(command.include_schema))),
+            (_, Some(query)) => Ok(self.plan(&query).await?.schema()),
+            (_, None) => Err(Status::invalid_argument("not a query")),
+        }
+    }
+
+    /// The schema and batches of the results of the command in `ticket`
+    async fn results(&self, ticket: &[u8]) -> Result<(SchemaRef, BatchStream), Status> {
+        let command = Command::unpack(ticket)?;
+        match (&command, self.query(&command)?) {
+            (Command::GetTables(command), _) => {
+                let batch = self.tables(command)?;
+                Ok((batch.schema(), Box::pin(stream::iter(vec![Ok(batch)]))))
+            }
+            (_, Some(query)) => {
+                let plan = self.plan(&query).await?;
+                let batches = execute_stream(plan).await.map_err(internal)?;
+                Ok((batches.schema(), batches))
+            }
+            (_, None) => Err(Status::invalid_argument("not a query")),
+        }
+    }
+
+    /// The response to a `DoGet` request, streaming the results zero-copy, see [`frames`]
+    async fn do_get_frames(self, request: hyper::Request<Body>) -> hyper::Response<BoxBody> {
+        let results = async {
+            let body = hyper::body::to_bytes(request.into_body())
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+            let ticket = Ticket::decode(unframe(&body)?).map_err(|e| Status::invalid_argument(e.to_string()))?;
+            self.results(&ticket.ticket).await
+        };
+        match results.await {
+            Ok((schema, batches)) => {
+                let mut response = hyper::Response::new(
+                    FramesBody {
+                        frames: frames(schema, batches),
+                        status: None,
+                    }
+                    .boxed(),
+                );
+                response
+                    .headers_mut()
+                    .insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+                response
+            }
+            Err(status) => status.to_http(),
+        }
+    }
+
+    /// One row per table of the default catalog that `command` selects
+    fn tables(&self, command: &CommandGetTables) -> Result<RecordBatch, Status> {
+        let mut rows: Vec<(String, String, &'static str, SchemaRef)> = vec![];
+        let catalog = match command.catalog.as_deref() {
+            Some(catalog) if catalog != DEFAULT_CATALOG => None,
+            _ => self.ctx.catalog(DEFAULT_CATALOG),
+        };
+        if let Some(catalog) = catalog {
+            for schema_name in catalog.schema_names() {
+                if !command.db_schema_filter_pattern.as_deref().is_none_or(|p| like(p, &schema_name)) {
+                    continue;
+                }
+                let schema = match catalog.schema(&schema_name) {
+                    Some(schema) => schema,
+                    None => continue,
+                };
+                for table_name in schema.table_names() {
+                    if !command.table_name_filter_pattern.as_deref().is_none_or(|p| like(p, &table_name)) {
+                        continue;
+                    }
+                    let table = match schema.table(&table_name) {
+                        Some(table) => table,
+                        None => continue,
+                    };
+                    let table_type = match table.table_type() {
+                        TableType::Base => "TABLE",
+                        TableType::View => "VIEW",
+                        TableType::Temporary => "LOCAL TEMPORARY",
+                    };
+                    if !command.table_types.is_empty() && !command.table_types.iter().any(|t| t == table_type) {
+                        continue;
+                    }
+                    rows.push((schema_name.clone(), table_name, table_type, table.schema()));
+                }
+            }
+        }
+        rows.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec![Some(DEFAULT_CATALOG); rows.len()])),
+            Arc::new(StringArray::from(rows.iter().map(|row| Some(row.0.as_str())).collect::<Vec<_>>())),
+            Arc::new(StringArray::from(rows.iter().map(|row| row.1.as_str()).collect::<Vec<_>>())),
+            Arc::new(StringArray::from(rows.iter().map(|row| row.2).collect::<Vec<_>>())),
+        ];
+        if command.include_schema {
+            let schemas = rows
+                .iter()
+                .map(|row| ipc_schema(&row.3))
+                .collect::<Result<Vec<_>, Status>>()?;
+            columns.push(Arc::new(BinaryArray::from(
+                schemas.iter().map(|schema| schema.as_slice()).collect::<Vec<_>>(),
+            )));
+        }
+        RecordBatch::try_new(Arc::new(tables_schema(command.include_schema)), columns)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+#[tonic::async_trait]
+impl FlightService for FlightSqlService {
+    type HandshakeStream = BoxedStream<HandshakeResponse>;
+    type ListFlightsStream = BoxedStream<FlightInfo>;
+    type DoGetStream = BoxedStream<FlightData>;
+    type DoPutStream = BoxedStream<PutResult>;
+    type DoActionStream = BoxedStream<arrow_flight::Result>;
+    type ListActionsStream = BoxedStream<ActionType>;
+    type DoExchangeStream = BoxedStream<FlightData>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("handshake"))
+    }
+
+    async fn list_flights(&self, _request: Request<Criteria>) -> Result<Response<Self::ListFlightsStream>, Status> {
+        Err(Status::unimplemented("list_flights"))
+    }
+
+    async fn get_flight_info(&self, request: Request<FlightDescriptor>) -> Result<Response<FlightInfo>, Status> {
+        let descriptor = request.into_inner();
+        if descriptor.r#type != DescriptorType::Cmd as i32 {
+            return Err(Status::invalid_argument("expected a command descriptor"));
+        }
+        let command = Command::unpack(&descriptor.cmd)?;
+        let schema = self.schema(&command).await?;
+        let ticket = match command {
+            Command::StatementQuery(command) => TicketStatementQuery {
+                statement_handle: command.query.into_bytes(),
+            }
+            .pack(),
+            Command::GetTables(_) | Command::PreparedStatementQuery(_) => descriptor.cmd.clone(),
+            Command::TicketStatementQuery(_) => return Err(Status::invalid_argument("a ticket is not a command")),
+        };
+        let endpoint = FlightEndpoint {
+            ticket: Some(Ticket { ticket }),
+            location: vec![],
+        };
+        let message = IpcMessage::try_from(SchemaAsIpc::new(&schema, &IpcWriteOptions::default()))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(FlightInfo::new(message, Some(descriptor), vec![endpoint], -1, -1)))
+    }
+
+    async fn get_schema(&self, request: Request<FlightDescriptor>) -> Result<Response<SchemaResult>, Status> {
+        let command = Command::unpack(&request.into_inner().cmd)?;
+        let schema = self.schema(&command).await?;
+        Ok(Response::new(SchemaResult::from(SchemaAsIpc::new(
+            &schema,
+            &IpcWriteOptions::default(),
+        ))))
+    }
+
+    /// Copies every batch into its message, unlike the `DoGet` of [`FlightSqlServer`]
+    async fn do_get(&self, request: Request<Ticket>) -> Result<Response<Self::DoGetStream>, Status> {
+        let (schema, batches) = self.results(&request.into_inner().ticket).await?;
+        Ok(Response::new(flight_data_stream(schema, batches)))
+    }
+
+    async fn do_put(&self, _request: Request<Streaming<FlightData>>) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("do_put, prepared statements take no parameters"))
+    }
+
+    async fn do_action(&self, request: Request<Action>) -> Result<Response<Self::DoActionStream>, Status> {
+        let action = request.into_inner();
+        match action.r#type.as_str() {
+            CREATE_PREPARED_STATEMENT => {
+                let request = ActionCreatePreparedStatementRequest::unpack(&action.body)
+                    .ok_or_else(|| Status::invalid_argument("expected ActionCreatePreparedStatementRequest"))?;
+                let schema = self.plan(&request.query).await?.schema();
+                let handle = self.next_handle.fetch_add(1, Ordering::SeqCst).to_be_bytes().to_vec();
+                let mut prepared = self.prepared.lock().unwrap();
+                while prepared.len() >= MAX_PREPARED_STATEMENTS {
+                    let oldest = prepared.keys().next().cloned().expect("prepared statements are open");
+                    prepared.remove(&oldest);
+                }
+                prepared.insert(handle.clone(), request.query);
+                drop(prepared);
+                let result = ActionCreatePreparedStatementResult {
+                    prepared_statement_handle: handle,
+                    dataset_schema: ipc_schema(&schema)?,
+                    parameter_schema: vec![],
+                };
+                let body = result.pack();
+                let results: Vec<Result<arrow_flight::Result, Status>> = vec![Ok(arrow_flight::Result { body })];
+                Ok(Response::new(Box::pin(stream::iter(results)) as Self::DoActionStream))
+            }
+            CLOSE_PREPARED_STATEMENT => {
+                let request = ActionClosePreparedStatementRequest::unpack(&action.body)
+                    .ok_or_else(|| Status::invalid_argument("expected ActionClosePreparedStatementRequest"))?;
+                self.prepared
+                    .lock()
+                    .unwrap()
+                    .remove(&request.prepared_statement_handle)
+                    .ok_or_else(|| Status::not_found("no such prepared statement"))?;
+                let results: Vec<Result<arrow_flight::Result, Status>> = vec![];
+                Ok(Response::new(Box::pin(stream::iter(results)) as Self::DoActionStream))
+            }
+            other => Err(Status::unimplemented(format!("action '{}'", other))),
+        }
+    }
+
+    async fn list_actions(&self, _request: Request<Empty>) -> Result<Response<Self::ListActionsStream>, Status> {
+        let actions: Vec<Result<ActionType, Status>> = vec![
+            Ok(ActionType {
+                r#type: CREATE_PREPARED_STATEMENT.to_owned(),
+                description: "Creates a prepared statement without parameters".to_owned(),
+            }),
+            Ok(ActionType {
+                r#type: CLOSE_PREPARED_STATEMENT.to_owned(),
+                description: "Closes a prepared statement".to_owned(),
+            }),
+        ];
+        Ok(Response::new(Box::pin(stream::iter(actions)) as Self::ListActionsStream))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("do_exchange"))
+    }
+}
+
+/// [`FlightSqlService`] as a gRPC service that answers `DoGet` with [`frames`], and every
+/// other method as the generated `FlightServiceServer`
+#[derive(Clone)]
+pub struct FlightSqlServer {
+    service: FlightSqlService,
+    inner: FlightServiceServer<FlightSqlService>,
+}
+
+impl FlightSqlServer {
+    pub fn new(service: FlightSqlService) -> Self {
+        Self {
+            service: service.clone(),
+            inner: FlightServiceServer::new(service),
+        }
+    }
+}
+
+impl NamedService for FlightSqlServer {
+    const NAME: &'static str = <FlightServiceServer<FlightSqlService> as NamedService>::NAME;
+}
+
+impl Service<hyper::Request<Body>> for FlightSqlServer {
+    type Response = hyper::Response<BoxBody>;
+    type Error = Never;
+    type Future = BoxFuture<'static, Result<Self::Response, Never>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Never>> {
+        Service::<hyper::Request<Body>>::poll_ready(&mut self.inner, cx)
+    }
+
+    fn call(&mut self, request: hyper::Request<Body>) -> Self::Future {
+        if request.uri().path() == "/arrow.flight.protocol.FlightService/DoGet" {
+            let service = self.service.clone();
+            Box::pin(async move { Ok(service.do_get_frames(request).await) })
+        } else {
+            self.inner.call(request)
+        }
+    }
+}
+
+/// The message of a gRPC request frame, compression is not supported
+fn unframe(frame: &[u8]) -> Result<&[u8], Status> {
+    match frame {
+        [0, length @ ..] if length.len() >= 4 => {
+            let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+            frame
+                .get(5..5 + length)
+                .ok_or_else(|| Status::invalid_argument("truncated request"))
+        }
+        _ => Err(Status::invalid_argument("expected an uncompressed request")),
+    }
+}
+
+/// The gRPC frame of `message`, encoded into one chunk
+fn frame(message: &FlightData) -> Bytes {
+    let mut chunk = Vec::with_capacity(5 + message.encoded_len());
+    chunk.push(0);
+    chunk.extend_from_slice(&(message.encoded_len() as u32).to_be_bytes());
+    message.encode(&mut chunk).expect("a Vec grows as needed");
+    Bytes::from(chunk)
+}
+
+/// The schema message followed by the messages of every batch as gRPC frames, see
+/// [`batch_frame`]
+fn frames(schema: SchemaRef, batches: BatchStream) -> BoxedStream<Bytes> {
+    let options = IpcWriteOptions::default();
+    let schema = frame(&FlightData::from(SchemaAsIpc::new(&schema, &options)));
+    let data = batches.flat_map(move |batch| {
+        let chunks: Vec<Result<Bytes, Status>> = match batch {
+            Ok(batch) => match batch_frame(&batch) {
+                Some(chunks) => chunks.into_iter().map(Ok).collect(),
+                None => {
+                    let (dictionaries, data) = flight_data_from_arrow_batch(&batch, &options);
+                    dictionaries.iter().chain(std::iter::once(&data)).map(|message| Ok(frame(message))).collect()
+                }
+            },
+            Err(e) => vec![Err(Status::internal(e.to_string()))],
+        };
+        stream::iter(chunks)
+    });
+    Box::pin(stream::iter(vec![Ok(schema)]).chain(data))
+}
+
+/// An Arrow buffer as the owner of the memory of a [`Bytes`]
+struct ArrowBuffer(Buffer);
+
+impl AsRef<[u8]> for ArrowBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+const PADDING: [u8; 8] = [0; 8];
+
+/// The gRPC frame of the `FlightData` message of `batch` as chunks: the frame and message
+/// headers, then each buffer of the arrays, padded to 8 bytes as in `arrow::ipc::writer`.
+/// `None` for batches with dictionaries or arrays at an offset, which the IPC writer handles.
+fn batch_frame(batch: &RecordBatch) -> Option<Vec<Bytes>> {
+    let mut nodes = vec![];
+    let mut buffers = vec![];
+    let mut body = vec![];
+    for column in batch.columns() {
+        add_array_data(column.data(), &mut nodes, &mut buffers, &mut body)?;
+    }
+    let body_length: usize = body.iter().map(Bytes::len).sum();
+
+    let mut fbb = FlatBufferBuilder::new();
+    let buffers = fbb.create_vector(&buffers);
+    let nodes = fbb.create_vector(&nodes);
+    let header = {
+        let mut builder = ipc::RecordBatchBuilder::new(&mut fbb);
+        builder.add_length(batch.num_rows() as i64);
+        builder.add_nodes(nodes);
+        builder.add_buffers(buffers);
+        builder.finish().as_union_value()
+    };
+    let mut message = ipc::MessageBuilder::new(&mut fbb);
+    message.add_version(ipc::MetadataVersion::V5);
+    message.add_header_type(ipc::MessageHeader::RecordBatch);
+    message.add_bodyLength(body_length as i64);
+    message.add_header(header);
+    let message = message.finish();
+    fbb.finish(message, None);
+
+    // the fields of `FlightData` that are set: `data_header` (2) and `data_body` (1000)
+    let mut fields = vec![];
+    encoding::bytes::encode(2, &fbb.finished_data().to_vec(), &mut fields);
+    encoding::encode_key(1000, WireType::LengthDelimited, &mut fields);
+    encoding::encode_varint(body_length as u64, &mut fields);
+    let mut head = Vec::with_capacity(5 + fields.len());
+    head.push(0);
+    head.extend_from_slice(&((fields.len() + body_length) as u32).to_be_bytes());
+    head.extend_from_slice(&fields);
+
+    Some(std::iter::once(Bytes::from(head)).chain(body).collect())
+}
+
+/// Adds the field node and buffers of `data` and its children, `None` when the array is a
+/// slice or has dictionaries
+fn add_array_data(
+    data: &ArrayData,
+    nodes: &mut Vec<ipc::FieldNode>,
+    buffers: &mut Vec<ipc::Buffer>,
+    body: &mut Vec<Bytes>,
+) -> Option<()> {
+    if data.offset() != 0 || matches!(data.data_type(), DataType::Dictionary(_, _)) {
+        return None;
+    }
+    nodes.push(ipc::FieldNode::new(data.len() as i64, data.null_count() as i64));
+    if data.data_type() != &DataType::Null {
+        // arrays without nulls get a validity buffer of set bits, as by the IPC writer
+        let validity = data.null_buffer().cloned().unwrap_or_else(|| {
+            let length = bit_util::ceil(data.len(), 8);
+            MutableBuffer::new(length).with_bitset(length, true).into()
+        });
+        add_buffer(validity, buffers, body);
+    }
+    for buffer in data.buffers() {
+        add_buffer(buffer.clone(), buffers, body);
+    }
+    for child in data.child_data() {
+        add_array_data(child, nodes, buffers, body)?;
+    }
+    Some(())
+}
+
+fn add_buffer(buffer: Buffer, buffers: &mut Vec<ipc::Buffer>, body: &mut Vec<Bytes>) {
+    let offset: usize = body.iter().map(Bytes::len).sum();
+    let padding = (8 - buffer.len() % 8) % 8;
+    buffers.push(ipc::Buffer::new(offset as i64, (buffer.len() + padding) as i64));
+    body.push(Bytes::from_owner(ArrowBuffer(buffer)));
+    if padding > 0 {
+        body.push(Bytes::from_static(&PADDING[..padding]));
+    }
+}
+
+/// A `DoGet` response body of gRPC frames, ending with the status of the stream as trailers
+struct FramesBody {
+    frames: BoxedStream<Bytes>,
+    /// Set once the frames end
+    status: Option<Status>,
+}
+
+impl HttpBody for FramesBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Status>>> {
+        if self.status.is_some() {
+            return Poll::Ready(None);
+        }
+        match self.frames.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(chunk))) => Poll::Ready(Some(Ok(chunk))),
+            Poll::Ready(Some(Err(status))) => {
+                self.status = Some(status);
+                Poll::Ready(None)
+            }
+            Poll::Ready(None) => {
+                self.status = Some(Status::new(Code::Ok, ""));
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn poll_trailers(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<Option<HeaderMap>, Status>> {
+        let status = self.status.take().unwrap_or_else(|| Status::new(Code::Ok, ""));
+        // the status headers, as tonic writes them
+        let mut trailers = status.to_http().into_parts().0.headers;
+        trailers.remove(CONTENT_TYPE);
+        Poll::Ready(Ok(Some(trailers)))
+    }
+}
+
+/// The schema message followed by the dictionaries and data of every batch, each copied
+/// into its message as the batch arrives
+fn flight_data_stream(schema: SchemaRef, batches: BatchStream) -> BoxedStream<FlightData> {
+    let options = IpcWriteOptions::default();
+    let schema = FlightData::from(SchemaAsIpc::new(&schema, &options));
+    let data = batches.flat_map(move |batch| {
+        let messages: Vec<Result<FlightData, Status>> = match batch {
+            Ok(batch) => {
+                let (dictionaries, data) = flight_data_from_arrow_batch(&batch, &options);
+                dictionaries.into_iter().chain(std::iter::once(data)).map(Ok).collect()
+            }
+            Err(e) => vec![Err(Status::internal(e.to_string()))],
+        };
+        stream::iter(messages)
+    });
+    Box::pin(stream::iter(vec![Ok(schema)]).chain(data))
+}
+
+/// The results of `CommandGetTables`
+fn tables_schema(include_schema: bool) -> Schema {
+    let mut fields = vec![
+        Field::new("catalog_name", DataType::Utf8, true),
+        Field::new("db_schema_name", DataType::Utf8, true),
+        Field::new("table_name", DataType::Utf8, false),
+        Field::new("table_type", DataType::Utf8, false),
+    ];
+    if include_schema {
+        fields.push(Field::new("table_schema", DataType::Binary, false));
+    }
+    Schema::new(fields)
+}
+
+/// The IPC encapsulated schema message
+fn ipc_schema(schema: &Schema) -> Result<Vec<u8>, Status> {
+    IpcMessage::try_from(SchemaAsIpc::new(schema, &IpcWriteOptions::default()))
+        .map(|message| message.0)
+        .map_err(|e| Status::internal(e.to_string()))
+}
+
+/// SQL `LIKE` matching with `%` and `_`
+fn like(pattern: &str, value: &str) -> bool {
+    fn matches(pattern: &[char], value: &[char]) -> bool {
+        match pattern.split_first() {
+            None => value.is_empty(),
+            Some(('%', rest)) => (0..=value.len()).any(|start| matches(rest, &value[start..])),
+            Some(('_', rest)) => !value.is_empty() && matches(rest, &value[1..]),
+            Some((c, rest)) => value.first() == Some(c) && matches(rest, &value[1..]),
+        }
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+    matches(&pattern, &value)
+}
+
+fn invalid(e: DataFusionError) -> Status {
+    Status::invalid_argument(e.to_string())
+}
+
+fn internal(e: DataFusionError) -> Status {
+    Status::internal(e.to_string())
+}
@@ -0,0 +1,355 @@
+//! Hive-style partitioned tables, whose `<key>=<value>` directories become typed columns.
+//!
+//! Partition columns are typed per key: `Int64` when every value is an integer, `Date32`
+//! when every value is a `YYYY-MM-DD` date and `Utf8` otherwise, so that values such as
+//! `007` that a type would rewrite keep their directory spelling.
+//! `__HIVE_DEFAULT_PARTITION__` stands for null and `%<hex>` escapes are decoded. Filters
+//! that only reference partition columns are evaluated against the partition values before
+//! the scan is planned, so the files of pruned directories are never opened. A single file,
+//! or a directory whose subdirectories are not all `<key>=<value>` directories, is one
+//! unpartitioned table.
+
+use crate::export::{unescape_partition_value, NULL_PARTITION};
+use async_trait::async_trait;
+use datafusion::arrow::array::{Array, ArrayRef, StringArray, UInt64Array};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::util::display::array_value_to_string;
+use datafusion::datasource::datasource::{TableProvider, TableProviderFilterPushDown};
+use datafusion::datasource::listing::{ListingOptions, ListingTable};
+use datafusion::datasource::object_store::ObjectStore;
+use datafusion::datasource::MemTable;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_plan::{Column, Expr, ExprRewriter};
+use datafusion::optimizer::utils::expr_to_columns;
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::expressions::{self, Literal};
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::union::UnionExec;
+use datafusion::physical_plan::{ExecutionPlan, PhysicalExpr};
+use datafusion::prelude::*;
+use datafusion::scalar::ScalarValue;
+use futures::StreamExt;
+use std::any::Any;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+const PARTITION_INDEX: &str = "__partition_index";
+
+/// A directory with the values of its partition columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct HivePartition {
+    pub path: String,
+    pub values: Vec<ScalarValue>,
+}
+
+pub struct HiveTable {
+    object_store: Arc<dyn ObjectStore>,
+    root: String,
+    options: ListingOptions,
+    file_schema: SchemaRef,
+    partition_fields: Vec<Field>,
+    partitions: Vec<HivePartition>,
+    schema: SchemaRef,
+}
+
+impl HiveTable {
+    /// Discovers the partitions below `root` and, unless `file_schema` is given, infers the
+    /// schema of the files. The partition columns of `options` are ignored.
+    pub async fn try_new(
+        object_store: Arc<dyn ObjectStore>,
+        root: &str,
+        options: ListingOptions,
+        file_schema: Option<SchemaRef>,
+    ) -> Result<Self> {
+        let root = root.trim_end_matches('/');
+        let directories = discover(object_store.as_ref(), root, &options.file_extension).await?;
+
+        let keys: Vec<String> = directories
+            .values()
+            .next()
+            .map(|pairs| pairs.iter().map(|(key, _)| key.clone()).collect())
+            .unwrap_or_default();
+        for (path, pairs) in &directories {
+            if !pairs.iter().map(|(key, _)| key).eq(keys.iter()) {
+                return Err(DataFusionError::Plan(format!(
+                    "inconsistent partition directories: expected {} in '{}'",
+                    keys.join("/"),
+                    path
+                )));
+            }
+        }
+
+        let mut partition_fields = vec![];
+        let mut columns = vec![];
+        for (index, key) in keys.iter().enumerate() {
+            let values: StringArray = directories
+                .values()
+                .map(|pairs| Some(pairs[index].1.as_str()).filter(|value| *value != NULL_PARTITION))
+                .collect();
+            let (data_type, typed) = infer_partition_type(values)?;
+            partition_fields.push(Field::new(key, data_type, typed.null_count() > 0));
+            columns.push(typed);
+        }
+        let partitions = directories
+            .keys()
+            .enumerate()
+            .map(|(row, path)| {
+                let values = columns
+                    .iter()
+                    .map(|column| ScalarValue::try_from_array(column, row))
+                    .collect::<Result<_>>()?;
+                Ok(HivePartition {
+                    path: path.clone(),
+                    values,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let file_schema = match file_schema {
+            Some(schema) => schema,
+            None => options.infer_schema(object_store.clone(), root).await?,
+        };
+        let mut fields = file_schema.fields().clone();
+        fields.extend(partition_fields.iter().cloned());
+
+        Ok(Self {
+            object_store,
+            root: root.to_owned(),
+            options,
+            file_schema,
+            partition_fields,
+            partitions,
+            schema: Arc::new(Schema::new(fields)),
+        })
+    }
+
+    /// The directory or file the table was discovered at, without a trailing `/`
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    pub fn partition_fields(&self) -> &[Field] {
+        &self.partition_fields
+    }
+
+    pub fn partitions(&self) -> &[HivePartition] {
+        &self.partitions
+    }
+
+    /// The listing options and the schema of the files, e.g. to register an unpartitioned
+    /// directory as a plain listing table
+    pub fn into_parts(self) -> (ListingOptions, SchemaRef) {
+        (self.options, self.file_schema)
+    }
+
+    fn is_partition_column(&self, name: &str) -> bool {
+        self.partition_fields.iter().any(|field| field.name() == name)
+    }
+
+    /// Columns that `filter` references, `None` when they cannot be determined
+    fn columns(filter: &Expr) -> Option<HashSet<Column>> {
+        let mut columns = HashSet::new();
+        expr_to_columns(filter, &mut columns).ok()?;
+        Some(columns)
+    }
+
+    /// The partitions that can hold rows matching all `filters`
+    async fn prune(&self, filters: &[Expr]) -> Result<Vec<&HivePartition>> {
+        let applicable: Vec<Expr> = filters
+            .iter()
+            .filter(|filter| {
+                Self::columns(filter).is_some_and(|columns| {
+                    !columns.is_empty() && columns.iter().all(|column| self.is_partition_column(&column.name))
+                })
+            })
+            .map(|filter| filter.clone().rewrite(&mut Unqualify))
+            .collect::<Result<_>>()?;
+        if applicable.is_empty() || self.partitions.is_empty() {
+            return Ok(self.partitions.iter().collect());
+        }
+
+        // one row per partition, holding its values and its index
+        let mut fields = self.partition_fields.clone();
+        fields.push(Field::new(PARTITION_INDEX, DataType::UInt64, false));
+        let mut columns: Vec<ArrayRef> = vec![];
+        for index in 0..self.partition_fields.len() {
+            columns.push(ScalarValue::iter_to_array(
+                self.partitions.iter().map(|partition| partition.values[index].clone()),
+            )?);
+        }
+        columns.push(Arc::new(UInt64Array::from_iter_values(0..self.partitions.len() as u64)));
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+
+        let mut ctx = ExecutionContext::new();
+        let mut df = ctx.read_table(Arc::new(MemTable::try_new(batch.schema(), vec![vec![batch]])?))?;
+        for filter in applicable {
+            df = df.filter(filter)?;
+        }
+        let mut partitions = vec![];
+        for batch in df.select_columns(&[PARTITION_INDEX])?.collect().await? {
+            let indices = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .expect("partition index is UInt64");
+            partitions.extend(indices.values().iter().map(|index| &self.partitions[*index as usize]));
+        }
+        Ok(partitions)
+    }
+}
+
+#[async_trait]
+impl TableProvider for HiveTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let file_columns = self.file_schema.fields().len();
+        let projection = projection
+            .clone()
+            .unwrap_or_else(|| (0..self.schema.fields().len()).collect());
+        let projected_schema = Arc::new(Schema::new(
+            projection.iter().map(|index| self.schema.field(*index).clone()).collect(),
+        ));
+
+        let mut file_projection: Vec<usize> = projection.iter().copied().filter(|index| *index < file_columns).collect();
+        // the files have to be read to count the rows, even for partition columns only
+        if file_projection.is_empty() && file_columns > 0 {
+            file_projection.push(0);
+        }
+        let file_filters: Vec<Expr> = filters
+            .iter()
+            .filter(|filter| {
+                Self::columns(filter)
+                    .is_some_and(|columns| columns.iter().all(|column| !self.is_partition_column(&column.name)))
+            })
+            .cloned()
+            .collect();
+
+        let mut plans: Vec<Arc<dyn ExecutionPlan>> = vec![];
+        for partition in self.prune(filters).await? {
+            let options = ListingOptions {
+                file_extension: self.options.file_extension.clone(),
+                format: self.options.format.clone(),
+                table_partition_cols: vec![],
+                collect_stat: self.options.collect_stat,
+                target_partitions: self.options.target_partitions,
+            };
+            let table = ListingTable::new(
+                self.object_store.clone(),
+                partition.path.clone(),
+                self.file_schema.clone(),
+                options,
+            );
+            let input = table
+                .scan(&Some(file_projection.clone()), batch_size, &file_filters, limit)
+                .await?;
+
+            let exprs = projection
+                .iter()
+                .map(|index| {
+                    let name = self.schema.field(*index).name().clone();
+                    let expr: Arc<dyn PhysicalExpr> = if *index < file_columns {
+                        let position = file_projection
+                            .iter()
+                            .position(|file_index| file_index == index)
+                            .expect("file columns are projected");
+                        Arc::new(expressions::Column::new(&name, position))
+                    } else {
+                        Arc::new(Literal::new(partition.values[index - file_columns].clone()))
+                    };
+                    (expr, name)
+                })
+                .collect();
+            plans.push(Arc::new(ProjectionExec::try_new(exprs, input)?));
+        }
+
+        Ok(match plans.len() {
+            0 => Arc::new(EmptyExec::new(false, projected_schema)),
+            1 => plans.remove(0),
+            _ => Arc::new(UnionExec::new(plans)),
+        })
+    }
+
+    fn supports_filter_pushdown(&self, _filter: &Expr) -> Result<TableProviderFilterPushDown> {
+        // pruning is by directory and row group, the filters still have to be applied
+        Ok(TableProviderFilterPushDown::Inexact)
+    }
+}
+
+/// Directories below `root` that hold files, with their `(key, value)` path segments.
+/// When `root` is a file or a directory below it is not a `<key>=<value>` directory, `root`
+/// is the only directory, without partition values.
+async fn discover(
+    object_store: &dyn ObjectStore,
+    root: &str,
+    extension: &str,
+) -> Result<BTreeMap<String, Vec<(String, String)>>> {
+    let unpartitioned = || BTreeMap::from([(root.to_owned(), vec![])]);
+    let mut directories = BTreeMap::new();
+    let mut files = object_store.list_file_with_suffix(root, extension).await?;
+    while let Some(file) = files.next().await {
+        let file = file?;
+        let directory = match file.path().rsplit_once('/') {
+            Some((directory, _)) if file.path() != root => directory.to_owned(),
+            _ => return Ok(unpartitioned()),
+        };
+        if directories.contains_key(&directory) {
+            continue;
+        }
+        let relative = directory.strip_prefix(root).unwrap_or(&directory).trim_start_matches('/');
+        let mut pairs = vec![];
+        for segment in relative.split('/').filter(|segment| !segment.is_empty()) {
+            match segment.split_once('=') {
+                Some((key, value)) if !key.is_empty() => pairs.push((key.to_owned(), unescape_partition_value(value))),
+                _ => return Ok(unpartitioned()),
+            }
+        }
+        directories.insert(directory, pairs);
+    }
+    Ok(directories)
+}
+
+/// The narrowest of `Int64`, `Date32` and `Utf8` that every non-null value converts to and
+/// back unchanged
+fn infer_partition_type(values: StringArray) -> Result<(DataType, ArrayRef)> {
+    let strings = Arc::new(values) as ArrayRef;
+    let values = strings.as_any().downcast_ref::<StringArray>().expect("partition values are Utf8");
+    for data_type in &[DataType::Int64, DataType::Date32] {
+        // invalid values become null
+        let typed = cast(&strings, data_type)?;
+        let round_trips = (0..values.len()).all(|index| {
+            values.is_null(index)
+                || array_value_to_string(&typed, index).is_ok_and(|value| value == values.value(index))
+        });
+        if typed.null_count() == values.null_count() && round_trips {
+            return Ok((data_type.clone(), typed));
+        }
+    }
+    Ok((DataType::Utf8, strings))
+}
+
+/// Drops the table qualifiers, the partition values are queried as an unnamed table
+struct Unqualify;
+
+impl ExprRewriter for Unqualify {
+    fn mutate(&mut self, expr: Expr) -> Result<Expr> {
+        Ok(match expr {
+            Expr::Column(column) => Expr::Column(Column::from_name(column.name)),
+            expr => expr,
+        })
+    }
+}
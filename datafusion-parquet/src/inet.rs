@@ -0,0 +1,131 @@
+//! Scalar functions over IP addresses stored as strings, e.g. the `ip_address` column of the
+//! `userdata` tables. Values that are not addresses (or networks) are null.
+//!
+//! ```text
+//! inet_parse(' 10.0.0.001 ')                      10.0.0.1, the address in canonical form
+//! cidr_contains('10.0.0.0/8', '10.1.2.3')         true, false for another address family
+//! ip_to_int('1.2.3.4')                            16909060, IPv4 only
+//! is_private_ip('192.168.1.10')                   true
+//! ip_version('::1')                               6
+//! ```
+
+use crate::udf::string_udf;
+use datafusion::arrow::array::{BooleanArray, Int64Array, StringArray};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::physical_plan::functions::Volatility;
+use datafusion::physical_plan::udf::ScalarUDF;
+use datafusion::prelude::*;
+use std::net::{IpAddr, Ipv4Addr};
+
+/// An address without surrounding whitespace, IPv4 octets may have leading zeros
+pub fn parse_ip(ip: &str) -> Option<IpAddr> {
+    let ip = ip.trim();
+    ip.parse().ok().or_else(|| parse_padded_ipv4(ip))
+}
+
+/// A network as `<address>/<prefix length>`, or a single address with the full length
+pub fn parse_cidr(cidr: &str) -> Option<(IpAddr, u32)> {
+    let (address, prefix) = match cidr.trim().split_once('/') {
+        Some((address, prefix)) => (parse_ip(address)?, Some(prefix.trim().parse::<u32>().ok()?)),
+        None => (parse_ip(cidr)?, None),
+    };
+    let bits = if address.is_ipv4() { 32 } else { 128 };
+    match prefix {
+        Some(prefix) if prefix > bits => None,
+        Some(prefix) => Some((address, prefix)),
+        None => Some((address, bits)),
+    }
+}
+
+/// The canonical form of an address or network, e.g. with leading zeros removed from IPv4
+/// octets and IPv6 zero groups compressed
+pub fn inet_parse(inet: &str) -> Option<String> {
+    let inet = inet.trim();
+    if inet.contains('/') {
+        let (address, prefix) = parse_cidr(inet)?;
+        return Some(format!("{}/{}", address, prefix));
+    }
+    parse_ip(inet).map(|address| address.to_string())
+}
+
+/// IPv4 addresses with leading zeros, which the standard library rejects as ambiguous
+fn parse_padded_ipv4(ip: &str) -> Option<IpAddr> {
+    let octets = ip
+        .split('.')
+        .map(|octet| if octet.len() <= 3 { octet.parse::<u8>().ok() } else { None })
+        .collect::<Option<Vec<_>>>()?;
+    match octets.as_slice() {
+        [a, b, c, d] => Some(IpAddr::V4(Ipv4Addr::new(*a, *b, *c, *d))),
+        _ => None,
+    }
+}
+
+/// Whether the address `ip` is in the network `cidr`, false when they are of different
+/// address families
+pub fn cidr_contains(cidr: &str, ip: &str) -> Option<bool> {
+    let (network, prefix) = parse_cidr(cidr)?;
+    let ip = parse_ip(ip)?;
+    let (network, ip, bits) = match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => (u32::from(network) as u128, u32::from(ip) as u128, 32),
+        (IpAddr::V6(network), IpAddr::V6(ip)) => (u128::from(network), u128::from(ip), 128),
+        _ => return Some(false),
+    };
+    if prefix == 0 {
+        return Some(true);
+    }
+    let shift = bits - prefix;
+    Some(network >> shift == ip >> shift)
+}
+
+/// The IPv4 address `ip` as an unsigned 32-bit number, null for IPv6 addresses
+pub fn ip_to_int(ip: &str) -> Option<i64> {
+    match parse_ip(ip)? {
+        IpAddr::V4(ip) => Some(u32::from(ip) as i64),
+        IpAddr::V6(_) => None,
+    }
+}
+
+/// Whether `ip` is in a private range: 10.0.0.0/8, 172.16.0.0/12 and 192.168.0.0/16
+/// (RFC 1918) or fc00::/7 (RFC 4193)
+pub fn is_private_ip(ip: &str) -> Option<bool> {
+    Some(match parse_ip(ip)? {
+        IpAddr::V4(ip) => ip.is_private(),
+        IpAddr::V6(ip) => ip.segments()[0] & 0xfe00 == 0xfc00,
+    })
+}
+
+/// 4 or 6
+pub fn ip_version(ip: &str) -> Option<i64> {
+    Some(match parse_ip(ip)? {
+        IpAddr::V4(_) => 4,
+        IpAddr::V6(_) => 6,
+    })
+}
+
+/// The IP address functions
+pub fn inet_udfs() -> Vec<ScalarUDF> {
+    vec![
+        string_udf::<StringArray, _, _>("inet_parse", 1, DataType::Utf8, Volatility::Immutable, |args| {
+            inet_parse(args[0])
+        }),
+        string_udf::<BooleanArray, _, _>("cidr_contains", 2, DataType::Boolean, Volatility::Immutable, |args| {
+            cidr_contains(args[0], args[1])
+        }),
+        string_udf::<Int64Array, _, _>("ip_to_int", 1, DataType::Int64, Volatility::Immutable, |args| {
+            ip_to_int(args[0])
+        }),
+        string_udf::<BooleanArray, _, _>("is_private_ip", 1, DataType::Boolean, Volatility::Immutable, |args| {
+            is_private_ip(args[0])
+        }),
+        string_udf::<Int64Array, _, _>("ip_version", 1, DataType::Int64, Volatility::Immutable, |args| {
+            ip_version(args[0])
+        }),
+    ]
+}
+
+/// Registers the IP address functions in `ctx`
+pub fn register_udfs(ctx: &mut ExecutionContext) {
+    for udf in inet_udfs() {
+        ctx.register_udf(udf);
+    }
+}
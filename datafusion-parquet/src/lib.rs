@@ -0,0 +1,236 @@
+//! Shared setup of the DataFusion binaries: listing tables over local Parquet files
+//! registered with an optionally stored schema, or declared in a [catalog] file.
+//! Local directories with `<key>=<value>` subdirectories are registered as [hive] tables.
+//! Queries over them can be [analyze]d for how much data they read, personal data can be
+//! masked with the [pii] functions, per role with a masking [policy], and IP addresses
+//! stored as strings are queried with the [inet] functions.
+
+pub mod analyze;
+pub mod catalog;
+pub mod error;
+pub mod export;
+pub mod flight_sql;
+pub mod hive;
+pub mod inet;
+pub mod pii;
+pub mod policy;
+pub mod query_server;
+mod udf;
+
+use datafusion::arrow::datatypes::{Schema, SchemaRef};
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::listing::ListingOptions;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::prelude::*;
+use hive::HiveTable;
+use policy::{apply_policy, Policy, Role};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Where [`ExecutionContext`] registers tables unless configured otherwise
+pub const DEFAULT_CATALOG: &str = "datafusion";
+pub const DEFAULT_SCHEMA: &str = "public";
+
+/// Listing options for a directory of Parquet files, with pruning and statistics enabled
+pub fn parquet_listing_options() -> ListingOptions {
+    let file_format = ParquetFormat::default().with_enable_pruning(true);
+    ListingOptions {
+        file_extension: ".parquet".to_owned(),
+        format: Arc::new(file_format),
+        table_partition_cols: vec![],
+        collect_stat: true,
+        target_partitions: 1,
+    }
+}
+
+/// Registers the Parquet files at `location` (a path or `file://` URL) as table `name`.
+///
+/// An optional stored schema (e.g. written by `schema-validate save`) declares the table
+/// instead of inferring it from the files at query time.
+pub async fn register_parquet(
+    ctx: &mut ExecutionContext,
+    name: &str,
+    location: &str,
+    schema_path: Option<&str>,
+) -> Result<()> {
+    let schema = match schema_path {
+        Some(path) => Some(Arc::new(read_schema(path)?)),
+        None => None,
+    };
+    let uri = if location.contains("://") {
+        location.to_owned()
+    } else {
+        format!("file://{}", location)
+    };
+    register_listing(ctx, name, &uri, parquet_listing_options(), schema).await
+}
+
+/// Registers a listing table, with typed partition columns discovered from the directory
+/// names of local tables that do not declare partition columns.
+pub async fn register_listing(
+    ctx: &mut ExecutionContext,
+    name: &str,
+    uri: &str,
+    options: ListingOptions,
+    schema: Option<SchemaRef>,
+) -> Result<()> {
+    if uri.starts_with("file://") && options.table_partition_cols.is_empty() {
+        // the object store registered for `file`, e.g. a metered one
+        let (object_store, path) = ctx.object_store(uri)?;
+        let table = HiveTable::try_new(object_store, path, options, schema).await?;
+        // unpartitioned directories keep the native listing table
+        if table.partition_fields().is_empty() {
+            let (options, schema) = table.into_parts();
+            return ctx.register_listing_table(name, uri, options, Some(schema)).await;
+        }
+        ctx.register_table(name, Arc::new(table))?;
+        return Ok(());
+    }
+    ctx.register_listing_table(name, uri, options, schema).await
+}
+
+/// Reads an Arrow schema file written by schema-validate, in its text format or as serde JSON
+pub fn read_schema(path: &str) -> Result<Schema> {
+    schema_validate::load(path).map_err(|e| DataFusionError::Plan(format!("invalid schema file '{}': {}", path, e)))
+}
+
+/// A table to register, from `<table>=<location>` and `--schema <table>=<schema file>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableArg {
+    pub name: String,
+    pub location: String,
+    pub schema: Option<String>,
+}
+
+/// The command line shared by the binaries:
+///
+/// ```text
+/// [<table>=<path or url>]... [--schema <table>=<schema file>]... [--catalog <toml file>]
+/// [--policy <toml file> --role <role>]
+/// ```
+///
+/// Without tables or a catalog, `my_table` is registered over `./data/`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    pub tables: Vec<TableArg>,
+    pub catalog: Option<String>,
+    pub policy: Option<String>,
+    pub role: Option<String>,
+    /// The values of the options of the binary, by option
+    pub options: HashMap<String, String>,
+}
+
+impl Args {
+    /// Parses the command line, along with `options` of the binary that take a value
+    pub fn parse(args: impl IntoIterator<Item = String>, options: &[&str]) -> Result<Self> {
+        let mut parsed = Args::default();
+        let mut schemas = HashMap::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                let (name, location) = split_assignment(&arg)?;
+                parsed.tables.push(TableArg {
+                    name,
+                    location,
+                    schema: None,
+                });
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| DataFusionError::Plan(format!("{} needs a value", arg)))?;
+            match arg.as_str() {
+                "--catalog" => parsed.catalog = Some(value),
+                "--policy" => parsed.policy = Some(value),
+                "--role" => parsed.role = Some(value),
+                "--schema" => {
+                    let (name, path) = split_assignment(&value)?;
+                    schemas.insert(name, path);
+                }
+                _ if options.contains(&arg.as_str()) => {
+                    parsed.options.insert(arg, value);
+                }
+                _ => return Err(DataFusionError::Plan(format!("unknown option '{}'", arg))),
+            }
+        }
+        if parsed.tables.is_empty() && parsed.catalog.is_none() {
+            parsed.tables.push(TableArg {
+                name: "my_table".to_owned(),
+                location: "./data/".to_owned(),
+                schema: None,
+            });
+        }
+        for table in &mut parsed.tables {
+            table.schema = schemas.remove(&table.name);
+        }
+        if let Some(name) = schemas.keys().next() {
+            return Err(DataFusionError::Plan(format!("--schema for unknown table '{}'", name)));
+        }
+        if parsed.policy.is_some() != parsed.role.is_some() {
+            return Err(DataFusionError::Plan("--policy and --role go together".to_owned()));
+        }
+        Ok(parsed)
+    }
+
+    /// Registers the [pii] and [inet] functions, the catalog and the tables, then masks
+    /// them with the policy of the role
+    pub async fn register(&self, ctx: &mut ExecutionContext) -> Result<()> {
+        pii::register_udfs(ctx);
+        inet::register_udfs(ctx);
+        if let Some(path) = &self.catalog {
+            let catalog = catalog::Catalog::load(path)?;
+            catalog::register_catalog(ctx, &catalog).await?;
+        }
+        for table in &self.tables {
+            register_parquet(ctx, &table.name, &table.location, table.schema.as_deref()).await?;
+        }
+        if let Some(role) = self.load_role()? {
+            apply_policy(ctx, &role)?;
+        }
+        Ok(())
+    }
+
+    /// The `--role` of the `--policy` file, if they are given
+    pub fn load_role(&self) -> Result<Option<Role>> {
+        match (&self.policy, &self.role) {
+            (Some(path), Some(name)) => {
+                let policy = Policy::load(path)?;
+                let role = policy
+                    .role(name)
+                    .ok_or_else(|| DataFusionError::Plan(format!("role '{}' is not in {}", name, path)))?;
+                Ok(Some(role.clone()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Servers listen on other addresses than localhost only when a role masks the
+    /// personal data of the tables, so a role without masks does not count
+    pub fn check_listen(&self, listen: SocketAddr) -> Result<()> {
+        if listen.ip().is_loopback() {
+            return Ok(());
+        }
+        match self.load_role()? {
+            Some(role) if !role.masks.is_empty() => Ok(()),
+            Some(role) => Err(DataFusionError::Plan(format!(
+                "listening on {} needs a role that masks columns, role '{}' masks none",
+                listen, role.name
+            ))),
+            None => Err(DataFusionError::Plan(format!(
+                "listening on {} needs --policy and --role, without them only localhost is served",
+                listen
+            ))),
+        }
+    }
+}
+
+fn split_assignment(arg: &str) -> Result<(String, String)> {
+    match arg.split_once('=') {
+        Some((name, value)) if !name.is_empty() && !value.is_empty() => Ok((name.to_owned(), value.to_owned())),
+        _ => Err(DataFusionError::Plan(format!(
+            "expected <table>=<value>, got '{}'",
+            arg
+        ))),
+    }
+}
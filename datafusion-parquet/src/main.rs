@@ -1,8 +1,7 @@
 use datafusion::error::Result;
 use datafusion::prelude::*;
-use datafusion::datasource::listing::ListingOptions;
-use datafusion::datasource::file_format::parquet::ParquetFormat;
-use std::sync::Arc;
+use datafusion_parquet::catalog::{register_catalog, Catalog};
+use datafusion_parquet::register_parquet;
 
 /// This example demonstrates executing a simple query against an Arrow data source (Parquet) and
 /// fetching results
@@ -10,22 +9,18 @@ use std::sync::Arc;
 async fn main() -> Result<()> {
     // create local execution context
     let mut ctx = ExecutionContext::new();
-    let file_format = ParquetFormat::default().with_enable_pruning(true);
 
-    let listing_options = ListingOptions {
-        file_extension: ".parquet".to_owned(),
-        format: Arc::new(file_format),
-        table_partition_cols: vec![],
-        collect_stat: true,
-        target_partitions: 1,
-    };
-
-    ctx.register_listing_table(
-        "my_table",
-        &format!("file://{}", "./data/"),
-        listing_options,
-        None,
-    ).await.unwrap();
+    // either a catalog that declares `my_table`, or an optional stored schema that declares
+    // the table instead of inferring it
+    match std::env::args().nth(1) {
+        Some(path) if path.ends_with(".toml") => {
+            let catalog = Catalog::load(&path)?;
+            register_catalog(&mut ctx, &catalog).await?;
+        }
+        schema_path => register_parquet(&mut ctx, "my_table", "./data/", schema_path.as_deref())
+            .await
+            .unwrap(),
+    }
     
     // execute the query
     let df = ctx.sql("SELECT COUNT(*) FROM my_table where country = 'China'").await?;
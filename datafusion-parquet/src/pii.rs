@@ -0,0 +1,130 @@
+//! Scalar functions that mask personal data, e.g. the `email`, `cc`, `ip_address` and
+//! `birthdate` columns of the `userdata` tables. All of them map `Utf8` to `Utf8` and null
+//! to null.
+//!
+//! ```text
+//! mask_email('jdoe@example.com')        j***@example.com
+//! mask_cc('4017-9562-1234-5678')        ****-****-****-5678
+//! hash_sha256('jdoe', 'salt')           hex SHA-256 of the salt followed by the value
+//! anonymize_ip('192.168.17.42')         192.168.17.0
+//! age_bucket('3/8/1971')                50-59
+//! ```
+
+use crate::udf::string_udf;
+use chrono::{Datelike, NaiveDate, Utc};
+use datafusion::arrow::array::StringArray;
+use datafusion::arrow::datatypes::DataType;
+use datafusion::physical_plan::functions::Volatility;
+use datafusion::physical_plan::udf::ScalarUDF;
+use datafusion::prelude::*;
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+
+/// Names of the masking functions, in the order of [`mask_udfs`]
+pub const MASKS: &[&str] = &["mask_email", "mask_cc", "hash_sha256", "anonymize_ip", "age_bucket"];
+
+/// Keeps the first character of the local part and the domain
+pub fn mask_email(email: &str) -> String {
+    match email.rsplit_once('@') {
+        Some((local, domain)) => {
+            let first = local.chars().next().map(String::from).unwrap_or_default();
+            format!("{}***@{}", first, domain)
+        }
+        None => "***".to_owned(),
+    }
+}
+
+/// Keeps the last four digits and the separators, numbers of four digits or less are
+/// masked completely
+pub fn mask_cc(cc: &str) -> String {
+    let digits = cc.chars().filter(char::is_ascii_digit).count();
+    let keep = if digits > 4 { 4 } else { 0 };
+    let mut seen = 0;
+    cc.chars()
+        .map(|c| {
+            if !c.is_ascii_digit() {
+                return c;
+            }
+            seen += 1;
+            if seen > digits - keep {
+                c
+            } else {
+                '*'
+            }
+        })
+        .collect()
+}
+
+/// Hex encoded SHA-256 of `salt` followed by `value`
+pub fn hash_sha256(value: &str, salt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(value.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Zeroes the last octet of an IPv4 address and the last 64 bits (the interface
+/// identifier) of an IPv6 address, `None` when `ip` is not an address
+pub fn anonymize_ip(ip: &str) -> Option<String> {
+    match ip.trim().parse::<IpAddr>().ok()? {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            Some(IpAddr::from([a, b, c, 0]).to_string())
+        }
+        IpAddr::V6(ip) => {
+            let mut segments = ip.segments();
+            segments[4..].iter_mut().for_each(|segment| *segment = 0);
+            Some(IpAddr::from(segments).to_string())
+        }
+    }
+}
+
+/// The ten year age bracket on `today`, e.g. `30-39`, of a birthdate given as `m/d/Y` (as
+/// in `userdata`) or `Y-m-d`. `None` when the date cannot be parsed or is after `today`.
+pub fn age_bucket(birthdate: &str, today: NaiveDate) -> Option<String> {
+    let birthdate = birthdate.trim();
+    let born = NaiveDate::parse_from_str(birthdate, "%m/%d/%Y")
+        .or_else(|_| NaiveDate::parse_from_str(birthdate, "%Y-%m-%d"))
+        .ok()?;
+    let had_birthday = (today.month(), today.day()) >= (born.month(), born.day());
+    let age = today.year() - born.year() - if had_birthday { 0 } else { 1 };
+    if age < 0 {
+        return None;
+    }
+    let bracket = age / 10 * 10;
+    Some(format!("{}-{}", bracket, bracket + 9))
+}
+
+/// A masking function of `args` Utf8 arguments
+fn mask<F>(name: &str, args: usize, volatility: Volatility, f: F) -> ScalarUDF
+where
+    F: Fn(&[&str]) -> Option<String> + Send + Sync + 'static,
+{
+    string_udf::<StringArray, _, _>(name, args, DataType::Utf8, volatility, f)
+}
+
+/// The masking functions, see [`MASKS`]
+pub fn mask_udfs() -> Vec<ScalarUDF> {
+    vec![
+        mask("mask_email", 1, Volatility::Immutable, |args| Some(mask_email(args[0]))),
+        mask("mask_cc", 1, Volatility::Immutable, |args| Some(mask_cc(args[0]))),
+        mask("hash_sha256", 2, Volatility::Immutable, |args| Some(hash_sha256(args[0], args[1]))),
+        mask("anonymize_ip", 1, Volatility::Immutable, |args| anonymize_ip(args[0])),
+        // ages change with the current date
+        mask("age_bucket", 1, Volatility::Stable, |args| {
+            age_bucket(args[0], Utc::today().naive_utc())
+        }),
+    ]
+}
+
+/// The masking function called `name`
+pub fn mask_udf(name: &str) -> Option<ScalarUDF> {
+    mask_udfs().into_iter().find(|udf| udf.name == name)
+}
+
+/// Registers the masking functions in `ctx`
+pub fn register_udfs(ctx: &mut ExecutionContext) {
+    for udf in mask_udfs() {
+        ctx.register_udf(udf);
+    }
+}
@@ -0,0 +1,291 @@
+//! Column masking policies per role, loaded from a TOML file:
+//!
+//! ```toml
+//! [[role]]
+//! name = "analyst"
+//! salt = "change me"              # prepended by hash_sha256, default empty
+//!
+//! [role.masks]                    # column = masking function, see `pii`
+//! email = "mask_email"
+//! cc = "mask_cc"
+//! ip_address = "anonymize_ip"
+//! birthdate = "age_bucket"
+//! last_name = "hash_sha256"
+//! ```
+//!
+//! A role's masks apply to the named columns of every registered table, whatever the case
+//! of the column names, and a role that masks a column no table has is rejected, as it is
+//! most likely misspelled. Masked columns are
+//! `Utf8`, and filters on them are not pushed into the scan, so queries only ever see and
+//! compare the masked values.
+
+use crate::error::PolicyError;
+use crate::pii::{mask_udf, MASKS};
+use crate::{DEFAULT_CATALOG, DEFAULT_SCHEMA};
+use async_trait::async_trait;
+use datafusion::arrow::compute::can_cast_types;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::datasource::datasource::{TableProvider, TableProviderFilterPushDown};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_plan::Expr;
+use datafusion::physical_plan::expressions::{cast, Column, Literal};
+use datafusion::physical_plan::functions::ScalarFunctionExpr;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::udf::ScalarUDF;
+use datafusion::physical_plan::{ExecutionPlan, PhysicalExpr};
+use datafusion::prelude::*;
+use datafusion::scalar::ScalarValue;
+use serde::Deserialize;
+use std::any::Any;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Role {
+    pub name: String,
+    #[serde(default)]
+    pub salt: String,
+    /// Masking function per column name
+    #[serde(default)]
+    pub masks: BTreeMap<String, String>,
+}
+
+impl Role {
+    /// The masking function of `column`, names match regardless of case
+    pub fn mask(&self, column: &str) -> Option<&String> {
+        self.masks
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(column))
+            .map(|(_, mask)| mask)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default, rename = "role")]
+    pub roles: Vec<Role>,
+}
+
+impl Policy {
+    /// Reads and validates the policy at `path`
+    pub fn load<P: AsRef<Path>>(path: P) -> std::result::Result<Self, PolicyError> {
+        let toml = fs::read_to_string(path.as_ref())
+            .map_err(|e| PolicyError::Io(format!("{}: {}", path.as_ref().display(), e)))?;
+        Self::from_toml(&toml)
+    }
+
+    /// Parses and validates a policy
+    pub fn from_toml(toml: &str) -> std::result::Result<Self, PolicyError> {
+        let policy: Policy = toml::from_str(toml).map_err(|e| PolicyError::Parse(e.to_string()))?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Checks every role and reports all problems at once
+    pub fn validate(&self) -> std::result::Result<(), PolicyError> {
+        let mut problems = vec![];
+        let mut names = HashSet::new();
+        for (index, role) in self.roles.iter().enumerate() {
+            let label = if role.name.is_empty() {
+                problems.push(format!("role #{}: name must not be empty", index + 1));
+                format!("role #{}", index + 1)
+            } else {
+                format!("role '{}'", role.name)
+            };
+            if !role.name.is_empty() && !names.insert(&role.name) {
+                problems.push(format!("{}: name is declared more than once", label));
+            }
+            let mut columns = HashSet::new();
+            for (column, mask) in &role.masks {
+                if column.is_empty() {
+                    problems.push(format!("{}: masks must not contain empty column names", label));
+                }
+                if !columns.insert(column.to_ascii_lowercase()) {
+                    problems.push(format!("{}: column '{}' is masked more than once", label, column));
+                }
+                if !MASKS.contains(&mask.as_str()) {
+                    problems.push(format!(
+                        "{}: unknown mask '{}' for column '{}', expected one of {}",
+                        label,
+                        mask,
+                        column,
+                        MASKS.join(", ")
+                    ));
+                }
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(PolicyError::Invalid(problems.join("\n")))
+        }
+    }
+
+    pub fn role(&self, name: &str) -> Option<&Role> {
+        self.roles.iter().find(|role| role.name == name)
+    }
+}
+
+/// A table whose masked columns are replaced by the masking function of their values
+pub struct MaskedTable {
+    inner: Arc<dyn TableProvider>,
+    /// The masking function per column of `inner`
+    masks: Vec<Option<ScalarUDF>>,
+    salt: String,
+    schema: SchemaRef,
+}
+
+impl MaskedTable {
+    /// Masks the columns of `inner` that `role` names
+    pub fn try_new(inner: Arc<dyn TableProvider>, role: &Role) -> Result<Self> {
+        let inner_schema = inner.schema();
+        let mut masks = vec![];
+        let mut fields = vec![];
+        for field in inner_schema.fields() {
+            match role.mask(field.name()) {
+                Some(mask) => {
+                    if !can_cast_types(field.data_type(), &DataType::Utf8) {
+                        return Err(DataFusionError::Plan(format!(
+                            "column '{}' of type {:?} cannot be masked with {}",
+                            field.name(),
+                            field.data_type(),
+                            mask
+                        )));
+                    }
+                    let udf = mask_udf(mask)
+                        .ok_or_else(|| DataFusionError::Plan(format!("unknown mask '{}'", mask)))?;
+                    masks.push(Some(udf));
+                    fields.push(Field::new(field.name(), DataType::Utf8, true));
+                }
+                None => {
+                    masks.push(None);
+                    fields.push(field.clone());
+                }
+            }
+        }
+        Ok(Self {
+            inner,
+            masks,
+            salt: role.salt.clone(),
+            schema: Arc::new(Schema::new(fields)),
+        })
+    }
+
+    /// The unmasked table
+    pub fn inner(&self) -> &Arc<dyn TableProvider> {
+        &self.inner
+    }
+
+    /// The masking expression of `value`, cast to Utf8 when needed
+    fn mask(
+        &self,
+        udf: &ScalarUDF,
+        value: Arc<dyn PhysicalExpr>,
+        input_schema: &Schema,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        let mut args = vec![cast(value, input_schema, DataType::Utf8)?];
+        if udf.name == "hash_sha256" {
+            args.push(Arc::new(Literal::new(ScalarValue::Utf8(Some(self.salt.clone())))));
+        }
+        Ok(Arc::new(ScalarFunctionExpr::new(
+            &udf.name,
+            udf.fun.clone(),
+            args,
+            &DataType::Utf8,
+        )))
+    }
+}
+
+#[async_trait]
+impl TableProvider for MaskedTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // filters on raw values would reveal them, they are applied to the masked output
+        let input = self.inner.scan(projection, batch_size, &[], limit).await?;
+        let input_schema = input.schema();
+        let indices = projection
+            .clone()
+            .unwrap_or_else(|| (0..self.schema.fields().len()).collect());
+
+        let exprs = indices
+            .iter()
+            .enumerate()
+            .map(|(position, index)| {
+                let name = self.schema.field(*index).name().clone();
+                let column: Arc<dyn PhysicalExpr> = Arc::new(Column::new(&name, position));
+                let expr = match &self.masks[*index] {
+                    Some(udf) => self.mask(udf, column, &input_schema)?,
+                    None => column,
+                };
+                Ok((expr, name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(ProjectionExec::try_new(exprs, input)?))
+    }
+
+    fn supports_filter_pushdown(&self, _filter: &Expr) -> Result<TableProviderFilterPushDown> {
+        Ok(TableProviderFilterPushDown::Unsupported)
+    }
+}
+
+/// Replaces every table in the default schema of `ctx` that has a column masked by `role`
+/// with a [`MaskedTable`]. Tables registered afterwards are not masked. Fails when a column
+/// masked by `role` is in none of the tables.
+pub fn apply_policy(ctx: &mut ExecutionContext, role: &Role) -> Result<()> {
+    let schema = ctx
+        .catalog(DEFAULT_CATALOG)
+        .and_then(|catalog| catalog.schema(DEFAULT_SCHEMA))
+        .ok_or_else(|| DataFusionError::Plan("the default schema is not registered".to_owned()))?;
+    let mut masked_columns = HashSet::new();
+    let mut tables = vec![];
+    for name in schema.table_names() {
+        let table = match schema.table(&name) {
+            Some(table) => table,
+            None => continue,
+        };
+        let columns: Vec<String> = table
+            .schema()
+            .fields()
+            .iter()
+            .filter(|field| role.mask(field.name()).is_some())
+            .map(|field| field.name().to_ascii_lowercase())
+            .collect();
+        if !columns.is_empty() {
+            masked_columns.extend(columns);
+            tables.push((name, table));
+        }
+    }
+    if let Some(column) = role
+        .masks
+        .keys()
+        .find(|column| !masked_columns.contains(&column.to_ascii_lowercase()))
+    {
+        return Err(DataFusionError::Plan(format!(
+            "role '{}' masks column '{}', which no table has",
+            role.name, column
+        )));
+    }
+    for (name, table) in tables {
+        let masked = MaskedTable::try_new(table, role)
+            .map_err(|e| DataFusionError::Plan(format!("table '{}': {}", name, e)))?;
+        ctx.register_table(name.as_str(), Arc::new(masked))?;
+    }
+    Ok(())
+}
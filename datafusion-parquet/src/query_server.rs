@@ -0,0 +1,419 @@
+//! HTTP query service over the tables of an [`ExecutionContext`], served by the `server`
+//! binary. Endpoints:
+//!
+//! ```text
+//! GET  /health                          {"status": "ok", "running": 1, "max_concurrent": 4}
+//! GET  /tables                          every table with its columns and their types
+//! POST /query?format=ndjson|csv|arrow   the SQL query in the body, results are streamed
+//! ```
+//!
+//! Without `format`, the `Accept` header picks the format (`application/x-ndjson`,
+//! `text/csv` or `application/vnd.apache.arrow.stream`), NDJSON by default. A query that
+//! fails or exceeds the timeout while streaming is cut off, so clients see an incomplete
+//! body, and the error is logged. Queries larger than [`MAX_QUERY_SIZE`] are rejected with
+//! 413 before they count towards the concurrency limit, queries beyond it with 503, and
+//! statements that would register tables with 400.
+
+use crate::{Args, DEFAULT_CATALOG, DEFAULT_SCHEMA};
+use datafusion::arrow::datatypes::Schema;
+use datafusion::arrow::ipc::writer::StreamWriter;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow::{csv, json};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_plan::LogicalPlan;
+use datafusion::physical_plan::{execute_stream, ExecutionPlan};
+use datafusion::prelude::*;
+use futures::StreamExt;
+use hyper::body::{Bytes, HttpBody, Sender};
+use hyper::header::{HeaderValue, ACCEPT, CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::{timeout_at, Instant};
+
+/// Largest accepted query, in bytes
+pub const MAX_QUERY_SIZE: usize = 1024 * 1024;
+
+/// The encoding of query results
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Ndjson,
+    Csv,
+    Arrow,
+}
+
+impl OutputFormat {
+    /// The format of a `format` parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ndjson" | "json" => Some(OutputFormat::Ndjson),
+            "csv" => Some(OutputFormat::Csv),
+            "arrow" => Some(OutputFormat::Arrow),
+            _ => None,
+        }
+    }
+
+    /// The first supported media type of an `Accept` header
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        accept.split(',').find_map(|media_type| {
+            match media_type.split(';').next().unwrap_or_default().trim() {
+                "application/x-ndjson" => Some(OutputFormat::Ndjson),
+                "text/csv" => Some(OutputFormat::Csv),
+                "application/vnd.apache.arrow.stream" => Some(OutputFormat::Arrow),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            OutputFormat::Ndjson => "application/x-ndjson",
+            OutputFormat::Csv => "text/csv",
+            OutputFormat::Arrow => "application/vnd.apache.arrow.stream",
+        }
+    }
+}
+
+/// A `Write` that keeps what was written until it is taken, for the Arrow IPC writer
+#[derive(Clone, Default)]
+pub struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Encodes the batches of a result one chunk at a time
+pub enum Encoder {
+    Ndjson,
+    Csv { schema: Arc<Schema>, headers: bool },
+    Arrow { writer: StreamWriter<SharedBuffer>, buffer: SharedBuffer },
+}
+
+impl Encoder {
+    pub fn new(format: OutputFormat, schema: Arc<Schema>) -> Result<Self> {
+        Ok(match format {
+            OutputFormat::Ndjson => Encoder::Ndjson,
+            OutputFormat::Csv => Encoder::Csv { schema, headers: true },
+            OutputFormat::Arrow => {
+                let buffer = SharedBuffer::default();
+                let writer = StreamWriter::try_new(buffer.clone(), &schema)?;
+                Encoder::Arrow { writer, buffer }
+            }
+        })
+    }
+
+    /// The first chunk, before any batch
+    pub fn start(&mut self) -> Vec<u8> {
+        match self {
+            // the schema message
+            Encoder::Arrow { buffer, .. } => buffer.take(),
+            _ => vec![],
+        }
+    }
+
+    pub fn encode(&mut self, batch: &RecordBatch) -> Result<Vec<u8>> {
+        let mut chunk = vec![];
+        match self {
+            Encoder::Ndjson => {
+                let mut writer = json::LineDelimitedWriter::new(&mut chunk);
+                writer.write_batches(std::slice::from_ref(batch))?;
+                writer.finish()?;
+            }
+            Encoder::Csv { headers, .. } => {
+                let mut writer = csv::WriterBuilder::new().has_headers(*headers).build(&mut chunk);
+                writer.write(batch)?;
+                *headers = false;
+            }
+            Encoder::Arrow { writer, buffer } => {
+                writer.write(batch)?;
+                chunk = buffer.take();
+            }
+        }
+        Ok(chunk)
+    }
+
+    /// The last chunk, after all batches
+    pub fn finish(&mut self) -> Result<Vec<u8>> {
+        match self {
+            // the header of an empty result
+            Encoder::Csv { schema, headers: true } => {
+                let empty = RecordBatch::new_empty(schema.clone());
+                self.encode(&empty)
+            }
+            Encoder::Arrow { writer, buffer } => {
+                writer.finish()?;
+                Ok(buffer.take())
+            }
+            _ => Ok(vec![]),
+        }
+    }
+}
+
+pub struct QueryService {
+    ctx: ExecutionContext,
+    timeout: Duration,
+    max_concurrent: usize,
+    queries: Arc<Semaphore>,
+}
+
+impl QueryService {
+    pub fn new(ctx: ExecutionContext, timeout: Duration, max_concurrent: usize) -> Self {
+        Self {
+            ctx,
+            timeout,
+            max_concurrent,
+            queries: Arc::new(Semaphore::new(max_concurrent)),
+        }
+    }
+
+    /// Answers a request, failures are JSON objects with an `error` message
+    pub async fn handle(self: Arc<Self>, request: Request<Body>) -> Response<Body> {
+        let (method, path) = (request.method().clone(), request.uri().path().to_owned());
+        let response = match (&method, path.as_str()) {
+            (&Method::GET, "/health") => Ok(self.health()),
+            (&Method::GET, "/tables") => self.tables(),
+            (&Method::POST, "/query") => self.clone().query(request).await,
+            (_, "/health") | (_, "/tables") | (_, "/query") => {
+                Err((StatusCode::METHOD_NOT_ALLOWED, "method not allowed".to_owned()))
+            }
+            _ => Err((StatusCode::NOT_FOUND, "not found".to_owned())),
+        };
+        response.unwrap_or_else(|(status, message)| json_response(status, json!({ "error": message })))
+    }
+
+    fn health(&self) -> Response<Body> {
+        let running = self.max_concurrent - self.queries.available_permits();
+        json_response(
+            StatusCode::OK,
+            json!({ "status": "ok", "running": running, "max_concurrent": self.max_concurrent }),
+        )
+    }
+
+    fn tables(&self) -> std::result::Result<Response<Body>, (StatusCode, String)> {
+        let schema = self
+            .ctx
+            .catalog(DEFAULT_CATALOG)
+            .and_then(|catalog| catalog.schema(DEFAULT_SCHEMA))
+            .ok_or_else(|| internal_error("the default schema is not registered"))?;
+        let mut names = schema.table_names();
+        names.sort();
+        let tables: Vec<_> = names
+            .iter()
+            .filter_map(|name| schema.table(name).map(|table| (name, table.schema())))
+            .map(|(name, schema)| {
+                let columns: Vec<_> = schema
+                    .fields()
+                    .iter()
+                    .map(|field| {
+                        json!({
+                            "name": field.name(),
+                            "type": field.data_type(),
+                            "nullable": field.is_nullable(),
+                        })
+                    })
+                    .collect();
+                json!({ "name": name, "columns": columns })
+            })
+            .collect();
+        Ok(json_response(StatusCode::OK, json!({ "tables": tables })))
+    }
+
+    async fn query(
+        self: Arc<Self>,
+        request: Request<Body>,
+    ) -> std::result::Result<Response<Body>, (StatusCode, String)> {
+        let format = self.format(&request)?;
+        let sql = read_query(request).await?;
+
+        let permit = self.queries.clone().try_acquire_owned().map_err(|_| {
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("{} queries are running, try again later", self.max_concurrent),
+            )
+        })?;
+        let deadline = Instant::now() + self.timeout;
+
+        // planning counts towards the timeout, but fails with a status
+        let plan = match timeout_at(deadline, self.plan(&sql)).await {
+            Ok(plan) => plan.map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?,
+            Err(_) => return Err((StatusCode::GATEWAY_TIMEOUT, "the query timed out".to_owned())),
+        };
+        let encoder = Encoder::new(format, plan.schema()).map_err(|e| internal_error(&e.to_string()))?;
+
+        let (mut sender, body) = Body::channel();
+        let timeout = self.timeout;
+        tokio::spawn(async move {
+            // an incomplete body tells the client that the query failed or timed out
+            match timeout_at(deadline, stream_results(plan, encoder, &mut sender)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    eprintln!("query failed: {}: {}", sql, e);
+                    sender.abort();
+                }
+                Err(_) => {
+                    eprintln!("query timed out after {}s: {}", timeout.as_secs(), sql);
+                    sender.abort();
+                }
+            }
+            drop(permit);
+        });
+
+        let mut response = Response::new(body);
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(format.content_type()));
+        Ok(response)
+    }
+
+    /// The output format requested by the `format` parameter or the `Accept` header
+    fn format(&self, request: &Request<Body>) -> std::result::Result<OutputFormat, (StatusCode, String)> {
+        let parameters: HashMap<&str, &str> = request
+            .uri()
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|parameter| parameter.split_once('='))
+            .collect();
+        if let Some(name) = parameters.get("format") {
+            return OutputFormat::from_name(name).ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("unknown format '{}', expected ndjson, csv or arrow", name),
+                )
+            });
+        }
+        Ok(request
+            .headers()
+            .get(ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .and_then(OutputFormat::from_accept)
+            .unwrap_or(OutputFormat::Ndjson))
+    }
+
+    async fn plan(&self, sql: &str) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = self.ctx.create_logical_plan(sql)?;
+        if let LogicalPlan::CreateExternalTable { .. } = plan {
+            return Err(DataFusionError::Plan("only queries are accepted".to_owned()));
+        }
+        let plan = self.ctx.optimize(&plan)?;
+        self.ctx.create_physical_plan(&plan).await
+    }
+}
+
+/// The query in the body of `request`, refused by its `Content-Length` or, without one,
+/// while it is received once it exceeds [`MAX_QUERY_SIZE`]
+async fn read_query(request: Request<Body>) -> std::result::Result<String, (StatusCode, String)> {
+    let too_large = || (StatusCode::PAYLOAD_TOO_LARGE, "the query is too large".to_owned());
+    let length = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok());
+    if length.is_some_and(|length| length > MAX_QUERY_SIZE as u64) {
+        return Err(too_large());
+    }
+    let mut body = request.into_body();
+    let mut query = vec![];
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        if query.len() + chunk.len() > MAX_QUERY_SIZE {
+            return Err(too_large());
+        }
+        query.extend_from_slice(&chunk);
+    }
+    String::from_utf8(query).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// Streams the encoded results of `plan`
+async fn stream_results(plan: Arc<dyn ExecutionPlan>, mut encoder: Encoder, sender: &mut Sender) -> Result<()> {
+    send(sender, encoder.start()).await?;
+    let mut batches = execute_stream(plan).await?;
+    while let Some(batch) = batches.next().await {
+        send(sender, encoder.encode(&batch?)?).await?;
+    }
+    send(sender, encoder.finish()?).await
+}
+
+/// Sends a non-empty chunk
+async fn send(sender: &mut Sender, chunk: Vec<u8>) -> Result<()> {
+    if !chunk.is_empty() {
+        sender
+            .send_data(Bytes::from(chunk))
+            .await
+            .map_err(|e| DataFusionError::Execution(format!("the client went away: {}", e)))?;
+    }
+    Ok(())
+}
+
+fn internal_error(message: &str) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, message.to_owned())
+}
+
+fn json_response(status: StatusCode, value: serde_json::Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(format!("{}\n", value)));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+/// The options of the server, besides the tables of [`Args`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerOptions {
+    pub listen: SocketAddr,
+    pub timeout: Duration,
+    pub max_concurrent: usize,
+}
+
+impl ServerOptions {
+    /// `--listen <addr>`, `--timeout <secs>` and `--max-concurrent <n>`, on localhost
+    /// unless a masking policy applies
+    pub fn from_args(args: &Args) -> Result<Self> {
+        let mut options = ServerOptions {
+            listen: ([127, 0, 0, 1], 8080).into(),
+            timeout: Duration::from_secs(30),
+            max_concurrent: 4,
+        };
+        if let Some(listen) = args.options.get("--listen") {
+            options.listen = listen
+                .parse()
+                .map_err(|_| DataFusionError::Plan(format!("invalid address '{}'", listen)))?;
+        }
+        if let Some(secs) = args.options.get("--timeout") {
+            options.timeout = secs
+                .parse()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .ok_or_else(|| DataFusionError::Plan(format!("invalid timeout '{}'", secs)))?;
+        }
+        if let Some(max) = args.options.get("--max-concurrent") {
+            options.max_concurrent = max
+                .parse()
+                .ok()
+                .filter(|max| *max > 0)
+                .ok_or_else(|| DataFusionError::Plan(format!("invalid concurrency limit '{}'", max)))?;
+        }
+        args.check_listen(options.listen)?;
+        Ok(options)
+    }
+}
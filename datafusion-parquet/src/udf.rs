@@ -0,0 +1,49 @@
+//! Construction of scalar functions over string arguments
+
+use datafusion::arrow::array::{Array, ArrayRef, StringArray};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_plan::create_udf;
+use datafusion::physical_plan::functions::{make_scalar_function, Volatility};
+use datafusion::physical_plan::udf::ScalarUDF;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+/// A function of `args` Utf8 arguments returning an array `A` of `return_type`, which
+/// applies `f` to the values of each row. Null when any argument is null or `f` returns
+/// `None`.
+pub(crate) fn string_udf<A, T, F>(
+    name: &str,
+    args: usize,
+    return_type: DataType,
+    volatility: Volatility,
+    f: F,
+) -> ScalarUDF
+where
+    A: Array + FromIterator<Option<T>> + 'static,
+    F: Fn(&[&str]) -> Option<T> + Send + Sync + 'static,
+{
+    let udf_name = name.to_owned();
+    let fun = make_scalar_function(move |arrays: &[ArrayRef]| {
+        let arrays = arrays
+            .iter()
+            .map(|array| {
+                array.as_any().downcast_ref::<StringArray>().ok_or_else(|| {
+                    DataFusionError::Internal(format!("{} expects Utf8 arguments", udf_name))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let len = arrays.first().map_or(0, |array| array.len());
+        let values: A = (0..len)
+            .map(|row| {
+                let row_values = arrays
+                    .iter()
+                    .map(|array| if array.is_null(row) { None } else { Some(array.value(row)) })
+                    .collect::<Option<Vec<_>>>()?;
+                f(&row_values)
+            })
+            .collect();
+        Ok(Arc::new(values) as ArrayRef)
+    });
+    create_udf(name, vec![DataType::Utf8; args], Arc::new(return_type), volatility, fun)
+}
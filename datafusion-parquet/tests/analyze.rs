@@ -0,0 +1,64 @@
+use datafusion::datasource::object_store::local::LocalFileSystem;
+use datafusion::prelude::*;
+use datafusion_parquet::analyze::{analyze, MeteredStore};
+use datafusion_parquet::register_parquet;
+use std::fs;
+use std::sync::Arc;
+
+/// A context whose local files are read through the returned store, with `users` over all
+/// bundled files, `first` over one of them and `sales` partitioned by year
+async fn context(name: &str) -> (ExecutionContext, Arc<MeteredStore>) {
+    let root = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for year in &["year=2020", "year=2021"] {
+        fs::create_dir_all(root.join(year)).unwrap();
+        fs::copy("./data/userdata1.parquet", root.join(year).join("part-0.parquet")).unwrap();
+    }
+
+    let mut ctx = ExecutionContext::new();
+    let store = Arc::new(MeteredStore::new(Arc::new(LocalFileSystem)));
+    ctx.register_object_store("file", store.clone());
+    register_parquet(&mut ctx, "users", "./data/", None).await.unwrap();
+    register_parquet(&mut ctx, "first", "./data/userdata1.parquet", None).await.unwrap();
+    register_parquet(&mut ctx, "sales", root.to_str().unwrap(), None).await.unwrap();
+    (ctx, store)
+}
+
+#[tokio::test]
+async fn files_are_counted_for_the_scanned_tables_only() {
+    let (mut ctx, store) = context("analyze-scoped").await;
+
+    // answered from the statistics collected while planning
+    let (batches, analysis) = analyze(&mut ctx, &store, "SELECT COUNT(*) FROM first").await.unwrap();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(analysis.rows, 1);
+    assert_eq!((analysis.files_scanned, analysis.files_skipped), (0, 1));
+    assert_eq!(analysis.bytes_read, 0);
+
+    let (_, analysis) = analyze(&mut ctx, &store, "SELECT id FROM first").await.unwrap();
+    assert_eq!(analysis.rows, 1000);
+    assert_eq!((analysis.files_scanned, analysis.files_skipped), (1, 0));
+    assert!(analysis.bytes_read > 0);
+
+    let (_, analysis) = analyze(&mut ctx, &store, "SELECT id FROM users").await.unwrap();
+    assert_eq!(analysis.rows, 3000);
+    assert_eq!((analysis.files_scanned, analysis.files_skipped), (3, 0));
+}
+
+#[tokio::test]
+async fn pruned_partitions_are_skipped() {
+    let (mut ctx, store) = context("analyze-partitions").await;
+    let (_, analysis) = analyze(&mut ctx, &store, "SELECT id FROM sales WHERE year = 2021").await.unwrap();
+    assert_eq!(analysis.rows, 1000);
+    assert_eq!((analysis.files_scanned, analysis.files_skipped), (1, 1));
+}
+
+#[tokio::test]
+async fn row_groups_are_pruned_by_statistics() {
+    let (mut ctx, store) = context("analyze-row-groups").await;
+    let (_, analysis) = analyze(&mut ctx, &store, "SELECT id FROM users WHERE id > 5000").await.unwrap();
+    assert_eq!(analysis.rows, 0);
+    assert_eq!(analysis.row_groups_pruned, 3);
+    assert!(analysis.operators[0].depth == 0 && analysis.operators.len() > 1);
+    assert!(analysis.to_string().contains("row groups pruned by statistics: 3\n"));
+}
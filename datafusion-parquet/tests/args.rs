@@ -0,0 +1,104 @@
+use datafusion::prelude::*;
+use datafusion_parquet::{Args, TableArg};
+
+fn parse(args: &[&str], options: &[&str]) -> datafusion::error::Result<Args> {
+    Args::parse(args.iter().map(|arg| arg.to_string()), options)
+}
+
+fn table(name: &str, location: &str, schema: Option<&str>) -> TableArg {
+    TableArg {
+        name: name.to_owned(),
+        location: location.to_owned(),
+        schema: schema.map(str::to_owned),
+    }
+}
+
+#[test]
+fn tables_default_to_my_table() {
+    let args = parse(&[], &[]).unwrap();
+    assert_eq!(args.tables, vec![table("my_table", "./data/", None)]);
+
+    let args = parse(&["--catalog", "catalog.toml"], &[]).unwrap();
+    assert!(args.tables.is_empty());
+    assert_eq!(args.catalog.as_deref(), Some("catalog.toml"));
+}
+
+#[test]
+fn schemas_and_options_are_assigned() {
+    let args = parse(
+        &[
+            "users=./data/",
+            "--schema",
+            "users=users.schema",
+            "--listen",
+            "0.0.0.0:8080",
+            "first=./data/userdata1.parquet",
+            "--policy",
+            "policy.toml",
+            "--role",
+            "analyst",
+        ],
+        &["--listen"],
+    )
+    .unwrap();
+    assert_eq!(
+        args.tables,
+        vec![
+            table("users", "./data/", Some("users.schema")),
+            table("first", "./data/userdata1.parquet", None),
+        ]
+    );
+    assert_eq!(args.options.get("--listen").map(String::as_str), Some("0.0.0.0:8080"));
+    assert_eq!(args.policy.as_deref(), Some("policy.toml"));
+    assert_eq!(args.role.as_deref(), Some("analyst"));
+}
+
+#[test]
+fn invalid_command_lines_are_rejected() {
+    let error = |args: &[&str]| parse(args, &["--listen"]).unwrap_err().to_string();
+    assert!(error(&["users"]).contains("expected <table>=<value>, got 'users'"));
+    assert!(error(&["=./data/"]).contains("expected <table>=<value>"));
+    assert!(error(&["--listen"]).contains("--listen needs a value"));
+    assert!(error(&["--timeout", "5"]).contains("unknown option '--timeout'"));
+    assert!(error(&["--schema", "other=other.schema"]).contains("--schema for unknown table 'other'"));
+    assert!(error(&["--role", "analyst"]).contains("--policy and --role go together"));
+}
+
+#[tokio::test]
+async fn tables_are_registered() {
+    let args = parse(&["users=./data/", "first=./data/userdata1.parquet"], &[]).unwrap();
+    let mut ctx = ExecutionContext::new();
+    args.register(&mut ctx).await.unwrap();
+    let batches = ctx
+        .sql("SELECT mask_email(email) FROM first LIMIT 1")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    assert_eq!(batches[0].num_rows(), 1);
+    assert!(ctx.table("users").is_ok());
+}
+
+#[test]
+fn only_masked_tables_are_served_beyond_localhost() {
+    let local = parse(&[], &[]).unwrap();
+    assert!(local.check_listen(([127, 0, 0, 1], 50051).into()).is_ok());
+    assert!(local.check_listen("[::1]:50051".parse().unwrap()).is_ok());
+    let error = local.check_listen(([192, 168, 1, 2], 50051).into()).unwrap_err();
+    assert!(error.to_string().contains("listening on 192.168.1.2:50051 needs --policy and --role"));
+
+    let policy = std::env::temp_dir().join(format!("datafusion-parquet-listen-{}.toml", std::process::id()));
+    std::fs::write(
+        &policy,
+        "[[role]]\nname = \"analyst\"\n[role.masks]\nemail = \"mask_email\"\n[[role]]\nname = \"admin\"\n",
+    )
+    .unwrap();
+    let with_role = |role: &str| parse(&["--policy", policy.to_str().unwrap(), "--role", role], &[]).unwrap();
+    assert!(with_role("analyst").check_listen(([0, 0, 0, 0], 50051).into()).is_ok());
+    // a role that masks nothing serves the tables as they are
+    let error = with_role("admin").check_listen(([0, 0, 0, 0], 50051).into()).unwrap_err();
+    assert!(error.to_string().contains("role 'admin' masks none"), "{}", error);
+    assert!(with_role("admin").check_listen(([127, 0, 0, 1], 50051).into()).is_ok());
+    std::fs::remove_file(&policy).unwrap();
+}
@@ -0,0 +1,109 @@
+use datafusion::arrow::array::UInt64Array;
+use datafusion::arrow::datatypes::Schema;
+use datafusion::prelude::*;
+use datafusion_parquet::catalog::{register_catalog, Catalog};
+use datafusion_parquet::error::CatalogError;
+use datafusion_parquet::register_parquet;
+use std::fs;
+
+fn problems(toml: &str) -> Vec<String> {
+    match Catalog::from_toml(toml) {
+        Err(CatalogError::Invalid(problems)) => problems.lines().map(str::to_owned).collect(),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+async fn count(ctx: &mut ExecutionContext, table: &str) -> u64 {
+    let sql = format!("SELECT COUNT(id) FROM {}", table);
+    let batches = ctx.sql(&sql).await.unwrap().collect().await.unwrap();
+    batches[0].column(0).as_any().downcast_ref::<UInt64Array>().unwrap().value(0)
+}
+
+#[test]
+fn entries_have_defaults() {
+    let catalog = Catalog::from_toml("[[table]]\nname = \"users\"\nurl = \"file://./data/\"").unwrap();
+    let table = &catalog.tables[0];
+    assert_eq!(table.format, "parquet");
+    assert!(table.collect_stat);
+    assert_eq!(table.target_partitions, num_cpus::get());
+    let options = table.listing_options();
+    assert_eq!(options.file_extension, ".parquet");
+    assert!(options.table_partition_cols.is_empty());
+
+    let catalog = Catalog::from_toml("[[table]]\nname = \"t\"\nurl = \"s3://bucket/t/\"\nformat = \"csv\"").unwrap();
+    assert_eq!(catalog.tables[0].listing_options().file_extension, ".csv");
+}
+
+#[test]
+fn every_problem_is_reported() {
+    let problems = problems(
+        r#"
+        [[table]]
+        name = "1st"
+        url = "./data/"
+        format = "orc"
+        file_extension = ""
+        target_partitions = 0
+        partition_cols = ["year", "", "year"]
+        schema = "./missing.schema"
+
+        [[table]]
+        name = "users"
+        url = "s3:///users/"
+
+        [[table]]
+        name = "users"
+        url = "file://./data/"
+        "#,
+    );
+    assert_eq!(
+        problems,
+        vec![
+            "table '1st': name must be a plain identifier, e.g. my_table",
+            "table '1st': url './data/' must start with file:// or s3://",
+            "table '1st': unknown format 'orc', expected parquet, csv or json",
+            "table '1st': file_extension must not be empty",
+            "table '1st': target_partitions must be at least 1",
+            "table '1st': partition_cols must not contain empty names",
+            "table '1st': partition column 'year' is listed twice",
+            "table '1st': schema file './missing.schema' does not exist",
+            "table 'users': url 's3:///users/' must name a bucket, e.g. s3://bucket/prefix/",
+            "table 'users': name is declared more than once",
+        ]
+    );
+}
+
+#[test]
+fn unreadable_catalogs_are_rejected() {
+    assert!(matches!(Catalog::load("./missing.toml"), Err(CatalogError::Io(_))));
+    let error = Catalog::from_toml("[[table]]\nname = \"t\"\nurl = \"file://./data/\"\nfiles = 1").unwrap_err();
+    assert!(matches!(error, CatalogError::Parse(_)));
+    assert!(error.to_string().starts_with("Cannot parse catalog: "));
+}
+
+#[tokio::test]
+async fn tables_are_declared_by_text_schema_files() {
+    let mut ctx = ExecutionContext::new();
+    register_parquet(&mut ctx, "inferred", "./data/", None).await.unwrap();
+    let schema: Schema = ctx.table("inferred").unwrap().schema().clone().into();
+
+    let dir = std::env::temp_dir().join(format!("datafusion-parquet-catalog-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let schema_path = dir.join("users.schema");
+    schema_validate::save(&schema, &schema_path).unwrap();
+    assert!(fs::read_to_string(&schema_path).unwrap().starts_with("# schema-validate schema v1"));
+
+    let toml = format!(
+        "[[table]]\nname = \"users\"\nurl = \"file://./data/\"\nschema = \"{}\"",
+        schema_path.display()
+    );
+    let catalog = Catalog::from_toml(&toml).unwrap();
+    register_catalog(&mut ctx, &catalog).await.unwrap();
+    assert_eq!(ctx.table("users").unwrap().schema().fields().len(), schema.fields().len());
+    assert_eq!(count(&mut ctx, "users").await, count(&mut ctx, "inferred").await);
+
+    fs::write(&schema_path, "# schema-validate schema v1\n").unwrap();
+    let error = register_catalog(&mut ctx, &catalog).await.unwrap_err().to_string();
+    assert!(error.contains(&format!("invalid schema file '{}'", schema_path.display())), "{}", error);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,130 @@
+use datafusion::arrow::array::{Array, Int64Array, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::MemTable;
+use datafusion::prelude::*;
+use datafusion_parquet::export::{escape_partition_value, parse_copy, unescape_partition_value, ExportFormat};
+use datafusion_parquet::register_parquet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+fn error(sql: &str) -> String {
+    parse_copy(sql).unwrap().unwrap_err().to_string()
+}
+
+#[test]
+fn copy_statements_are_parsed() {
+    assert!(parse_copy("SELECT 1;").is_none());
+    assert!(parse_copy("COPYRIGHT").is_none());
+
+    let copy = parse_copy("copy (SELECT ')' AS a, \"b(\" FROM t) to './out''s.csv';").unwrap().unwrap();
+    assert_eq!(copy.query, "SELECT ')' AS a, \"b(\" FROM t");
+    assert_eq!(copy.path, PathBuf::from("./out's.csv"));
+    assert!(matches!(copy.options.format, ExportFormat::Csv));
+    assert!(copy.options.partition_by.is_empty());
+
+    let copy = parse_copy("COPY (SELECT * FROM t) TO 'out.ndjson'").unwrap().unwrap();
+    assert!(matches!(copy.options.format, ExportFormat::Ndjson));
+    let copy = parse_copy("COPY (SELECT * FROM t) TO 'out'").unwrap().unwrap();
+    assert!(matches!(copy.options.format, ExportFormat::Parquet(_)));
+}
+
+#[test]
+fn copy_options_are_parsed() {
+    let copy = parse_copy(
+        "COPY (SELECT * FROM t) TO './out/' \
+         (FORMAT Parquet, PARTITION_BY 'country, , gender', COMPRESSION zstd, MAX_ROW_GROUP_SIZE 100)",
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(copy.options.partition_by, vec!["country", "gender"]);
+    match copy.options.format {
+        ExportFormat::Parquet(properties) => assert_eq!(properties.max_row_group_size(), 100),
+        format => panic!("unexpected format {:?}", format),
+    }
+
+    // the format option wins over the extension
+    let copy = parse_copy("COPY (SELECT 1) TO 'out.parquet' (FORMAT csv, PARTITION_BY 'a''b')").unwrap().unwrap();
+    assert!(matches!(copy.options.format, ExportFormat::Csv));
+    assert_eq!(copy.options.partition_by, vec!["a'b"]);
+}
+
+#[test]
+fn invalid_copy_statements_are_rejected() {
+    assert!(error("COPY SELECT 1 TO 'out'").contains("expected COPY (<query>) TO '<path>'"));
+    assert!(error("COPY (SELECT 1) TO out").contains("expected COPY"));
+    assert!(error("COPY (SELECT 1) TO 'out' FORMAT csv").contains("expected COPY"));
+    assert!(error("COPY (SELECT 1 TO 'out'").contains("expected COPY"));
+    assert!(error("COPY (SELECT 1) TO 'out' (FORMAT)").contains("missing value for COPY option 'FORMAT'"));
+    assert!(error("COPY (SELECT 1) TO 'out' (PARTITION_BY 'a)").contains("expected COPY"));
+    assert!(error("COPY (SELECT 1) TO 'out' (FORMAT xml)").contains("invalid value 'xml' for option 'format'"));
+    assert!(error("COPY (SELECT 1) TO 'out' (COMPRESSION best)").contains("invalid value 'best' for option 'COMPRESSION'"));
+    assert!(error("COPY (SELECT 1) TO 'out' (ROW_GROUPS 2)").contains("unknown Parquet writer option 'ROW_GROUPS'"));
+    assert!(error("COPY (SELECT 1) TO 'out.csv' (COMPRESSION zstd)").contains("option 'COMPRESSION' is only valid for Parquet"));
+}
+
+#[test]
+fn partition_values_are_escaped_as_by_hive() {
+    assert_eq!(escape_partition_value("a/b=c:d"), "a%2Fb%3Dc%3Ad");
+    assert_eq!(escape_partition_value("50% off?"), "50%25 off%3F");
+    assert_eq!(escape_partition_value("tab\there"), "tab%09here");
+    assert_eq!(escape_partition_value("Zürich"), "Zürich");
+    for value in ["a/b=c:d", "50% off?", "tab\there", "Zürich", "%", "%+1"] {
+        assert_eq!(unescape_partition_value(&escape_partition_value(value)), value);
+    }
+    assert_eq!(unescape_partition_value("100%"), "100%");
+    assert_eq!(unescape_partition_value("%zz%+1"), "%zz%+1");
+}
+
+#[tokio::test]
+async fn partitioned_exports_read_back_the_same_values() {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("city", DataType::Utf8, true),
+        Field::new("sales", DataType::Int64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec![Some("a/b"), None, Some("a/b"), Some("50%"), Some("")])),
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5])),
+        ],
+    )
+    .unwrap();
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("sales", Arc::new(MemTable::try_new(schema, vec![vec![batch]]).unwrap()))
+        .unwrap();
+
+    let out = std::env::temp_dir().join(format!("datafusion-parquet-export-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&out);
+    let sql = format!("COPY (SELECT * FROM sales) TO '{}' (PARTITION_BY 'city')", out.display());
+    let copy = parse_copy(&sql).unwrap().unwrap();
+    assert_eq!(copy.execute(&mut ctx).await.unwrap(), 5);
+    let mut dirs: Vec<String> = fs::read_dir(&out)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    dirs.sort();
+    assert_eq!(dirs, vec!["city=50%25", "city=__HIVE_DEFAULT_PARTITION__", "city=a%2Fb"]);
+
+    register_parquet(&mut ctx, "exported", out.to_str().unwrap(), None).await.unwrap();
+    let batches = ctx
+        .sql("SELECT city, SUM(sales) FROM exported GROUP BY city ORDER BY city")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let cities = batches[0].column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let sums = batches[0].column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+    let rows: Vec<(Option<&str>, i64)> = (0..batches[0].num_rows())
+        .map(|row| (if cities.is_null(row) { None } else { Some(cities.value(row)) }, sums.value(row)))
+        .collect();
+    // empty values share the null partition, as in Hive
+    assert_eq!(rows, vec![(None, 7), (Some("50%"), 4), (Some("a/b"), 4)]);
+
+    // a second run would leave the partitions of the first next to its own
+    let error = copy.execute(&mut ctx).await.unwrap_err().to_string();
+    assert!(error.contains("is not empty"), "{}", error);
+    fs::remove_dir_all(&out).unwrap();
+}
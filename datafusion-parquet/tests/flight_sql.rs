@@ -0,0 +1,257 @@
+use arrow_flight::flight_descriptor::DescriptorType;
+use arrow_flight::flight_service_client::FlightServiceClient;
+use arrow_flight::utils::flight_data_to_arrow_batch;
+use arrow_flight::{Action, FlightDescriptor, FlightInfo};
+use datafusion::arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, ListArray, StringArray, StructArray,
+    UInt64Array,
+};
+use datafusion::arrow::datatypes::{DataType, Field, Int32Type, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::MemTable;
+use datafusion::prelude::*;
+use datafusion_parquet::flight_sql::protocol::*;
+use datafusion_parquet::flight_sql::{
+    FlightSqlServer, FlightSqlService, CLOSE_PREPARED_STATEMENT, CREATE_PREPARED_STATEMENT, MAX_PREPARED_STATEMENTS,
+};
+use datafusion_parquet::register_parquet;
+use futures::StreamExt;
+use std::convert::TryFrom;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Channel, Server};
+use tonic::Code;
+
+type Client = FlightServiceClient<Channel>;
+
+/// A client of a server over `userdata`
+async fn client() -> Client {
+    let mut ctx = ExecutionContext::new();
+    register_parquet(&mut ctx, "userdata", "./data/", None).await.unwrap();
+    serve(ctx).await
+}
+
+/// A client of a server over the tables of `ctx`, running on an ephemeral port of this process
+async fn serve(ctx: ExecutionContext) -> Client {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(FlightSqlServer::new(FlightSqlService::new(ctx)))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    FlightServiceClient::connect(format!("http://{}", addr)).await.unwrap()
+}
+
+fn descriptor(cmd: Vec<u8>) -> FlightDescriptor {
+    FlightDescriptor {
+        r#type: DescriptorType::Cmd as i32,
+        cmd,
+        path: vec![],
+    }
+}
+
+async fn flight_info(client: &mut Client, cmd: Vec<u8>) -> FlightInfo {
+    client.get_flight_info(descriptor(cmd)).await.unwrap().into_inner()
+}
+
+/// The batches of every endpoint of `info`
+async fn fetch(client: &mut Client, info: &FlightInfo) -> Vec<RecordBatch> {
+    let mut batches = vec![];
+    for endpoint in &info.endpoint {
+        let ticket = endpoint.ticket.clone().unwrap();
+        let mut stream = client.do_get(ticket).await.unwrap().into_inner();
+        let schema = Arc::new(Schema::try_from(&stream.next().await.unwrap().unwrap()).unwrap());
+        while let Some(data) = stream.next().await {
+            batches.push(flight_data_to_arrow_batch(&data.unwrap(), schema.clone(), &[]).unwrap());
+        }
+    }
+    batches
+}
+
+async fn query(client: &mut Client, sql: &str) -> Vec<RecordBatch> {
+    let info = flight_info(client, CommandStatementQuery { query: sql.to_owned() }.pack()).await;
+    fetch(client, &info).await
+}
+
+fn count(batches: &[RecordBatch]) -> u64 {
+    batches[0].column(0).as_any().downcast_ref::<UInt64Array>().unwrap().value(0)
+}
+
+#[tokio::test]
+async fn get_tables_lists_registered_tables() {
+    let mut client = client().await;
+    let command = CommandGetTables {
+        table_name_filter_pattern: Some("user%".to_owned()),
+        include_schema: true,
+        ..Default::default()
+    };
+    let info = flight_info(&mut client, command.pack()).await;
+    let batches = fetch(&mut client, &info).await;
+    assert_eq!(batches.len(), 1);
+    let batch = &batches[0];
+    assert_eq!(batch.num_rows(), 1);
+    let column = |index: usize| batch.column(index).as_any().downcast_ref::<StringArray>().unwrap().value(0).to_owned();
+    assert_eq!(column(0), "datafusion");
+    assert_eq!(column(1), "public");
+    assert_eq!(column(2), "userdata");
+    assert_eq!(column(3), "TABLE");
+    let schemas = batch.column(4).as_any().downcast_ref::<BinaryArray>().unwrap();
+    assert!(!schemas.value(0).is_empty());
+
+    let command = CommandGetTables {
+        table_name_filter_pattern: Some("orders".to_owned()),
+        ..Default::default()
+    };
+    let info = flight_info(&mut client, command.pack()).await;
+    let rows: usize = fetch(&mut client, &info).await.iter().map(|batch| batch.num_rows()).sum();
+    assert_eq!(rows, 0);
+}
+
+#[tokio::test]
+async fn get_schema_describes_query_results() {
+    let mut client = client().await;
+    let command = CommandStatementQuery {
+        query: "SELECT id, email FROM userdata".to_owned(),
+    };
+    let result = client.get_schema(descriptor(command.pack())).await.unwrap().into_inner();
+    let schema = Schema::try_from(&result).unwrap();
+    let names: Vec<&str> = schema.fields().iter().map(|field| field.name().as_str()).collect();
+    assert_eq!(names, vec!["id", "email"]);
+
+    let command = CommandStatementQuery {
+        query: "SELECT missing FROM userdata".to_owned(),
+    };
+    let status = client.get_schema(descriptor(command.pack())).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn statements_stream_results() {
+    let mut client = client().await;
+    assert_eq!(count(&query(&mut client, "SELECT COUNT(*) FROM userdata").await), 3000);
+
+    let batches = query(&mut client, "SELECT CAST(id AS BIGINT) AS id FROM userdata WHERE id <= 10 ORDER BY id").await;
+    let ids: Vec<i64> = batches
+        .iter()
+        .flat_map(|batch| {
+            let ids = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+            (0..ids.len()).map(|row| ids.value(row)).collect::<Vec<_>>()
+        })
+        .collect();
+    assert!(!ids.is_empty());
+    assert!(ids.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    let command = CommandStatementQuery {
+        query: "CREATE EXTERNAL TABLE t STORED AS PARQUET LOCATION './data/'".to_owned(),
+    };
+    let status = client.get_flight_info(descriptor(command.pack())).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn prepared_statements_run_until_closed() {
+    let mut client = client().await;
+    let request = ActionCreatePreparedStatementRequest {
+        query: "SELECT COUNT(*) FROM userdata WHERE id <= 100".to_owned(),
+    };
+    let action = Action {
+        r#type: CREATE_PREPARED_STATEMENT.to_owned(),
+        body: request.pack(),
+    };
+    let mut results = client.do_action(action).await.unwrap().into_inner();
+    let result = results.next().await.unwrap().unwrap();
+    let prepared = ActionCreatePreparedStatementResult::unpack(&result.body).unwrap();
+    assert!(!prepared.prepared_statement_handle.is_empty());
+    assert!(!prepared.dataset_schema.is_empty());
+
+    let command = CommandPreparedStatementQuery {
+        prepared_statement_handle: prepared.prepared_statement_handle.clone(),
+    };
+    let expected = count(&query(&mut client, "SELECT COUNT(*) FROM userdata WHERE id <= 100").await);
+    for _ in 0..2 {
+        let info = flight_info(&mut client, command.pack()).await;
+        assert_eq!(count(&fetch(&mut client, &info).await), expected);
+    }
+
+    let close = ActionClosePreparedStatementRequest {
+        prepared_statement_handle: prepared.prepared_statement_handle.clone(),
+    };
+    let action = Action {
+        r#type: CLOSE_PREPARED_STATEMENT.to_owned(),
+        body: close.pack(),
+    };
+    client.do_action(action).await.unwrap();
+    let status = client.get_flight_info(descriptor(command.pack())).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+async fn prepare(client: &mut Client, query: &str) -> Vec<u8> {
+    let action = Action {
+        r#type: CREATE_PREPARED_STATEMENT.to_owned(),
+        body: ActionCreatePreparedStatementRequest { query: query.to_owned() }.pack(),
+    };
+    let mut results = client.do_action(action).await.unwrap().into_inner();
+    let result = results.next().await.unwrap().unwrap();
+    ActionCreatePreparedStatementResult::unpack(&result.body)
+        .unwrap()
+        .prepared_statement_handle
+}
+
+#[tokio::test]
+async fn the_oldest_prepared_statements_are_closed_beyond_the_limit() {
+    let mut client = client().await;
+    let mut handles = vec![];
+    for _ in 0..=MAX_PREPARED_STATEMENTS {
+        handles.push(prepare(&mut client, "SELECT 1").await);
+    }
+    let command = |handle: &[u8]| {
+        CommandPreparedStatementQuery {
+            prepared_statement_handle: handle.to_vec(),
+        }
+        .pack()
+    };
+    let status = client.get_flight_info(descriptor(command(&handles[0]))).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    for handle in &handles[1..] {
+        assert!(client.get_flight_info(descriptor(command(handle))).await.is_ok());
+    }
+}
+
+#[tokio::test]
+async fn batches_arrive_as_they_were_produced() {
+    let items = vec![Some(vec![Some(1), None]), None, Some(vec![]), Some(vec![Some(4)])];
+    let x: ArrayRef = Arc::new(Float64Array::from(vec![Some(0.5), None, Some(2.0), Some(3.0)]));
+    let ok: ArrayRef = Arc::new(BooleanArray::from(vec![true, false, true, true]));
+    let point = StructArray::from(vec![
+        (Field::new("x", DataType::Float64, true), x),
+        (Field::new("ok", DataType::Boolean, false), ok),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+        Arc::new(StringArray::from(vec![Some("a"), None, Some(""), Some("dddd")])),
+        Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(items)),
+        Arc::new(point),
+    ];
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("items", columns[2].data_type().clone(), true),
+        Field::new("point", columns[3].data_type().clone(), false),
+    ]));
+    let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch.clone()]]).unwrap()))
+        .unwrap();
+    let mut client = serve(ctx).await;
+
+    assert_eq!(query(&mut client, "SELECT * FROM t").await, vec![batch]);
+    // a limit slices the batch, its buffers are sent whole with the length of the slice
+    let batches = query(&mut client, "SELECT id FROM t LIMIT 2").await;
+    let ids: Vec<i64> = batches
+        .iter()
+        .flat_map(|batch| batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap().values().to_vec())
+        .collect();
+    assert_eq!(ids, vec![1, 2]);
+}
@@ -0,0 +1,219 @@
+use async_trait::async_trait;
+use datafusion::arrow::array::{Float64Array, Int64Array};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::TableProvider;
+use datafusion::datasource::object_store::local::LocalFileSystem;
+use datafusion::datasource::object_store::{FileMetaStream, ListEntryStream, ObjectReader, ObjectStore, SizedFile};
+use datafusion::error::Result;
+use datafusion::parquet::arrow::ArrowWriter;
+use datafusion::prelude::*;
+use datafusion_parquet::hive::HiveTable;
+use datafusion_parquet::{parquet_listing_options, register_parquet};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// The local file system, remembering which files were opened
+#[derive(Debug)]
+struct CountingStore {
+    inner: LocalFileSystem,
+    opened: Mutex<HashSet<String>>,
+}
+
+impl CountingStore {
+    fn take_opened(&self) -> usize {
+        std::mem::take(&mut *self.opened.lock().unwrap()).len()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for CountingStore {
+    async fn list_file(&self, prefix: &str) -> Result<FileMetaStream> {
+        self.inner.list_file(prefix).await
+    }
+
+    async fn list_dir(&self, prefix: &str, delimiter: Option<String>) -> Result<ListEntryStream> {
+        self.inner.list_dir(prefix, delimiter).await
+    }
+
+    fn file_reader(&self, file: SizedFile) -> Result<Arc<dyn ObjectReader>> {
+        self.opened.lock().unwrap().insert(file.path.clone());
+        self.inner.file_reader(file)
+    }
+}
+
+const PARTITIONS: &[&str] = &[
+    "year=2020/day=2020-12-31/region=eu",
+    "year=2021/day=2021-01-01/region=eu",
+    "year=2021/day=2021-01-01/region=us",
+    "year=2021/day=2021-01-02/region=__HIVE_DEFAULT_PARTITION__",
+];
+
+/// Writes two rows to every partition of a fresh table directory
+fn write_fixture(name: &str) -> PathBuf {
+    write_partitions(name, PARTITIONS)
+}
+
+fn write_partitions(name: &str, partitions: &[&str]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("amount", DataType::Float64, false),
+    ]));
+    for (index, partition) in partitions.iter().enumerate() {
+        let directory = root.join(partition);
+        fs::create_dir_all(&directory).unwrap();
+        let id = index as i64 * 2;
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from(vec![id, id + 1])),
+                Arc::new(Float64Array::from(vec![1.5, 2.5])),
+            ],
+        )
+        .unwrap();
+        let file = File::create(directory.join("part-0.parquet")).unwrap();
+        let mut writer = ArrowWriter::try_new(file, schema.clone(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+    root
+}
+
+async fn hive_table(name: &str) -> (HiveTable, Arc<CountingStore>) {
+    let root = write_fixture(name);
+    let store = Arc::new(CountingStore {
+        inner: LocalFileSystem,
+        opened: Mutex::new(HashSet::new()),
+    });
+    let table = HiveTable::try_new(store.clone(), root.to_str().unwrap(), parquet_listing_options(), None)
+        .await
+        .unwrap();
+    // schema inference reads the footers of every file
+    store.take_opened();
+    (table, store)
+}
+
+async fn register(name: &str) -> (ExecutionContext, Arc<CountingStore>) {
+    let (table, store) = hive_table(name).await;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("sales", Arc::new(table)).unwrap();
+    (ctx, store)
+}
+
+async fn count(ctx: &mut ExecutionContext, sql: &str) -> usize {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    batches.iter().map(|batch| batch.num_rows()).sum()
+}
+
+#[tokio::test]
+async fn partition_columns_are_typed() {
+    let (table, _) = hive_table("hive-typed").await;
+    assert_eq!(table.partitions().len(), PARTITIONS.len());
+    let schema = table.schema();
+    let types: Vec<_> = schema.fields().iter().map(|field| (field.name().as_str(), field.data_type())).collect();
+    assert_eq!(
+        types,
+        vec![
+            ("id", &DataType::Int64),
+            ("amount", &DataType::Float64),
+            ("year", &DataType::Int64),
+            ("day", &DataType::Date32),
+            ("region", &DataType::Utf8),
+        ]
+    );
+    assert!(schema.field_with_name("region").unwrap().is_nullable());
+    assert!(!schema.field_with_name("year").unwrap().is_nullable());
+}
+
+#[tokio::test]
+async fn values_a_type_would_rewrite_stay_strings() {
+    let root = write_partitions("hive-spelling", &["batch=007/day=2021-1-5", "batch=8/day=2021-01-06"]);
+    let table = HiveTable::try_new(
+        Arc::new(LocalFileSystem),
+        root.to_str().unwrap(),
+        parquet_listing_options(),
+        None,
+    )
+    .await
+    .unwrap();
+    let schema = table.schema();
+    assert_eq!(schema.field_with_name("batch").unwrap().data_type(), &DataType::Utf8);
+    assert_eq!(schema.field_with_name("day").unwrap().data_type(), &DataType::Utf8);
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("sales", Arc::new(table)).unwrap();
+    assert_eq!(count(&mut ctx, "SELECT id FROM sales WHERE batch = '007'").await, 2);
+}
+
+#[tokio::test]
+async fn filters_on_partition_columns_prune_directories() {
+    let (mut ctx, store) = register("hive-pruning").await;
+
+    assert_eq!(count(&mut ctx, "SELECT id FROM sales WHERE year = 2021 AND region = 'eu'").await, 2);
+    assert_eq!(store.take_opened(), 1);
+
+    assert_eq!(count(&mut ctx, "SELECT id FROM sales WHERE year > 2020").await, 6);
+    assert_eq!(store.take_opened(), 3);
+
+    assert_eq!(
+        count(&mut ctx, "SELECT id FROM sales WHERE day = CAST('2021-01-01' AS DATE)").await,
+        4
+    );
+    assert_eq!(store.take_opened(), 2);
+
+    assert_eq!(count(&mut ctx, "SELECT region FROM sales WHERE region IS NULL").await, 2);
+    assert_eq!(store.take_opened(), 1);
+
+    assert_eq!(count(&mut ctx, "SELECT id FROM sales WHERE year = 1999").await, 0);
+    assert_eq!(store.take_opened(), 0);
+
+    // filters on file columns do not prune directories
+    assert_eq!(count(&mut ctx, "SELECT id FROM sales WHERE id = 3").await, 1);
+    assert_eq!(store.take_opened(), PARTITIONS.len());
+}
+
+#[tokio::test]
+async fn partition_values_are_queryable() {
+    let (mut ctx, _) = register("hive-values").await;
+    let batches = ctx
+        .sql("SELECT year, COUNT(*) FROM sales GROUP BY year ORDER BY year")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let years = batches[0].column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(years.values(), &[2020, 2021]);
+}
+
+#[tokio::test]
+async fn single_files_are_plain_tables() {
+    let mut ctx = ExecutionContext::new();
+    register_parquet(&mut ctx, "users", "./data/userdata1.parquet", None).await.unwrap();
+    let table = ctx.table("users").unwrap();
+    assert!(table.schema().field_with_unqualified_name("country").is_ok());
+    assert_eq!(count(&mut ctx, "SELECT id FROM users").await, 1000);
+}
+
+#[tokio::test]
+async fn plain_subdirectories_are_not_partitions() {
+    let root = write_fixture("hive-plain");
+    fs::rename(root.join("year=2020"), root.join("archive")).unwrap();
+    let store = Arc::new(CountingStore {
+        inner: LocalFileSystem,
+        opened: Mutex::new(HashSet::new()),
+    });
+    let table = HiveTable::try_new(store, root.to_str().unwrap(), parquet_listing_options(), None)
+        .await
+        .unwrap();
+    assert!(table.partition_fields().is_empty());
+
+    let mut ctx = ExecutionContext::new();
+    register_parquet(&mut ctx, "sales", root.to_str().unwrap(), None).await.unwrap();
+    assert_eq!(ctx.table("sales").unwrap().schema().fields().len(), 2);
+    assert_eq!(count(&mut ctx, "SELECT id FROM sales").await, 2 * PARTITIONS.len());
+}
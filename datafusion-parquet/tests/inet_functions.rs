@@ -0,0 +1,154 @@
+use datafusion::arrow::array::{Array, BooleanArray, Int64Array, StringArray, UInt64Array};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::prelude::*;
+use datafusion_parquet::inet::{self, cidr_contains, inet_parse, ip_to_int, ip_version, is_private_ip};
+use datafusion_parquet::register_parquet;
+
+async fn userdata() -> ExecutionContext {
+    let mut ctx = ExecutionContext::new();
+    inet::register_udfs(&mut ctx);
+    register_parquet(&mut ctx, "userdata", "./data/", None).await.unwrap();
+    ctx
+}
+
+async fn query(ctx: &mut ExecutionContext, sql: &str) -> Vec<RecordBatch> {
+    ctx.sql(sql).await.unwrap().collect().await.unwrap()
+}
+
+fn strings(batch: &RecordBatch, column: usize) -> Vec<Option<String>> {
+    let array = batch.column(column).as_any().downcast_ref::<StringArray>().unwrap();
+    (0..array.len())
+        .map(|row| if array.is_null(row) { None } else { Some(array.value(row).to_owned()) })
+        .collect()
+}
+
+fn ints(batch: &RecordBatch, column: usize) -> Vec<Option<i64>> {
+    let array = batch.column(column).as_any().downcast_ref::<Int64Array>().unwrap();
+    (0..array.len())
+        .map(|row| if array.is_null(row) { None } else { Some(array.value(row)) })
+        .collect()
+}
+
+fn bools(batch: &RecordBatch, column: usize) -> Vec<Option<bool>> {
+    let array = batch.column(column).as_any().downcast_ref::<BooleanArray>().unwrap();
+    (0..array.len())
+        .map(|row| if array.is_null(row) { None } else { Some(array.value(row)) })
+        .collect()
+}
+
+#[test]
+fn functions_handle_addresses_and_invalid_input() {
+    assert_eq!(inet_parse(" 10.0.0.001 ").as_deref(), Some("10.0.0.1"));
+    assert_eq!(inet_parse("2001:db8:0:0::1/64").as_deref(), Some("2001:db8::1/64"));
+    assert_eq!(inet_parse("10.0.0.1/33"), None);
+    assert_eq!(inet_parse("10.0.0"), None);
+
+    assert_eq!(cidr_contains("10.0.0.0/8", "10.47.111.83"), Some(true));
+    assert_eq!(cidr_contains("10.0.0.0/8", "11.0.0.1"), Some(false));
+    assert_eq!(cidr_contains("0.0.0.0/0", "1.2.3.4"), Some(true));
+    assert_eq!(cidr_contains("fe80::/10", "fe80::1"), Some(true));
+    assert_eq!(cidr_contains("10.0.0.0/8", "::1"), Some(false));
+    assert_eq!(cidr_contains("10.0.0.0/8", "not an ip"), None);
+    assert_eq!(cidr_contains("10.0.0.0/x", "10.0.0.1"), None);
+
+    assert_eq!(ip_to_int("1.2.3.4"), Some(16_909_060));
+    assert_eq!(ip_to_int("255.255.255.255"), Some(4_294_967_295));
+    assert_eq!(ip_to_int("::1"), None);
+
+    assert_eq!(is_private_ip("172.16.0.1"), Some(true));
+    assert_eq!(is_private_ip("172.32.0.1"), Some(false));
+    assert_eq!(is_private_ip("fd00::1"), Some(true));
+    assert_eq!(is_private_ip(""), None);
+
+    assert_eq!(ip_version("1.197.201.2"), Some(4));
+    assert_eq!(ip_version("::ffff:1.2.3.4"), Some(6));
+    assert_eq!(ip_version("1.2.3.4.5"), None);
+}
+
+#[tokio::test]
+async fn invalid_input_is_null_in_sql() {
+    let mut ctx = userdata().await;
+    let batches = query(
+        &mut ctx,
+        "SELECT inet_parse('not an ip'), cidr_contains('10.0.0.0/33', '10.0.0.1'), ip_to_int('::1'), \
+         is_private_ip(''), ip_version(CAST(NULL AS VARCHAR))",
+    )
+    .await;
+    let batch = &batches[0];
+    assert_eq!(batch.num_rows(), 1);
+    for column in batch.columns() {
+        assert!(column.is_null(0));
+    }
+}
+
+#[tokio::test]
+async fn projections_over_userdata() {
+    let mut ctx = userdata().await;
+    let batches = query(
+        &mut ctx,
+        "SELECT ip_address, inet_parse(ip_address), ip_to_int(ip_address), is_private_ip(ip_address), \
+         ip_version(ip_address) FROM userdata",
+    )
+    .await;
+
+    let mut rows = 0;
+    let mut private = 0;
+    for batch in &batches {
+        let addresses = strings(batch, 0);
+        let parsed = strings(batch, 1);
+        let numbers = ints(batch, 2);
+        let privates = bools(batch, 3);
+        let versions = ints(batch, 4);
+        for (row, address) in addresses.iter().enumerate() {
+            let address = match address {
+                Some(address) => address.as_str(),
+                None => {
+                    assert_eq!(parsed[row], None);
+                    continue;
+                }
+            };
+            assert_eq!(parsed[row], inet_parse(address), "{}", address);
+            assert_eq!(numbers[row], ip_to_int(address), "{}", address);
+            assert_eq!(privates[row], is_private_ip(address), "{}", address);
+            assert_eq!(versions[row], ip_version(address), "{}", address);
+            private += usize::from(privates[row] == Some(true));
+        }
+        rows += batch.num_rows();
+    }
+    assert_eq!(rows, 3000);
+    assert!(private > 0);
+}
+
+#[tokio::test]
+async fn subnet_filters_over_userdata() {
+    let mut ctx = userdata().await;
+    let all: Vec<String> = query(&mut ctx, "SELECT ip_address FROM userdata")
+        .await
+        .iter()
+        .flat_map(|batch| strings(batch, 0))
+        .flatten()
+        .collect();
+    let expected = all.iter().filter(|address| address.starts_with("10.")).count();
+    assert!(expected > 0);
+
+    let matching: Vec<String> = query(
+        &mut ctx,
+        "SELECT ip_address FROM userdata WHERE cidr_contains('10.0.0.0/8', ip_address)",
+    )
+    .await
+    .iter()
+    .flat_map(|batch| strings(batch, 0))
+    .flatten()
+    .collect();
+    assert_eq!(matching.len(), expected);
+    assert!(matching.iter().all(|address| address.starts_with("10.")));
+
+    let batches = query(
+        &mut ctx,
+        "SELECT COUNT(*) FROM userdata WHERE is_private_ip(ip_address) AND ip_version(ip_address) = 4 \
+         AND ip_to_int(ip_address) BETWEEN ip_to_int('10.0.0.0') AND ip_to_int('10.255.255.255')",
+    )
+    .await;
+    let count = batches[0].column(0).as_any().downcast_ref::<UInt64Array>().unwrap();
+    assert_eq!(count.value(0) as usize, expected);
+}
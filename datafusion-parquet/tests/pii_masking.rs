@@ -0,0 +1,121 @@
+use chrono::NaiveDate;
+use datafusion::arrow::array::{StringArray, UInt64Array};
+use datafusion::prelude::*;
+use datafusion_parquet::error::PolicyError;
+use datafusion_parquet::pii::{self, age_bucket, mask_cc, mask_email};
+use datafusion_parquet::policy::{apply_policy, Policy};
+use datafusion_parquet::register_parquet;
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd(year, month, day)
+}
+
+async fn users(policy: Option<&str>) -> datafusion::error::Result<ExecutionContext> {
+    let mut ctx = ExecutionContext::new();
+    pii::register_udfs(&mut ctx);
+    register_parquet(&mut ctx, "users", "./data/userdata1.parquet", None).await?;
+    if let Some(policy) = policy {
+        let policy = Policy::from_toml(policy)?;
+        apply_policy(&mut ctx, &policy.roles[0])?;
+    }
+    Ok(ctx)
+}
+
+async fn first_string(ctx: &mut ExecutionContext, sql: &str) -> String {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    let array = batches[0].column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    array.value(0).to_owned()
+}
+
+async fn count(ctx: &mut ExecutionContext, sql: &str) -> u64 {
+    let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+    batches[0].column(0).as_any().downcast_ref::<UInt64Array>().unwrap().value(0)
+}
+
+#[test]
+fn short_card_numbers_are_masked_completely() {
+    assert_eq!(mask_cc("4017-9562-1234-5678"), "****-****-****-5678");
+    assert_eq!(mask_cc("12345"), "*2345");
+    assert_eq!(mask_cc("1234"), "****");
+    assert_eq!(mask_cc("12-3"), "**-*");
+    assert_eq!(mask_cc(""), "");
+    assert_eq!(mask_cc("n/a"), "n/a");
+}
+
+#[test]
+fn ages_count_leap_day_birthdays_from_march() {
+    // a leap day birthday is reached on March 1st in other years
+    assert_eq!(age_bucket("2/29/2000", date(2010, 2, 28)).as_deref(), Some("0-9"));
+    assert_eq!(age_bucket("2/29/2000", date(2010, 3, 1)).as_deref(), Some("10-19"));
+    assert_eq!(age_bucket("2000-02-29", date(2020, 2, 29)).as_deref(), Some("20-29"));
+    assert_eq!(age_bucket("2/30/2000", date(2010, 3, 1)), None);
+}
+
+#[test]
+fn future_birthdates_have_no_age() {
+    assert_eq!(age_bucket("1/1/2031", date(2030, 12, 31)), None);
+    assert_eq!(age_bucket("12/31/2030", date(2030, 12, 31)).as_deref(), Some("0-9"));
+    assert_eq!(age_bucket("", date(2030, 12, 31)), None);
+}
+
+#[test]
+fn every_policy_problem_is_reported() {
+    let error = Policy::from_toml(
+        r#"
+        [[role]]
+        name = ""
+        [role.masks]
+        "" = "mask_email"
+
+        [[role]]
+        name = "analyst"
+        [role.masks]
+        email = "mask_mail"
+        Email = "mask_email"
+
+        [[role]]
+        name = "analyst"
+        "#,
+    )
+    .unwrap_err();
+    let problems = match error {
+        PolicyError::Invalid(problems) => problems,
+        error => panic!("unexpected error {}", error),
+    };
+    assert_eq!(
+        problems.lines().collect::<Vec<_>>(),
+        vec![
+            "role #1: name must not be empty",
+            "role #1: masks must not contain empty column names",
+            "role 'analyst': column 'email' is masked more than once",
+            "role 'analyst': unknown mask 'mask_mail' for column 'email', expected one of \
+             mask_email, mask_cc, hash_sha256, anonymize_ip, age_bucket",
+            "role 'analyst': name is declared more than once",
+        ]
+    );
+    assert!(matches!(Policy::from_toml("[[role]]\nname = 1"), Err(PolicyError::Parse(_))));
+}
+
+#[tokio::test]
+async fn filters_only_see_masked_values() {
+    let mut raw = users(None).await.unwrap();
+    let email = first_string(&mut raw, "SELECT email FROM users WHERE email <> '' LIMIT 1").await;
+
+    let mut masked = users(Some("[[role]]\nname = \"analyst\"\n[role.masks]\nEMAIL = \"mask_email\"")).await.unwrap();
+    let sql = |value: &str| format!("SELECT COUNT(*) FROM users WHERE email = '{}'", value);
+    assert_eq!(count(&mut masked, &sql(&email)).await, 0);
+    assert!(count(&mut masked, &sql(&mask_email(&email))).await >= 1);
+    assert_eq!(
+        first_string(&mut masked, &format!("SELECT email FROM users WHERE email = '{}'", mask_email(&email))).await,
+        mask_email(&email)
+    );
+}
+
+#[tokio::test]
+async fn roles_masking_unknown_columns_are_rejected() {
+    let error = users(Some("[[role]]\nname = \"analyst\"\n[role.masks]\nemial = \"mask_email\""))
+        .await
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("role 'analyst' masks column 'emial', which no table has"));
+}
@@ -0,0 +1,167 @@
+use datafusion::arrow::array::{Int32Array, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::ipc::reader::StreamReader;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::prelude::*;
+use datafusion_parquet::query_server::{Encoder, OutputFormat, QueryService, ServerOptions, MAX_QUERY_SIZE};
+use datafusion_parquet::{register_parquet, Args};
+use hyper::header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE};
+use hyper::{Body, Request, Response, StatusCode};
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn service(max_concurrent: usize) -> Arc<QueryService> {
+    let mut ctx = ExecutionContext::new();
+    register_parquet(&mut ctx, "users", "./data/userdata1.parquet", None).await.unwrap();
+    Arc::new(QueryService::new(ctx, Duration::from_secs(30), max_concurrent))
+}
+
+fn post(uri: &str, body: impl Into<Body>) -> Request<Body> {
+    Request::post(uri).body(body.into()).unwrap()
+}
+
+async fn text(response: Response<Body>) -> String {
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+fn options(args: &[&str]) -> datafusion::error::Result<ServerOptions> {
+    let args = Args::parse(
+        args.iter().map(|arg| arg.to_string()),
+        &["--listen", "--timeout", "--max-concurrent"],
+    )?;
+    ServerOptions::from_args(&args)
+}
+
+fn batch() -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    let columns = vec![
+        Arc::new(Int32Array::from(vec![1, 2])) as _,
+        Arc::new(StringArray::from(vec![Some("a,b"), None])) as _,
+    ];
+    RecordBatch::try_new(schema, columns).unwrap()
+}
+
+#[test]
+fn options_serve_localhost_without_a_policy() {
+    let defaults = options(&[]).unwrap();
+    assert_eq!(defaults.listen, ([127, 0, 0, 1], 8080).into());
+    assert_eq!(defaults.timeout, Duration::from_secs(30));
+    assert_eq!(defaults.max_concurrent, 4);
+
+    let parsed = options(&["--listen", "[::1]:9000", "--timeout", "5", "--max-concurrent", "2"]).unwrap();
+    assert_eq!(parsed.listen, "[::1]:9000".parse().unwrap());
+    assert_eq!(parsed.timeout, Duration::from_secs(5));
+    assert_eq!(parsed.max_concurrent, 2);
+
+    let error = |args: &[&str]| options(args).unwrap_err().to_string();
+    assert!(error(&["--listen", "localhost"]).contains("invalid address 'localhost'"));
+    assert!(error(&["--timeout", "0"]).contains("invalid timeout '0'"));
+    assert!(error(&["--max-concurrent", "none"]).contains("invalid concurrency limit 'none'"));
+    assert!(error(&["--listen", "0.0.0.0:8080"]).contains("listening on 0.0.0.0:8080 needs --policy and --role"));
+    let lan = options(&["--listen", "0.0.0.0:8080", "--policy", "policy.toml", "--role", "analyst"]).unwrap();
+    assert_eq!(lan.listen, ([0, 0, 0, 0], 8080).into());
+}
+
+#[test]
+fn formats_are_negotiated() {
+    assert_eq!(OutputFormat::from_name("json"), Some(OutputFormat::Ndjson));
+    assert_eq!(OutputFormat::from_name("xml"), None);
+    assert_eq!(OutputFormat::from_accept("text/html, text/csv;q=0.9"), Some(OutputFormat::Csv));
+    assert_eq!(
+        OutputFormat::from_accept("application/vnd.apache.arrow.stream"),
+        Some(OutputFormat::Arrow)
+    );
+    assert_eq!(OutputFormat::from_accept("*/*"), None);
+}
+
+#[test]
+fn encoders_write_complete_documents() {
+    let batch = batch();
+
+    let mut csv = Encoder::new(OutputFormat::Csv, batch.schema()).unwrap();
+    let mut out = csv.start();
+    out.extend(csv.encode(&batch).unwrap());
+    out.extend(csv.encode(&batch).unwrap());
+    out.extend(csv.finish().unwrap());
+    assert_eq!(String::from_utf8(out).unwrap(), "id,name\n1,\"a,b\"\n2,\n1,\"a,b\"\n2,\n");
+
+    // an empty result still has its header
+    let mut csv = Encoder::new(OutputFormat::Csv, batch.schema()).unwrap();
+    assert_eq!(String::from_utf8(csv.finish().unwrap()).unwrap(), "id,name\n");
+
+    let mut ndjson = Encoder::new(OutputFormat::Ndjson, batch.schema()).unwrap();
+    let out = ndjson.encode(&batch).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "{\"id\":1,\"name\":\"a,b\"}\n{\"id\":2}\n");
+
+    let mut arrow = Encoder::new(OutputFormat::Arrow, batch.schema()).unwrap();
+    let mut out = arrow.start();
+    out.extend(arrow.encode(&batch).unwrap());
+    out.extend(arrow.finish().unwrap());
+    let batches: Vec<RecordBatch> = StreamReader::try_new(Cursor::new(out))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(batches, vec![batch]);
+}
+
+#[tokio::test]
+async fn queries_are_streamed_in_the_requested_format() {
+    let service = service(4).await;
+    let sql = "SELECT id, id * 2 AS double FROM users ORDER BY id LIMIT 2";
+
+    let response = service.clone().handle(post("/query?format=csv", sql)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "text/csv");
+    assert_eq!(text(response).await, "id,double\n1,2\n2,4\n");
+
+    let mut request = post("/query", sql);
+    request.headers_mut().insert(ACCEPT, "application/x-ndjson".parse().unwrap());
+    let response = service.clone().handle(request).await;
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/x-ndjson");
+    assert_eq!(text(response).await.lines().count(), 2);
+
+    let response = service.handle(Request::get("/health").body(Body::empty()).unwrap()).await;
+    let health: serde_json::Value = serde_json::from_str(&text(response).await).unwrap();
+    assert_eq!(health, serde_json::json!({ "status": "ok", "running": 0, "max_concurrent": 4 }));
+}
+
+#[tokio::test]
+async fn bad_queries_are_rejected() {
+    let service = service(4).await;
+    let status = |request| {
+        let service = service.clone();
+        async move { service.handle(request).await.status() }
+    };
+
+    assert_eq!(status(post("/query?format=xml", "SELECT 1")).await, StatusCode::BAD_REQUEST);
+    assert_eq!(status(post("/query", "SELECT nope FROM users")).await, StatusCode::BAD_REQUEST);
+    assert_eq!(status(post("/query", vec![0xff, 0xfe])).await, StatusCode::BAD_REQUEST);
+    let create = "CREATE EXTERNAL TABLE t STORED AS PARQUET LOCATION '/etc/'";
+    assert_eq!(status(post("/query", create)).await, StatusCode::BAD_REQUEST);
+    assert_eq!(status(Request::get("/query").body(Body::empty()).unwrap()).await, StatusCode::METHOD_NOT_ALLOWED);
+
+    // refused by its declared length, before the body is read
+    let mut request = post("/query", "SELECT 1");
+    request.headers_mut().insert(CONTENT_LENGTH, (MAX_QUERY_SIZE + 1).into());
+    assert_eq!(status(request).await, StatusCode::PAYLOAD_TOO_LARGE);
+    // and without one, while it is read
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        while sender.send_data(vec![b' '; 64 * 1024].into()).await.is_ok() {}
+    });
+    assert_eq!(status(post("/query", body)).await, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn queries_beyond_the_limit_are_refused() {
+    // no query may run at all
+    let service = service(0).await;
+    let response = service.handle(post("/query", "SELECT 1")).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(text(response).await.contains("0 queries are running, try again later"));
+}
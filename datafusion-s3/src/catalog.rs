@@ -0,0 +1,215 @@
+//! A declarative catalog of listing tables, loaded from a TOML file:
+//!
+//! ```toml
+//! [[table]]
+//! name = "tbl"
+//! url = "s3://data/"              # s3://<bucket>/<prefix> or file://<path>
+//! format = "parquet"              # parquet, csv or json, default parquet
+//! file_extension = ".parquet"     # default by format
+//! partition_cols = ["year"]       # default discovered from <key>=<value> directories
+//! collect_stat = true             # default true
+//! target_partitions = 4           # default the number of CPUs
+//! schema = "./tbl.json"           # stored schema, inferred from the files when missing
+//! ```
+//!
+//! The entries, their defaults and their validation are those of the `datafusion-parquet`
+//! catalog, except that stored schemas are only read as serde JSON.
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::Schema;
+use datafusion::datasource::file_format::csv::CsvFormat;
+use datafusion::datasource::file_format::json::JsonFormat;
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::file_format::FileFormat;
+use datafusion::datasource::listing::{ListingOptions, ListingTable};
+use datafusion::datasource::object_store::local::LocalFileSystem;
+use datafusion::datasource::object_store::ObjectStore;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::prelude::ExecutionContext;
+use serde::Deserialize;
+
+use crate::error::CatalogError;
+use crate::partitions::discover_partition_cols;
+use crate::S3FileSystem;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TableEntry {
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_format")]
+    pub format: String,
+    pub file_extension: Option<String>,
+    #[serde(default)]
+    pub partition_cols: Vec<String>,
+    #[serde(default = "default_collect_stat")]
+    pub collect_stat: bool,
+    #[serde(default = "num_cpus::get")]
+    pub target_partitions: usize,
+    /// Path of a stored schema, serialized as serde JSON
+    pub schema: Option<String>,
+}
+
+fn default_format() -> String {
+    "parquet".to_owned()
+}
+
+fn default_collect_stat() -> bool {
+    true
+}
+
+impl TableEntry {
+    fn file_format(&self) -> Option<(Arc<dyn FileFormat>, &'static str)> {
+        match self.format.as_str() {
+            // pruning with statistics is enabled by default
+            "parquet" => Some((Arc::new(ParquetFormat::default()), ".parquet")),
+            "csv" => Some((Arc::new(CsvFormat::default()), ".csv")),
+            "json" => Some((Arc::new(JsonFormat::default()), ".json")),
+            _ => None,
+        }
+    }
+
+    /// Listing options of a validated entry
+    pub fn listing_options(&self) -> ListingOptions {
+        let (format, extension) = self.file_format().expect("format is validated");
+        ListingOptions {
+            format,
+            collect_stat: self.collect_stat,
+            file_extension: self.file_extension.clone().unwrap_or_else(|| extension.to_owned()),
+            target_partitions: self.target_partitions,
+            table_partition_cols: self.partition_cols.clone(),
+        }
+    }
+
+    /// Every problem of this entry, without the table name
+    fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let is_identifier = self.name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_identifier {
+            problems.push("name must be a plain identifier, e.g. my_table".to_owned());
+        }
+        match self.url.split_once("://") {
+            Some(("file", path)) if !path.is_empty() => {}
+            Some(("s3", path)) if !path.is_empty() && !path.starts_with('/') => {}
+            Some(("s3", _)) => problems.push(format!(
+                "url '{}' must name a bucket, e.g. s3://bucket/prefix/",
+                self.url
+            )),
+            _ => problems.push(format!("url '{}' must start with file:// or s3://", self.url)),
+        }
+        if self.file_format().is_none() {
+            problems.push(format!(
+                "unknown format '{}', expected parquet, csv or json",
+                self.format
+            ));
+        }
+        if self.file_extension.as_deref() == Some("") {
+            problems.push("file_extension must not be empty".to_owned());
+        }
+        if self.target_partitions == 0 {
+            problems.push("target_partitions must be at least 1".to_owned());
+        }
+        let mut columns = HashSet::new();
+        for column in &self.partition_cols {
+            if column.is_empty() {
+                problems.push("partition_cols must not contain empty names".to_owned());
+            } else if !columns.insert(column) {
+                problems.push(format!("partition column '{}' is listed twice", column));
+            }
+        }
+        if let Some(schema) = &self.schema {
+            if !Path::new(schema).is_file() {
+                problems.push(format!("schema file '{}' does not exist", schema));
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Catalog {
+    #[serde(default, rename = "table")]
+    pub tables: Vec<TableEntry>,
+}
+
+impl Catalog {
+    /// Reads and validates the catalog at `path`
+    pub fn load<P: AsRef<Path>>(path: P) -> std::result::Result<Self, CatalogError> {
+        let toml = fs::read_to_string(path.as_ref())
+            .map_err(|e| CatalogError::Io(format!("{}: {}", path.as_ref().display(), e)))?;
+        Self::from_toml(&toml)
+    }
+
+    /// Parses and validates a catalog
+    pub fn from_toml(toml: &str) -> std::result::Result<Self, CatalogError> {
+        let catalog: Catalog = toml::from_str(toml).map_err(|e| CatalogError::Parse(e.to_string()))?;
+        catalog.validate()?;
+        Ok(catalog)
+    }
+
+    /// Checks every entry and reports all problems at once
+    pub fn validate(&self) -> std::result::Result<(), CatalogError> {
+        let mut problems = vec![];
+        let mut names = HashSet::new();
+        for (index, table) in self.tables.iter().enumerate() {
+            let label = if table.name.is_empty() {
+                format!("table #{}", index + 1)
+            } else {
+                format!("table '{}'", table.name)
+            };
+            if !table.name.is_empty() && !names.insert(&table.name) {
+                problems.push(format!("{}: name is declared more than once", label));
+            }
+            for problem in table.problems() {
+                problems.push(format!("{}: {}", label, problem));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(CatalogError::Invalid(problems.join("\n")))
+        }
+    }
+}
+
+/// Registers every table of `catalog` in `ctx`, reading `s3://` tables through `s3`
+pub async fn register_catalog(
+    ctx: &mut ExecutionContext,
+    catalog: &Catalog,
+    s3: Arc<S3FileSystem>,
+) -> Result<()> {
+    for table in &catalog.tables {
+        let (object_store, path): (Arc<dyn ObjectStore>, &str) = match table.url.split_once("://") {
+            Some(("s3", path)) => (s3.clone(), path),
+            Some((_, path)) => (Arc::new(LocalFileSystem), path),
+            None => unreachable!("url is validated"),
+        };
+        let mut options = table.listing_options();
+        if options.table_partition_cols.is_empty() {
+            options.table_partition_cols =
+                discover_partition_cols(object_store.as_ref(), path, &options.file_extension).await?;
+        }
+        let schema = match &table.schema {
+            Some(schema) => Arc::new(read_schema(schema)?),
+            None => options.infer_schema(object_store.clone(), path).await?,
+        };
+        let listing_table = ListingTable::new(object_store, path.to_owned(), schema, options);
+        ctx.register_table(table.name.as_str(), Arc::new(listing_table))
+            .map_err(|e| DataFusionError::Plan(format!("table '{}': {}", table.name, e)))?;
+    }
+    Ok(())
+}
+
+/// Reads an Arrow schema serialized as serde JSON
+fn read_schema(path: &str) -> Result<Schema> {
+    let file = File::open(path)?;
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| DataFusionError::Plan(format!("invalid schema file '{}': {}", path, e)))
+}
@@ -1,3 +1,4 @@
+use datafusion::error::DataFusionError;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
@@ -21,3 +22,33 @@ impl Display for S3Error {
 }
 
 impl Error for S3Error {}
+
+/// Errors while loading a table catalog, as in `datafusion-parquet`
+#[derive(Debug, PartialEq)]
+pub enum CatalogError {
+    /// Wrapper for errors while reading the catalog file
+    Io(String),
+    /// Returned when the catalog is not valid TOML or has unknown keys
+    Parse(String),
+    /// Returned when entries are not valid, with one line per problem
+    Invalid(String),
+}
+
+impl Display for CatalogError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatalogError::Io(desc) => write!(f, "Cannot read catalog: {}", desc),
+            CatalogError::Parse(desc) => write!(f, "Cannot parse catalog: {}", desc),
+            CatalogError::Invalid(desc) => write!(f, "Invalid catalog:\n{}", desc),
+        }
+    }
+}
+
+impl Error for CatalogError {}
+
+/// An unusable catalog is a planning error of the binary that loads it
+impl From<CatalogError> for DataFusionError {
+    fn from(err: CatalogError) -> Self {
+        DataFusionError::Plan(err.to_string())
+    }
+}
@@ -43,8 +43,12 @@ use datafusion::prelude::ExecutionContext;
 use datafusion::datasource::file_format::parquet::ParquetFormat;
 use bytes::Buf;
 
+pub mod catalog;
 pub mod error;
+pub mod partitions;
+use crate::catalog::{register_catalog, Catalog};
 use crate::error::S3Error;
+use crate::partitions::discover_partition_cols;
 
 /// new_client creates a new aws_sdk_s3::Client
 /// this uses aws_config::load_from_env() as a base config then allows users to override specific settings if required
@@ -324,32 +328,42 @@ async fn main() -> Result<()> {
         .await,
     );
 
-    let filename = "data/";
+    let mut ctx = ExecutionContext::new();
 
-    let listing_options = ListingOptions {
-        format: Arc::new(ParquetFormat::default()),
-        collect_stat: true,
-        file_extension: "parquet".to_owned(),
-        target_partitions: num_cpus::get(),
-        table_partition_cols: vec![],   
-    };
+    // usage: datafusion-s3 [<catalog.toml> [<sql>]]
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(path) = args.first() {
+        let catalog = Catalog::load(path)?;
+        register_catalog(&mut ctx, &catalog, s3_file_system).await?;
+    } else {
+        let filename = "data/";
+        let file_extension = "parquet";
+
+        let listing_options = ListingOptions {
+            format: Arc::new(ParquetFormat::default()),
+            collect_stat: true,
+            file_extension: file_extension.to_owned(),
+            target_partitions: num_cpus::get(),
+            table_partition_cols: discover_partition_cols(s3_file_system.as_ref(), filename, file_extension)
+                .await?,
+        };
 
-    let resolved_schema = listing_options
-        .infer_schema(s3_file_system.clone(), filename)
-        .await?;
+        let resolved_schema = listing_options
+            .infer_schema(s3_file_system.clone(), filename)
+            .await?;
 
-    let table = ListingTable::new(
-        s3_file_system,
-        filename.to_owned(),
-        resolved_schema,
-        listing_options,
-    );
+        let table = ListingTable::new(
+            s3_file_system,
+            filename.to_owned(),
+            resolved_schema,
+            listing_options,
+        );
 
-    let mut ctx = ExecutionContext::new();
-
-    ctx.register_table("tbl", Arc::new(table)).unwrap();
+        ctx.register_table("tbl", Arc::new(table)).unwrap();
+    }
 
-    let batches = ctx.sql("SELECT * FROM tbl").await?;
+    let sql = args.get(1).map_or("SELECT * FROM tbl", |sql| sql.as_str());
+    let batches = ctx.sql(sql).await?;
     batches.show().await?;
 
     Ok(())
@@ -0,0 +1,65 @@
+//! Discovery of Hive-style partition columns from `<key>=<value>` directory names.
+//!
+//! The discovered names are declared as the partition columns of a listing table, which
+//! exposes them as string columns and prunes the directories that filters on them exclude.
+
+use std::collections::BTreeSet;
+
+use datafusion::datasource::object_store::ObjectStore;
+use datafusion::error::{DataFusionError, Result};
+use futures::StreamExt;
+
+/// Names of the partition columns below `prefix`, in directory order.
+///
+/// Files directly below `prefix` mean the table is not partitioned. Directories that are not
+/// `<key>=<value>` pairs, or that do not agree on the keys, are an error.
+pub async fn discover_partition_cols(
+    object_store: &dyn ObjectStore,
+    prefix: &str,
+    file_extension: &str,
+) -> Result<Vec<String>> {
+    let root = prefix.trim_end_matches('/');
+    let mut layouts = BTreeSet::new();
+    let mut files = object_store.list_file_with_suffix(prefix, file_extension).await?;
+    while let Some(file) = files.next().await {
+        let file = file?;
+        let path = file.path();
+        let relative = path.strip_prefix(root).unwrap_or(path).trim_start_matches('/');
+        let directories = match relative.rsplit_once('/') {
+            Some((directories, _)) => directories,
+            None => "",
+        };
+        let keys = directories
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| match segment.split_once('=') {
+                Some((key, _)) if !key.is_empty() => Ok(key.to_owned()),
+                _ => Err(DataFusionError::Plan(format!(
+                    "'{}' in '{}' is not a <key>=<value> directory",
+                    segment, path
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        layouts.insert(keys);
+    }
+
+    let mut layouts = layouts.into_iter();
+    let keys = layouts.next().unwrap_or_default();
+    match layouts.next() {
+        None => Ok(keys),
+        Some(other) => Err(DataFusionError::Plan(format!(
+            "inconsistent partition directories below '{}': {} and {}",
+            prefix,
+            display_keys(&keys),
+            display_keys(&other)
+        ))),
+    }
+}
+
+fn display_keys(keys: &[String]) -> String {
+    if keys.is_empty() {
+        "no partitions".to_owned()
+    } else {
+        keys.join("/")
+    }
+}
@@ -0,0 +1,235 @@
+//! Rust structs with serde derives generated from a stored schema.
+//!
+//! Nullable fields become `Option`s, lists become `Vec`s and every struct field gets its own
+//! struct named after the parent and the field. The generated module also contains a
+//! `schema()` function and a `to_record_batch` helper, which serializes the records as
+//! NDJSON and reads them back with the Arrow JSON reader, so nested values are converted by
+//! the same code that reads the input files.
+//!
+//! Dates and timestamps are generated as `i64` in the unit of the Arrow type. Types the JSON
+//! reader cannot produce, such as binary and decimal, are rejected.
+
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+
+use crate::error::{Result, SchemaError};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield", "self", "super",
+];
+
+/// Generates a Rust module for records of `schema`, with `name` as the top-level struct
+pub fn rust_structs(schema: &Schema, name: &str) -> Result<String> {
+    let mut structs = vec![];
+    generate_struct(&pascal_case(name), schema.fields(), &mut structs)?;
+
+    let mut code = String::new();
+    code.push_str("// Generated by schema-validate, do not edit.\n\n");
+    code.push_str("use std::io::Cursor;\n");
+    code.push_str("use std::sync::Arc;\n\n");
+    code.push_str("use arrow::datatypes::{DataType, Field, Schema};\n");
+    code.push_str("use arrow::error::{ArrowError, Result};\n");
+    code.push_str("use arrow::json::Reader;\n");
+    code.push_str("use arrow::record_batch::RecordBatch;\n");
+    code.push_str("use serde::{Deserialize, Serialize};\n");
+
+    // the top-level struct comes first, followed by the nested structs
+    for definition in &structs {
+        code.push('\n');
+        code.push_str(definition);
+    }
+
+    code.push_str("\n/// The Arrow schema the records were generated from\n");
+    code.push_str("pub fn schema() -> Schema {\n");
+    code.push_str("    Schema::new(vec![\n");
+    for field in schema.fields() {
+        code.push_str(&format!("        {},\n", field_expr(field)));
+    }
+    code.push_str("    ])\n");
+    code.push_str("}\n");
+
+    code.push_str(&format!(
+        "
+/// Builds a record batch with [`schema`] from `records`
+pub fn to_record_batch(records: &[{name}]) -> Result<RecordBatch> {{
+    let mut ndjson = vec![];
+    for record in records {{
+        serde_json::to_writer(&mut ndjson, record).map_err(|e| ArrowError::JsonError(e.to_string()))?;
+        ndjson.push(b'\\n');
+    }}
+    let schema = Arc::new(schema());
+    let mut reader = Reader::new(Cursor::new(ndjson), schema.clone(), records.len().max(1), None);
+    match reader.next()? {{
+        Some(batch) => Ok(batch),
+        None => Ok(RecordBatch::new_empty(schema)),
+    }}
+}}
+",
+        name = pascal_case(name)
+    ));
+    Ok(code)
+}
+
+/// Pushes the definition of struct `name`, followed by the definitions of its nested structs
+fn generate_struct(name: &str, fields: &[Field], structs: &mut Vec<String>) -> Result<()> {
+    let mut definition = String::new();
+    definition.push_str("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n");
+    definition.push_str(&format!("pub struct {} {{\n", name));
+
+    let mut nested = vec![];
+    for field in fields {
+        let ident = identifier(field.name());
+        let nested_name = format!("{}{}", name, pascal_case(field.name()));
+        let rust_type = field_type(field, &nested_name, &mut nested)?;
+        if ident != *field.name() {
+            definition.push_str(&format!("    #[serde(rename = {:?})]\n", field.name()));
+        }
+        definition.push_str(&format!("    pub {}: {},\n", ident, rust_type));
+    }
+    definition.push_str("}\n");
+
+    structs.push(definition);
+    for (nested_name, nested_fields) in nested {
+        generate_struct(&nested_name, &nested_fields, structs)?;
+    }
+    Ok(())
+}
+
+/// Rust type of a field, wrapped in `Option` when the field is nullable
+fn field_type(field: &Field, nested_name: &str, nested: &mut Vec<(String, Vec<Field>)>) -> Result<String> {
+    let rust_type = data_type(field.data_type(), nested_name, nested)?;
+    if field.is_nullable() {
+        Ok(format!("Option<{}>", rust_type))
+    } else {
+        Ok(rust_type)
+    }
+}
+
+fn data_type(data_type: &DataType, nested_name: &str, nested: &mut Vec<(String, Vec<Field>)>) -> Result<String> {
+    let rust_type = match data_type {
+        DataType::Boolean => "bool",
+        DataType::Int8 => "i8",
+        DataType::Int16 => "i16",
+        DataType::Int32 => "i32",
+        DataType::Int64 => "i64",
+        DataType::UInt8 => "u8",
+        DataType::UInt16 => "u16",
+        DataType::UInt32 => "u32",
+        DataType::UInt64 => "u64",
+        DataType::Float32 => "f32",
+        DataType::Float64 => "f64",
+        DataType::Date32 => "i32",
+        DataType::Date64 | DataType::Timestamp(_, _) => "i64",
+        DataType::Utf8 | DataType::LargeUtf8 => "String",
+        DataType::Dictionary(_, values) if **values == DataType::Utf8 => "String",
+        DataType::List(item) | DataType::LargeList(item) => {
+            return Ok(format!("Vec<{}>", field_type(item, nested_name, nested)?));
+        }
+        DataType::Struct(fields) => {
+            nested.push((nested_name.to_owned(), fields.clone()));
+            return Ok(nested_name.to_owned());
+        }
+        _ => {
+            return Err(SchemaError::InvalidInput(format!(
+                "cannot generate a Rust type for {:?}",
+                data_type
+            )))
+        }
+    };
+    Ok(rust_type.to_owned())
+}
+
+/// Rust expression that constructs `field`
+fn field_expr(field: &Field) -> String {
+    format!(
+        "Field::new({:?}, {}, {})",
+        field.name(),
+        data_type_expr(field.data_type()),
+        field.is_nullable()
+    )
+}
+
+/// Rust expression that constructs `data_type`, for the types [`data_type`] accepts
+fn data_type_expr(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Timestamp(unit, tz) => format!(
+            "DataType::Timestamp(arrow::datatypes::TimeUnit::{}, {})",
+            time_unit(unit),
+            match tz {
+                Some(tz) => format!("Some({:?}.to_owned())", tz),
+                None => "None".to_owned(),
+            }
+        ),
+        DataType::List(item) => format!("DataType::List(Box::new({}))", field_expr(item)),
+        DataType::LargeList(item) => format!("DataType::LargeList(Box::new({}))", field_expr(item)),
+        DataType::Struct(fields) => {
+            let fields: Vec<String> = fields.iter().map(field_expr).collect();
+            format!("DataType::Struct(vec![{}])", fields.join(", "))
+        }
+        DataType::Dictionary(keys, values) => format!(
+            "DataType::Dictionary(Box::new({}), Box::new({}))",
+            data_type_expr(keys),
+            data_type_expr(values)
+        ),
+        // all other variants without payload print as their Rust name
+        _ => format!("DataType::{:?}", data_type),
+    }
+}
+
+fn time_unit(unit: &TimeUnit) -> &'static str {
+    match unit {
+        TimeUnit::Second => "Second",
+        TimeUnit::Millisecond => "Millisecond",
+        TimeUnit::Microsecond => "Microsecond",
+        TimeUnit::Nanosecond => "Nanosecond",
+    }
+}
+
+/// A valid Rust field name for `name`, keywords get a trailing underscore
+fn identifier(name: &str) -> String {
+    let mut ident: String = snake_case(name)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if KEYWORDS.contains(&ident.as_str()) {
+        ident.push('_');
+    }
+    ident
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if previous_lower {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+            previous_lower = false;
+        } else {
+            snake.push(c);
+            previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        }
+    }
+    snake
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,214 @@
+//! Schema compatibility modes, modeled on the ones used by schema registries.
+//!
+//! A *reader* schema can read data written with a *writer* schema when:
+//!
+//! * every reader field that is missing from the writer is nullable, so it can be filled
+//!   with nulls; writer fields that are unknown to the reader are ignored,
+//! * a reader field is not non-nullable when the writer field is nullable,
+//! * every writer type can be promoted to the reader type:
+//!   * identical types always match, and `Null` can be read as any type,
+//!   * signed and unsigned integers widen to larger integers of the same signedness,
+//!     unsigned integers also widen to larger signed integers,
+//!   * integers up to 16 bits widen to `Float32`, integers up to 32 bits to `Float64`,
+//!     and floats widen to larger floats,
+//!   * `Utf8` widens to `LargeUtf8`, `Binary` to `LargeBinary`, `List` to `LargeList`
+//!     and `Date32` to `Date64`,
+//!   * decimals widen when neither the scale nor the integer digits shrink,
+//!   * dictionary encoding is a representation detail: a dictionary is compared through its
+//!     value type, so adding, removing or re-keying a dictionary is compatible as long as
+//!     the values are,
+//!   * lists and structs are compatible when their children are, by the same rules.
+//!
+//! Which schema takes which role depends on the [`Compatibility`] mode.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use serde::{Deserialize, Serialize};
+
+use crate::error::SchemaError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compatibility {
+    /// Every candidate is accepted
+    None,
+    /// The candidate can read data written with the baseline, e.g. it may add nullable
+    /// fields, drop fields and widen types
+    Backward,
+    /// The baseline can read data written with the candidate, e.g. the candidate may add
+    /// fields, drop nullable fields and narrow types
+    Forward,
+    /// Both backward and forward
+    Full,
+}
+
+/// Backward, the default of most schema registries
+impl Default for Compatibility {
+    fn default() -> Self {
+        Compatibility::Backward
+    }
+}
+
+impl Display for Compatibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compatibility::None => write!(f, "none"),
+            Compatibility::Backward => write!(f, "backward"),
+            Compatibility::Forward => write!(f, "forward"),
+            Compatibility::Full => write!(f, "full"),
+        }
+    }
+}
+
+impl FromStr for Compatibility {
+    type Err = SchemaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Compatibility::None),
+            "backward" => Ok(Compatibility::Backward),
+            "forward" => Ok(Compatibility::Forward),
+            "full" => Ok(Compatibility::Full),
+            _ => Err(SchemaError::InvalidInput(format!(
+                "unknown compatibility mode '{}', expected one of none, backward, forward, full",
+                s
+            ))),
+        }
+    }
+}
+
+/// A rule of a [`Compatibility`] mode that the candidate breaks
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    /// Dotted path of the field, e.g. `payload.id`
+    pub field: String,
+    /// The mode whose reader/writer direction failed, `backward` or `forward`
+    pub direction: Compatibility,
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}): {}", self.field, self.direction, self.message)
+    }
+}
+
+/// Judges `candidate` against `baseline` and returns every broken rule
+pub fn check(baseline: &Schema, candidate: &Schema, mode: Compatibility) -> Vec<Violation> {
+    let mut violations = vec![];
+    if matches!(mode, Compatibility::Backward | Compatibility::Full) {
+        can_read(candidate.fields(), baseline.fields(), "", Compatibility::Backward, &mut violations);
+    }
+    if matches!(mode, Compatibility::Forward | Compatibility::Full) {
+        can_read(baseline.fields(), candidate.fields(), "", Compatibility::Forward, &mut violations);
+    }
+    violations
+}
+
+fn can_read(
+    reader: &[Field],
+    writer: &[Field],
+    prefix: &str,
+    direction: Compatibility,
+    violations: &mut Vec<Violation>,
+) {
+    for reader_field in reader {
+        let path = format!("{}{}", prefix, reader_field.name());
+        let writer_field = match writer.iter().find(|f| f.name() == reader_field.name()) {
+            Some(field) => field,
+            None => {
+                if !reader_field.is_nullable() {
+                    violations.push(Violation {
+                        field: path,
+                        direction,
+                        message: "non-nullable field has no value in the writer schema".to_owned(),
+                    });
+                }
+                continue;
+            }
+        };
+
+        if writer_field.is_nullable() && !reader_field.is_nullable() {
+            violations.push(Violation {
+                field: path.clone(),
+                direction,
+                message: "nullable field is read as non-nullable".to_owned(),
+            });
+        }
+
+        check_type(writer_field.data_type(), reader_field.data_type(), &path, direction, violations);
+    }
+}
+
+fn check_type(
+    writer: &DataType,
+    reader: &DataType,
+    path: &str,
+    direction: Compatibility,
+    violations: &mut Vec<Violation>,
+) {
+    match (writer, reader) {
+        (DataType::Dictionary(_, writer_values), _) => {
+            check_type(writer_values, reader, path, direction, violations)
+        }
+        (_, DataType::Dictionary(_, reader_values)) => {
+            check_type(writer, reader_values, path, direction, violations)
+        }
+        (DataType::Struct(writer_fields), DataType::Struct(reader_fields)) => can_read(
+            reader_fields,
+            writer_fields,
+            &format!("{}.", path),
+            direction,
+            violations,
+        ),
+        (DataType::List(writer_item), DataType::List(reader_item))
+        | (DataType::List(writer_item), DataType::LargeList(reader_item))
+        | (DataType::LargeList(writer_item), DataType::LargeList(reader_item)) => {
+            let item_path = format!("{}[]", path);
+            if writer_item.is_nullable() && !reader_item.is_nullable() {
+                violations.push(Violation {
+                    field: item_path.clone(),
+                    direction,
+                    message: "nullable list items are read as non-nullable".to_owned(),
+                });
+            }
+            check_type(writer_item.data_type(), reader_item.data_type(), &item_path, direction, violations)
+        }
+        _ if can_promote(writer, reader) => {}
+        _ => violations.push(Violation {
+            field: path.to_owned(),
+            direction,
+            message: format!("{:?} cannot be read as {:?}", writer, reader),
+        }),
+    }
+}
+
+/// Whether values of the primitive type `from` can be read losslessly as `to`
+pub fn can_promote(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+
+    if from == to {
+        return true;
+    }
+    match (from, to) {
+        (Null, _) => true,
+        (Int8, Int16 | Int32 | Int64 | Float32 | Float64) => true,
+        (Int16, Int32 | Int64 | Float32 | Float64) => true,
+        (Int32, Int64 | Float64) => true,
+        (UInt8, UInt16 | UInt32 | UInt64 | Int16 | Int32 | Int64 | Float32 | Float64) => true,
+        (UInt16, UInt32 | UInt64 | Int32 | Int64 | Float32 | Float64) => true,
+        (UInt32, UInt64 | Int64 | Float64) => true,
+        (Float16, Float32 | Float64) => true,
+        (Float32, Float64) => true,
+        (Utf8, LargeUtf8) => true,
+        (Binary, LargeBinary) => true,
+        (Date32, Date64) => true,
+        (Decimal(from_precision, from_scale), Decimal(to_precision, to_scale)) => {
+            to_scale >= from_scale
+                && to_precision.saturating_sub(*to_scale) >= from_precision.saturating_sub(*from_scale)
+        }
+        _ => false,
+    }
+}
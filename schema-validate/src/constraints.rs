@@ -0,0 +1,290 @@
+//! Value constraints on top-level schema fields.
+//!
+//! Constraints are stored as JSON in the field metadata under [`CONSTRAINTS_METADATA_KEY`],
+//! or in a sidecar JSON file that maps field names to constraints:
+//!
+//! ```json
+//! {
+//!   "a": { "required": true, "min": 0, "max": 100, "unique": true },
+//!   "d": { "pattern": "^[0-9]+$", "min_length": 1, "max_length": 8 },
+//!   "c": { "enum": [true, false] }
+//! }
+//! ```
+//!
+//! Numeric bounds only apply to numbers and pattern and length bounds only to strings;
+//! values of another type are left to the type checks in [`crate::records`]. Patterns are
+//! not anchored, use `^` and `$` to match the whole value.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use arrow::datatypes::{Field, Schema};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Result, SchemaError};
+use crate::json_schema::ENUM_METADATA_KEY;
+
+/// Field metadata key holding the field's [`Constraints`] as JSON
+pub const CONSTRAINTS_METADATA_KEY: &str = "constraints";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Constraints {
+    /// The field must be present and not null
+    pub required: bool,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub pattern: Option<String>,
+    #[serde(rename = "enum")]
+    pub values: Option<Vec<Value>>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    /// No two records may have the same non-null value
+    pub unique: bool,
+}
+
+/// Constraints by field name
+pub type ConstraintSet = BTreeMap<String, Constraints>;
+
+/// Reads the constraints stored in the field metadata of `schema`. Enum values imported
+/// from JSON Schema are picked up as well.
+pub fn from_schema(schema: &Schema) -> Result<ConstraintSet> {
+    let mut constraints = ConstraintSet::new();
+    for field in schema.fields() {
+        let metadata = match field.metadata() {
+            Some(metadata) => metadata,
+            None => continue,
+        };
+        let mut field_constraints: Constraints = match metadata.get(CONSTRAINTS_METADATA_KEY) {
+            Some(json) => serde_json::from_str(json)?,
+            None => Constraints::default(),
+        };
+        if field_constraints.values.is_none() {
+            if let Some(json) = metadata.get(ENUM_METADATA_KEY) {
+                field_constraints.values = Some(serde_json::from_str(json)?);
+            }
+        }
+        if field_constraints != Constraints::default() {
+            constraints.insert(field.name().clone(), field_constraints);
+        }
+    }
+    Ok(constraints)
+}
+
+/// Reads a sidecar constraints file
+pub fn load_sidecar<P: AsRef<Path>>(path: P) -> Result<ConstraintSet> {
+    let file = File::open(path)?;
+    Ok(serde_json::from_reader(BufReader::new(file))?)
+}
+
+/// Returns a copy of `schema` with `constraints` stored in the field metadata, replacing
+/// constraints that were stored before
+pub fn attach(schema: &Schema, constraints: &ConstraintSet) -> Result<Schema> {
+    for name in constraints.keys() {
+        if schema.field_with_name(name).is_err() {
+            return Err(SchemaError::InvalidInput(format!(
+                "constraints for unknown field '{}'",
+                name
+            )));
+        }
+    }
+
+    let mut fields = vec![];
+    for field in schema.fields() {
+        let mut metadata = field.metadata().clone().unwrap_or_default();
+        match constraints.get(field.name()) {
+            Some(field_constraints) => {
+                metadata.insert(
+                    CONSTRAINTS_METADATA_KEY.to_owned(),
+                    serde_json::to_string(field_constraints)?,
+                );
+            }
+            None => {
+                metadata.remove(CONSTRAINTS_METADATA_KEY);
+            }
+        }
+        let mut field = Field::new(field.name(), field.data_type().clone(), field.is_nullable());
+        field.set_metadata(if metadata.is_empty() { None } else { Some(metadata) });
+        fields.push(field);
+    }
+    Ok(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConstraintViolation {
+    /// 1-based line number in the input
+    pub line: usize,
+    pub field: String,
+    /// The broken constraint, e.g. `max 100`
+    pub constraint: String,
+    pub actual: Value,
+}
+
+impl Display for ConstraintViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: {}: violates {}, found {}",
+            self.line, self.field, self.constraint, self.actual
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ConstraintReport {
+    /// Number of non-empty lines that were checked
+    pub records: usize,
+    pub violations: Vec<ConstraintViolation>,
+    /// True when checking stopped early because `max_errors` was reached
+    pub truncated: bool,
+}
+
+impl ConstraintReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty() && !self.truncated
+    }
+}
+
+impl Display for ConstraintReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for violation in &self.violations {
+            writeln!(f, "{}", violation)?;
+        }
+        write!(
+            f,
+            "{} record(s) checked, {} constraint violation(s)",
+            self.records,
+            self.violations.len()
+        )?;
+        if self.truncated {
+            write!(f, ", stopped after reaching the error limit")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Checks every line of `reader` against `constraints`, stopping after `max_errors`
+/// violations. Lines that are not JSON objects are skipped, they are reported by
+/// [`crate::records::validate_records`].
+pub fn check_records<R: BufRead>(
+    reader: R,
+    constraints: &ConstraintSet,
+    max_errors: usize,
+) -> Result<ConstraintReport> {
+    let patterns = compile_patterns(constraints)?;
+    // first line number of every value seen so far, for fields that must be unique
+    let mut seen: HashMap<&str, HashMap<String, usize>> = HashMap::new();
+    let mut report = ConstraintReport::default();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        report.records += 1;
+        let record = match serde_json::from_str::<Value>(&line) {
+            Ok(Value::Object(record)) => record,
+            _ => continue,
+        };
+
+        for (name, field_constraints) in constraints {
+            let value = record.get(name).unwrap_or(&Value::Null);
+            let mut broken = check_value(field_constraints, patterns.get(name.as_str()), value);
+
+            if field_constraints.unique && !value.is_null() {
+                let first = seen
+                    .entry(name.as_str())
+                    .or_default()
+                    .entry(value.to_string())
+                    .or_insert(index + 1);
+                if *first != index + 1 {
+                    broken.push(format!("unique (first seen on line {})", first));
+                }
+            }
+
+            for constraint in broken {
+                if report.violations.len() == max_errors {
+                    report.truncated = true;
+                    return Ok(report);
+                }
+                report.violations.push(ConstraintViolation {
+                    line: index + 1,
+                    field: name.clone(),
+                    constraint,
+                    actual: value.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn compile_patterns(constraints: &ConstraintSet) -> Result<HashMap<&str, Regex>> {
+    let mut patterns = HashMap::new();
+    for (name, field_constraints) in constraints {
+        if let Some(pattern) = &field_constraints.pattern {
+            let regex = Regex::new(pattern).map_err(|err| {
+                SchemaError::InvalidInput(format!("invalid pattern for field '{}': {}", name, err))
+            })?;
+            patterns.insert(name.as_str(), regex);
+        }
+    }
+    Ok(patterns)
+}
+
+/// Descriptions of all constraints `value` breaks, except uniqueness
+fn check_value(constraints: &Constraints, pattern: Option<&Regex>, value: &Value) -> Vec<String> {
+    let mut broken = vec![];
+    if value.is_null() {
+        if constraints.required {
+            broken.push("required".to_owned());
+        }
+        return broken;
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(min) = constraints.min {
+            if number < min {
+                broken.push(format!("min {}", min));
+            }
+        }
+        if let Some(max) = constraints.max {
+            if number > max {
+                broken.push(format!("max {}", max));
+            }
+        }
+    }
+
+    if let Some(string) = value.as_str() {
+        let length = string.chars().count();
+        if let Some(min_length) = constraints.min_length {
+            if length < min_length {
+                broken.push(format!("min_length {}", min_length));
+            }
+        }
+        if let Some(max_length) = constraints.max_length {
+            if length > max_length {
+                broken.push(format!("max_length {}", max_length));
+            }
+        }
+        if let Some(pattern) = pattern {
+            if !pattern.is_match(string) {
+                broken.push(format!("pattern {}", pattern.as_str()));
+            }
+        }
+    }
+
+    if let Some(values) = &constraints.values {
+        if !values.contains(value) {
+            broken.push(format!("enum {}", Value::Array(values.clone())));
+        }
+    }
+
+    broken
+}
@@ -0,0 +1,238 @@
+//! `CREATE TABLE` statements generated from a stored schema.
+//!
+//! | Arrow                  | DataFusion      | PostgreSQL         | SQLite    |
+//! |------------------------|-----------------|--------------------|-----------|
+//! | `Boolean`              | `BOOLEAN`       | `BOOLEAN`          | `INTEGER` |
+//! | `Int8`, `Int16`        | `SMALLINT`      | `SMALLINT`         | `INTEGER` |
+//! | `Int32`                | `INT`           | `INTEGER`          | `INTEGER` |
+//! | `Int64`                | `BIGINT`        | `BIGINT`           | `INTEGER` |
+//! | `UInt64`               | `DECIMAL(20,0)` | `NUMERIC(20)`      | `INTEGER` |
+//! | `Float32`              | `FLOAT`         | `REAL`             | `REAL`    |
+//! | `Float64`              | `DOUBLE`        | `DOUBLE PRECISION` | `REAL`    |
+//! | `Decimal(p, s)`        | `DECIMAL(p,s)`  | `NUMERIC(p,s)`     | `NUMERIC` |
+//! | `Utf8`, `LargeUtf8`    | `VARCHAR`       | `TEXT`             | `TEXT`    |
+//! | `Binary`               | -               | `BYTEA`            | `BLOB`    |
+//! | `Date32`, `Date64`     | `DATE`          | `DATE`             | `TEXT`    |
+//! | `Timestamp`            | `TIMESTAMP`     | `TIMESTAMP[TZ]`    | `TEXT`    |
+//! | `List`                 | -               | `<item>[]`         | `TEXT`    |
+//! | `Struct`               | -               | `JSONB`            | `TEXT`    |
+//!
+//! Smaller unsigned integers map to the next larger signed type and dictionaries to their
+//! value type. Field descriptions become column comments.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use arrow::datatypes::{DataType, Schema};
+
+use crate::error::{Result, SchemaError};
+use crate::json_schema::DESCRIPTION_METADATA_KEY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// `CREATE EXTERNAL TABLE` for a DataFusion `ExecutionContext`
+    DataFusion,
+    Postgres,
+    Sqlite,
+}
+
+impl Display for Dialect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Dialect::DataFusion => write!(f, "datafusion"),
+            Dialect::Postgres => write!(f, "postgres"),
+            Dialect::Sqlite => write!(f, "sqlite"),
+        }
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = SchemaError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "datafusion" => Ok(Dialect::DataFusion),
+            "postgres" | "postgresql" => Ok(Dialect::Postgres),
+            "sqlite" => Ok(Dialect::Sqlite),
+            _ => Err(SchemaError::InvalidInput(format!(
+                "unknown SQL dialect '{}', expected one of datafusion, postgres, sqlite",
+                s
+            ))),
+        }
+    }
+}
+
+/// Where an external DataFusion table reads its files from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalLocation {
+    /// `PARQUET`, `CSV` or `NDJSON`
+    pub format: String,
+    pub location: String,
+}
+
+/// Generates the DDL that declares `table` with the fields of `schema`.
+///
+/// DataFusion requires an external `location`. It reads the schema of Parquet files from
+/// the files themselves and rejects column definitions for them, so the columns are only
+/// listed as comments there; register the table with the stored schema instead to enforce it.
+pub fn create_table(
+    schema: &Schema,
+    table: &str,
+    dialect: Dialect,
+    location: Option<&ExternalLocation>,
+) -> Result<String> {
+    let mut columns = vec![];
+    let mut comments = vec![];
+    for field in schema.fields() {
+        let mut column = format!("{} {}", quote(field.name()), sql_type(field.data_type(), dialect)?);
+        if !field.is_nullable() {
+            column.push_str(" NOT NULL");
+        }
+        let description = field
+            .metadata()
+            .as_ref()
+            .and_then(|metadata| metadata.get(DESCRIPTION_METADATA_KEY));
+        columns.push((column, description));
+        if let Some(description) = description {
+            comments.push(format!(
+                "COMMENT ON COLUMN {}.{} IS '{}';",
+                quote(table),
+                quote(field.name()),
+                description.replace('\'', "''")
+            ));
+        }
+    }
+
+    let mut ddl = String::new();
+    match dialect {
+        Dialect::DataFusion => {
+            let location = location.ok_or_else(|| {
+                SchemaError::InvalidInput("a DataFusion external table needs a location".to_owned())
+            })?;
+            let format = location.format.to_ascii_uppercase();
+            ddl.push_str(&format!("CREATE EXTERNAL TABLE {}", quote(table)));
+            if format == "PARQUET" {
+                ddl.push('\n');
+                for (column, description) in &columns {
+                    ddl.push_str(&format!("-- {}{}\n", column, inline_comment(description)));
+                }
+            } else {
+                ddl.push_str(" (\n");
+                ddl.push_str(&column_list(&columns));
+                ddl.push_str(")\n");
+            }
+            ddl.push_str(&format!("STORED AS {}\n", format));
+            if format == "CSV" {
+                ddl.push_str("WITH HEADER ROW\n");
+            }
+            ddl.push_str(&format!("LOCATION '{}';\n", location.location.replace('\'', "''")));
+        }
+        Dialect::Postgres => {
+            ddl.push_str(&format!("CREATE TABLE {} (\n", quote(table)));
+            ddl.push_str(&column_list(&columns));
+            ddl.push_str(");\n");
+            for comment in comments {
+                ddl.push_str(&comment);
+                ddl.push('\n');
+            }
+        }
+        Dialect::Sqlite => {
+            ddl.push_str(&format!("CREATE TABLE {} (\n", quote(table)));
+            ddl.push_str(&column_list(&columns));
+            ddl.push_str(");\n");
+        }
+    }
+    Ok(ddl)
+}
+
+/// One column per line, descriptions as trailing comments after the separating comma
+fn column_list(columns: &[(String, Option<&String>)]) -> String {
+    let mut list = String::new();
+    for (index, (column, description)) in columns.iter().enumerate() {
+        let separator = if index + 1 < columns.len() { "," } else { "" };
+        list.push_str(&format!("  {}{}{}\n", column, separator, inline_comment(description)));
+    }
+    list
+}
+
+fn inline_comment(description: &Option<&String>) -> String {
+    match description {
+        Some(description) => format!(" -- {}", description.replace('\n', " ")),
+        None => String::new(),
+    }
+}
+
+/// Quotes identifiers that would otherwise be folded to lower case or are not plain words
+fn quote(identifier: &str) -> String {
+    let plain = identifier.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && identifier
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if plain {
+        identifier.to_owned()
+    } else {
+        format!("\"{}\"", identifier.replace('"', "\"\""))
+    }
+}
+
+fn sql_type(data_type: &DataType, dialect: Dialect) -> Result<String> {
+    use DataType::*;
+
+    let unsupported = || {
+        Err(SchemaError::InvalidInput(format!(
+            "{:?} has no {} column type",
+            data_type, dialect
+        )))
+    };
+
+    let sql = match dialect {
+        Dialect::DataFusion => match data_type {
+            Boolean => "BOOLEAN".to_owned(),
+            Int8 | Int16 | UInt8 => "SMALLINT".to_owned(),
+            Int32 | UInt16 => "INT".to_owned(),
+            Int64 | UInt32 => "BIGINT".to_owned(),
+            UInt64 => "DECIMAL(20,0)".to_owned(),
+            Float16 | Float32 => "FLOAT".to_owned(),
+            Float64 => "DOUBLE".to_owned(),
+            Decimal(precision, scale) => format!("DECIMAL({},{})", precision, scale),
+            Utf8 | LargeUtf8 => "VARCHAR".to_owned(),
+            Date32 | Date64 => "DATE".to_owned(),
+            Timestamp(_, _) => "TIMESTAMP".to_owned(),
+            Dictionary(_, values) => return sql_type(values, dialect),
+            _ => return unsupported(),
+        },
+        Dialect::Postgres => match data_type {
+            Boolean => "BOOLEAN".to_owned(),
+            Int8 | Int16 | UInt8 => "SMALLINT".to_owned(),
+            Int32 | UInt16 => "INTEGER".to_owned(),
+            Int64 | UInt32 => "BIGINT".to_owned(),
+            UInt64 => "NUMERIC(20)".to_owned(),
+            Float16 | Float32 => "REAL".to_owned(),
+            Float64 => "DOUBLE PRECISION".to_owned(),
+            Decimal(precision, scale) => format!("NUMERIC({},{})", precision, scale),
+            Utf8 | LargeUtf8 => "TEXT".to_owned(),
+            Binary | LargeBinary | FixedSizeBinary(_) => "BYTEA".to_owned(),
+            Date32 | Date64 => "DATE".to_owned(),
+            Timestamp(_, None) => "TIMESTAMP".to_owned(),
+            Timestamp(_, Some(_)) => "TIMESTAMPTZ".to_owned(),
+            List(item) | LargeList(item) | FixedSizeList(item, _) => {
+                format!("{}[]", sql_type(item.data_type(), dialect)?)
+            }
+            Struct(_) => "JSONB".to_owned(),
+            Dictionary(_, values) => return sql_type(values, dialect),
+            _ => return unsupported(),
+        },
+        Dialect::Sqlite => match data_type {
+            Boolean | Int8 | Int16 | Int32 | Int64 | UInt8 | UInt16 | UInt32 | UInt64 => {
+                "INTEGER".to_owned()
+            }
+            Float16 | Float32 | Float64 => "REAL".to_owned(),
+            Decimal(_, _) => "NUMERIC".to_owned(),
+            Binary | LargeBinary | FixedSizeBinary(_) => "BLOB".to_owned(),
+            Dictionary(_, values) => return sql_type(values, dialect),
+            // dates and timestamps as ISO 8601 text, nested values as JSON text
+            _ => "TEXT".to_owned(),
+        },
+    };
+    Ok(sql)
+}
@@ -0,0 +1,221 @@
+//! Field-level differences between an expected and an actual schema
+
+use std::fmt::{Display, Formatter};
+
+use arrow::datatypes::{DataType, Schema};
+use serde::Serialize;
+
+/// How serious a single change is for consumers of the expected schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Harmless, e.g. a field moved to another position
+    Info,
+    /// Probably harmless, but worth a look, e.g. an unknown field
+    Warning,
+    /// Existing readers break, e.g. a field was removed or changed type
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// What changed about a field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Field is only present in the actual schema
+    Added { data_type: DataType, nullable: bool },
+    /// Field is only present in the expected schema
+    Removed { data_type: DataType, nullable: bool },
+    TypeChanged { expected: DataType, actual: DataType },
+    NullabilityChanged { expected: bool, actual: bool },
+    /// Field is present in both schemas, but at a different position
+    Reordered { expected: usize, actual: usize },
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeKind::Added { data_type, nullable } => {
+                write!(f, "added {:?} (nullable: {})", data_type, nullable)
+            }
+            ChangeKind::Removed { data_type, nullable } => {
+                write!(f, "removed {:?} (nullable: {})", data_type, nullable)
+            }
+            ChangeKind::TypeChanged { expected, actual } => {
+                write!(f, "type changed from {:?} to {:?}", expected, actual)
+            }
+            ChangeKind::NullabilityChanged { expected, actual } => {
+                write!(f, "nullable changed from {} to {}", expected, actual)
+            }
+            ChangeKind::Reordered { expected, actual } => {
+                write!(f, "moved from position {} to {}", expected, actual)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+/// All field changes between two schemas, in expected field order followed by added fields
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct SchemaDiff {
+    pub changes: Vec<FieldChange>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Highest severity of all changes, `None` if the schemas are identical
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.changes.iter().map(|change| change.severity).max()
+    }
+
+    /// Renders the changes as an aligned plain text table
+    pub fn to_table(&self) -> String {
+        let rows: Vec<[String; 3]> = self
+            .changes
+            .iter()
+            .map(|change| [change.severity.to_string(), change.field.clone(), change.kind.to_string()])
+            .collect();
+        let header = ["SEVERITY".to_owned(), "FIELD".to_owned(), "CHANGE".to_owned()];
+
+        let mut widths = [0; 3];
+        for row in std::iter::once(&header).chain(rows.iter()) {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        let mut table = String::new();
+        for row in std::iter::once(&header).chain(rows.iter()) {
+            let line = format!(
+                "{:w0$}  {:w1$}  {}",
+                row[0],
+                row[1],
+                row[2],
+                w0 = widths[0],
+                w1 = widths[1]
+            );
+            table.push_str(line.trim_end());
+            table.push('\n');
+        }
+        table
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("SchemaDiff is always serializable")
+    }
+}
+
+/// Compares `actual` against `expected` field by field.
+///
+/// Fields are matched by name. A field only counts as reordered when its position relative
+/// to the other shared fields changed, so an added or removed field does not mark every
+/// following field as moved.
+pub fn diff(expected: &Schema, actual: &Schema) -> SchemaDiff {
+    let mut changes = vec![];
+
+    let shared_expected: Vec<&String> = expected
+        .fields()
+        .iter()
+        .map(|field| field.name())
+        .filter(|name| actual.field_with_name(name).is_ok())
+        .collect();
+    let shared_actual: Vec<&String> = actual
+        .fields()
+        .iter()
+        .map(|field| field.name())
+        .filter(|name| expected.field_with_name(name).is_ok())
+        .collect();
+
+    for expected_field in expected.fields() {
+        let name = expected_field.name();
+        let actual_field = match actual.field_with_name(name) {
+            Ok(field) => field,
+            Err(_) => {
+                changes.push(FieldChange {
+                    field: name.clone(),
+                    severity: Severity::Error,
+                    kind: ChangeKind::Removed {
+                        data_type: expected_field.data_type().clone(),
+                        nullable: expected_field.is_nullable(),
+                    },
+                });
+                continue;
+            }
+        };
+
+        if actual_field.data_type() != expected_field.data_type() {
+            changes.push(FieldChange {
+                field: name.clone(),
+                severity: Severity::Error,
+                kind: ChangeKind::TypeChanged {
+                    expected: expected_field.data_type().clone(),
+                    actual: actual_field.data_type().clone(),
+                },
+            });
+        }
+
+        if actual_field.is_nullable() != expected_field.is_nullable() {
+            // non-nullable data still satisfies a nullable contract, the reverse does not
+            let severity = if expected_field.is_nullable() {
+                Severity::Info
+            } else {
+                Severity::Error
+            };
+            changes.push(FieldChange {
+                field: name.clone(),
+                severity,
+                kind: ChangeKind::NullabilityChanged {
+                    expected: expected_field.is_nullable(),
+                    actual: actual_field.is_nullable(),
+                },
+            });
+        }
+
+        let expected_pos = shared_expected.iter().position(|n| *n == name);
+        let actual_pos = shared_actual.iter().position(|n| *n == name);
+        if expected_pos != actual_pos {
+            changes.push(FieldChange {
+                field: name.clone(),
+                severity: Severity::Info,
+                kind: ChangeKind::Reordered {
+                    expected: expected.index_of(name).unwrap_or_default(),
+                    actual: actual.index_of(name).unwrap_or_default(),
+                },
+            });
+        }
+    }
+
+    for actual_field in actual.fields() {
+        if expected.field_with_name(actual_field.name()).is_err() {
+            changes.push(FieldChange {
+                field: actual_field.name().clone(),
+                severity: Severity::Warning,
+                kind: ChangeKind::Added {
+                    data_type: actual_field.data_type().clone(),
+                    nullable: actual_field.is_nullable(),
+                },
+            });
+        }
+    }
+
+    SchemaDiff { changes }
+}
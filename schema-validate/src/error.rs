@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use arrow::error::ArrowError;
+use parquet::errors::ParquetError;
+
+/// Enum with all errors in this crate.
+/// PartialEq is to enable testing for specific error types
+#[derive(Debug, PartialEq)]
+pub enum SchemaError {
+    /// Wrapper for errors while reading or writing schema and data files
+    Io(String),
+    /// Wrapper for Arrow errors, e.g. when inferring a schema
+    Arrow(String),
+    /// Wrapper for Parquet errors, e.g. when reading a file footer
+    Parquet(String),
+    /// Wrapper for errors while (de)serializing a schema
+    Serde(String),
+    /// Returned when user input, such as a compatibility mode, is not valid
+    InvalidInput(String),
+    /// Returned when a registry subject or version does not exist
+    NotFound(String),
+    /// Returned when a schema breaks the compatibility mode it is checked against
+    Incompatible(String),
+}
+
+/// Result type for operations that could result in a [`SchemaError`]
+pub type Result<T> = std::result::Result<T, SchemaError>;
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::Io(desc) => write!(f, "IO error: {}", desc),
+            SchemaError::Arrow(desc) => write!(f, "Arrow error: {}", desc),
+            SchemaError::Parquet(desc) => write!(f, "Parquet error: {}", desc),
+            SchemaError::Serde(desc) => write!(f, "Serde error: {}", desc),
+            SchemaError::InvalidInput(desc) => write!(f, "Invalid input: {}", desc),
+            SchemaError::NotFound(desc) => write!(f, "Not found: {}", desc),
+            SchemaError::Incompatible(desc) => write!(f, "Incompatible schema: {}", desc),
+        }
+    }
+}
+
+impl Error for SchemaError {}
+
+impl From<std::io::Error> for SchemaError {
+    fn from(err: std::io::Error) -> Self {
+        SchemaError::Io(err.to_string())
+    }
+}
+
+impl From<ArrowError> for SchemaError {
+    fn from(err: ArrowError) -> Self {
+        SchemaError::Arrow(err.to_string())
+    }
+}
+
+impl From<ParquetError> for SchemaError {
+    fn from(err: ParquetError) -> Self {
+        SchemaError::Parquet(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for SchemaError {
+    fn from(err: serde_json::Error) -> Self {
+        SchemaError::Serde(err.to_string())
+    }
+}
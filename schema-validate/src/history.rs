@@ -0,0 +1,237 @@
+//! A local history of inferred schemas, to follow how the schema of incoming files drifts.
+//!
+//! Layout on disk:
+//!
+//! ```text
+//! <root>/history.ndjson              one entry per recorded file, in recording order
+//! <root>/schemas/<fingerprint>.json  each distinct schema, stored once
+//! ```
+//!
+//! The timeline compares every entry with the entry recorded before it, so a change is
+//! attributed to the first file that shows it.
+
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use arrow::datatypes::Schema;
+use serde::{Deserialize, Serialize};
+
+use crate::diff::{diff, ChangeKind, FieldChange};
+use crate::error::{Result, SchemaError};
+use crate::registry::fingerprint;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// The file the schema was inferred from
+    pub file: String,
+    /// See [`fingerprint`]
+    pub fingerprint: String,
+    /// Seconds since the Unix epoch
+    pub recorded_at: u64,
+}
+
+/// A field that appeared, disappeared or changed type between two entries
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DriftEvent {
+    pub recorded_at: u64,
+    /// The file that introduced the change
+    pub file: String,
+    /// The file recorded before it
+    pub previous_file: String,
+    #[serde(flatten)]
+    pub change: FieldChange,
+}
+
+impl Display for DriftEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}  {}: {} {} (since {})",
+            self.recorded_at, self.file, self.change.field, self.change.kind, self.previous_file
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct DriftTimeline {
+    /// Number of history entries the timeline covers
+    pub entries: usize,
+    pub events: Vec<DriftEvent>,
+}
+
+impl DriftTimeline {
+    fn count(&self, kind: fn(&ChangeKind) -> bool) -> usize {
+        self.events.iter().filter(|event| kind(&event.change.kind)).count()
+    }
+}
+
+impl Display for DriftTimeline {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+        writeln!(f, "{} file(s) recorded, {} drift event(s)", self.entries, self.events.len())
+    }
+}
+
+/// Limits on the drift within a time window, e.g.
+///
+/// ```json
+/// {"max_appeared": 5, "max_disappeared": 0, "max_type_changes": 0, "window_secs": 604800}
+/// ```
+///
+/// Limits that are not set are not checked, without a window the whole history is checked.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DriftPolicy {
+    pub max_appeared: Option<usize>,
+    pub max_disappeared: Option<usize>,
+    pub max_type_changes: Option<usize>,
+    pub window_secs: Option<u64>,
+}
+
+impl DriftPolicy {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path.as_ref())?;
+        serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+            SchemaError::InvalidInput(format!("invalid drift policy {}: {}", path.as_ref().display(), e))
+        })
+    }
+
+    /// Descriptions of every limit the events of `timeline` since `now - window_secs` exceed
+    pub fn check(&self, timeline: &DriftTimeline, now: u64) -> Vec<String> {
+        let since = self.window_secs.map_or(0, |window| now.saturating_sub(window));
+        let recent = DriftTimeline {
+            entries: timeline.entries,
+            events: timeline
+                .events
+                .iter()
+                .filter(|event| event.recorded_at >= since)
+                .cloned()
+                .collect(),
+        };
+
+        let limits = [
+            ("appeared", self.max_appeared, recent.count(|kind| matches!(kind, ChangeKind::Added { .. }))),
+            ("disappeared", self.max_disappeared, recent.count(|kind| matches!(kind, ChangeKind::Removed { .. }))),
+            (
+                "changed type",
+                self.max_type_changes,
+                recent.count(|kind| matches!(kind, ChangeKind::TypeChanged { .. })),
+            ),
+        ];
+        limits
+            .iter()
+            .filter_map(|(what, limit, count)| match limit {
+                Some(limit) if count > limit => Some(format!(
+                    "{} field(s) {}, the policy allows {}",
+                    count, what, limit
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct History {
+    root: PathBuf,
+}
+
+impl History {
+    /// Opens the history at `root`. The directory is only created by [`History::record`],
+    /// a history that does not exist yet reads as empty.
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<Self> {
+        Ok(Self {
+            root: root.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Appends an entry for the schema inferred from `file`, recorded now
+    pub fn record(&self, file: &str, schema: &Schema) -> Result<HistoryEntry> {
+        let fingerprint = fingerprint(schema)?;
+        fs::create_dir_all(self.root.join("schemas"))?;
+        let schema_path = self.schema_path(&fingerprint);
+        if !schema_path.exists() {
+            fs::write(schema_path, serde_json::to_string(schema)?)?;
+        }
+
+        let entry = HistoryEntry {
+            file: file.to_owned(),
+            fingerprint,
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+        };
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.root.join("history.ndjson"))?;
+        writeln!(log, "{}", serde_json::to_string(&entry)?)?;
+        Ok(entry)
+    }
+
+    /// All entries, in recording order
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        let path = self.root.join("history.ndjson");
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        let mut entries = vec![];
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                entries.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// The schema with `fingerprint`
+    pub fn schema(&self, fingerprint: &str) -> Result<Schema> {
+        let path = self.schema_path(fingerprint);
+        if !path.exists() {
+            return Err(SchemaError::NotFound(format!("schema {} is not in the history", fingerprint)));
+        }
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    /// Fields that appeared, disappeared or changed type from one entry to the next
+    pub fn timeline(&self) -> Result<DriftTimeline> {
+        let entries = self.entries()?;
+        let mut timeline = DriftTimeline {
+            entries: entries.len(),
+            events: vec![],
+        };
+
+        for pair in entries.windows(2) {
+            let (previous, entry) = (&pair[0], &pair[1]);
+            if previous.fingerprint == entry.fingerprint {
+                continue;
+            }
+            let changes = diff(&self.schema(&previous.fingerprint)?, &self.schema(&entry.fingerprint)?);
+            for change in changes.changes {
+                if matches!(
+                    change.kind,
+                    ChangeKind::Added { .. } | ChangeKind::Removed { .. } | ChangeKind::TypeChanged { .. }
+                ) {
+                    timeline.events.push(DriftEvent {
+                        recorded_at: entry.recorded_at,
+                        file: entry.file.clone(),
+                        previous_file: previous.file.clone(),
+                        change,
+                    });
+                }
+            }
+        }
+        Ok(timeline)
+    }
+
+    fn schema_path(&self, fingerprint: &str) -> PathBuf {
+        self.root.join("schemas").join(format!("{}.json", fingerprint))
+    }
+}
@@ -0,0 +1,280 @@
+//! Conversion between Arrow schemas and JSON Schema (draft 2020-12) documents.
+//!
+//! | JSON Schema                          | Arrow                                   |
+//! |--------------------------------------|-----------------------------------------|
+//! | `boolean`                            | `Boolean`                               |
+//! | `integer`                            | `Int64` (any integer type on export)    |
+//! | `number`                             | `Float64` (any float or decimal type)   |
+//! | `string`                             | `Utf8`                                  |
+//! | `string` with format `date`          | `Date32`                                |
+//! | `string` with format `date-time`     | `Timestamp(Millisecond, None)`          |
+//! | `enum` of strings                    | `Dictionary(Int32, Utf8)`               |
+//! | `array` with `items`                 | `List`                                  |
+//! | `object` with `properties`           | `Struct`                                |
+//! | `null`                               | `Null`                                  |
+//!
+//! Properties that are not `required`, or whose type includes `null`, become nullable
+//! fields. Enum values and descriptions are kept in the field metadata under
+//! [`ENUM_METADATA_KEY`] and [`DESCRIPTION_METADATA_KEY`], so they survive a round trip.
+//!
+//! Inferring newline delimited JSON never yields the date, timestamp or dictionary types a
+//! contract maps to, and whole numbers infer as `Int64`. [`to_contract_types`] reads such
+//! inferred types as the contract's, leaving the values themselves to the record checks.
+
+use std::collections::{BTreeMap, HashMap};
+
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use serde_json::{json, Map, Value};
+
+use crate::error::{Result, SchemaError};
+
+pub const DRAFT_2020_12: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Field metadata key holding the allowed values as a JSON array
+pub const ENUM_METADATA_KEY: &str = "enum";
+/// Field metadata key holding a human-readable description
+pub const DESCRIPTION_METADATA_KEY: &str = "description";
+/// Schema metadata key marking a schema imported from a JSON Schema contract
+pub const CONTRACT_METADATA_KEY: &str = "$schema";
+
+/// Whether `value` looks like a JSON Schema document rather than a serialized Arrow schema
+pub fn is_json_schema(value: &Value) -> bool {
+    value.get("$schema").is_some() || value.get("properties").is_some()
+}
+
+/// Converts an Arrow schema to a JSON Schema object document
+pub fn to_json_schema(schema: &Schema) -> Value {
+    let mut document = fields_to_object(schema.fields());
+    if let Value::Object(object) = &mut document {
+        object.insert("$schema".to_owned(), json!(DRAFT_2020_12));
+    }
+    document
+}
+
+fn fields_to_object(fields: &[Field]) -> Value {
+    let mut properties = Map::new();
+    let mut required = vec![];
+    for field in fields {
+        properties.insert(field.name().clone(), field_to_json_schema(field));
+        if !field.is_nullable() {
+            required.push(json!(field.name()));
+        }
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+fn field_to_json_schema(field: &Field) -> Value {
+    let mut value = type_to_json_schema(field.data_type());
+    let metadata = field.metadata().clone().unwrap_or_default();
+
+    if let Value::Object(object) = &mut value {
+        if let Some(values) = metadata.get(ENUM_METADATA_KEY) {
+            if let Ok(values) = serde_json::from_str::<Value>(values) {
+                object.insert("enum".to_owned(), values);
+            }
+        }
+        if let Some(description) = metadata.get(DESCRIPTION_METADATA_KEY) {
+            object.insert("description".to_owned(), json!(description));
+        }
+        if field.is_nullable() {
+            if let Some(Value::String(ty)) = object.get("type").cloned() {
+                if ty != "null" {
+                    object.insert("type".to_owned(), json!([ty, "null"]));
+                }
+            }
+        }
+    }
+    value
+}
+
+fn type_to_json_schema(data_type: &DataType) -> Value {
+    match data_type {
+        DataType::Null => json!({ "type": "null" }),
+        DataType::Boolean => json!({ "type": "boolean" }),
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => json!({ "type": "integer" }),
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => {
+            json!({ "type": "integer", "minimum": 0 })
+        }
+        DataType::Float16 | DataType::Float32 | DataType::Float64 | DataType::Decimal(_, _) => {
+            json!({ "type": "number" })
+        }
+        DataType::Date32 | DataType::Date64 => json!({ "type": "string", "format": "date" }),
+        DataType::Timestamp(_, _) => json!({ "type": "string", "format": "date-time" }),
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => {
+            json!({ "type": "string", "contentEncoding": "base64" })
+        }
+        DataType::List(item) | DataType::LargeList(item) | DataType::FixedSizeList(item, _) => {
+            json!({ "type": "array", "items": field_to_json_schema(item) })
+        }
+        DataType::Struct(fields) => fields_to_object(fields),
+        DataType::Dictionary(_, values) => type_to_json_schema(values),
+        _ => json!({ "type": "string" }),
+    }
+}
+
+/// Converts a JSON Schema object document to an Arrow schema, marked with
+/// [`CONTRACT_METADATA_KEY`]
+pub fn from_json_schema(document: &Value) -> Result<Schema> {
+    let draft = document.get("$schema").and_then(|draft| draft.as_str()).unwrap_or(DRAFT_2020_12);
+    match object_to_fields(document, "$")? {
+        Some(fields) => Ok(Schema::new_with_metadata(
+            fields,
+            HashMap::from([(CONTRACT_METADATA_KEY.to_owned(), draft.to_owned())]),
+        )),
+        None => Err(SchemaError::InvalidInput(
+            "a JSON Schema contract must describe an object with properties".to_owned(),
+        )),
+    }
+}
+
+/// Whether `schema` was imported from a JSON Schema contract by [`from_json_schema`]
+pub fn is_contract(schema: &Schema) -> bool {
+    schema.metadata().contains_key(CONTRACT_METADATA_KEY)
+}
+
+/// The `inferred` schema with every field whose type is how JSON carries the `contract`
+/// type retyped to the contract's: `Utf8` for dates, timestamps and enums, `Int64` for
+/// numbers. Other fields are kept as inferred.
+pub fn to_contract_types(contract: &Schema, inferred: &Schema) -> Schema {
+    Schema::new_with_metadata(
+        contract_fields(contract.fields(), inferred.fields()),
+        inferred.metadata().clone(),
+    )
+}
+
+fn contract_fields(contract: &[Field], inferred: &[Field]) -> Vec<Field> {
+    inferred
+        .iter()
+        .map(|field| match contract.iter().find(|expected| expected.name() == field.name()) {
+            Some(expected) => field_with_type(field, contract_type(expected.data_type(), field.data_type())),
+            None => field.clone(),
+        })
+        .collect()
+}
+
+fn contract_type(contract: &DataType, inferred: &DataType) -> DataType {
+    match (contract, inferred) {
+        (DataType::Date32 | DataType::Timestamp(_, _), DataType::Utf8) => contract.clone(),
+        (DataType::Dictionary(_, values), DataType::Utf8) if **values == DataType::Utf8 => contract.clone(),
+        (DataType::Float64, DataType::Int64) => contract.clone(),
+        (DataType::List(expected), DataType::List(item)) => {
+            DataType::List(Box::new(field_with_type(item, contract_type(expected.data_type(), item.data_type()))))
+        }
+        (DataType::Struct(expected), DataType::Struct(fields)) => DataType::Struct(contract_fields(expected, fields)),
+        _ => inferred.clone(),
+    }
+}
+
+fn field_with_type(field: &Field, data_type: DataType) -> Field {
+    let mut retyped = Field::new(field.name(), data_type, field.is_nullable());
+    retyped.set_metadata(field.metadata().clone());
+    retyped
+}
+
+/// Fields of an `object` schema, `None` if `schema` does not describe an object
+fn object_to_fields(schema: &Value, path: &str) -> Result<Option<Vec<Field>>> {
+    let properties = match schema.get("properties") {
+        Some(Value::Object(properties)) => properties,
+        Some(_) => return Err(invalid(path, "'properties' must be an object")),
+        None => return Ok(None),
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(|required| required.as_array())
+        .map(|required| required.iter().filter_map(|name| name.as_str()).collect())
+        .unwrap_or_default();
+
+    let mut fields = vec![];
+    for (name, property) in properties {
+        let property_path = format!("{}.{}", path, name);
+        let (data_type, nullable) = property_to_type(property, &property_path)?;
+        let mut field = Field::new(name, data_type, nullable || !required.contains(&name.as_str()));
+        field.set_metadata(property_metadata(property));
+        fields.push(field);
+    }
+    Ok(Some(fields))
+}
+
+fn property_metadata(property: &Value) -> Option<BTreeMap<String, String>> {
+    let mut metadata = BTreeMap::new();
+    if let Some(values @ Value::Array(_)) = property.get("enum") {
+        metadata.insert(ENUM_METADATA_KEY.to_owned(), values.to_string());
+    }
+    if let Some(Value::String(description)) = property.get("description") {
+        metadata.insert(DESCRIPTION_METADATA_KEY.to_owned(), description.clone());
+    }
+    if metadata.is_empty() {
+        None
+    } else {
+        Some(metadata)
+    }
+}
+
+/// Arrow type of a property and whether it allows null
+fn property_to_type(property: &Value, path: &str) -> Result<(DataType, bool)> {
+    // `anyOf`/`oneOf` are only supported as a way to make a single type nullable
+    for keyword in &["anyOf", "oneOf"] {
+        if let Some(Value::Array(alternatives)) = property.get(*keyword) {
+            let (nulls, others): (Vec<&Value>, Vec<&Value>) = alternatives
+                .iter()
+                .partition(|alternative| alternative.get("type") == Some(&json!("null")));
+            return match others.as_slice() {
+                [single] => {
+                    let (data_type, nullable) = property_to_type(single, path)?;
+                    Ok((data_type, nullable || !nulls.is_empty()))
+                }
+                _ => Err(invalid(path, &format!("'{}' with more than one non-null type", keyword))),
+            };
+        }
+    }
+
+    let (types, nullable) = match property.get("type") {
+        Some(Value::String(ty)) => (vec![ty.as_str()], ty == "null"),
+        Some(Value::Array(types)) => {
+            let types: Vec<&str> = types.iter().filter_map(|ty| ty.as_str()).collect();
+            let nullable = types.contains(&"null");
+            (types.into_iter().filter(|ty| *ty != "null").collect(), nullable)
+        }
+        Some(_) => return Err(invalid(path, "'type' must be a string or an array of strings")),
+        // an enum without a type is treated as an enum of strings
+        None if property.get("enum").is_some() => (vec!["string"], false),
+        None => return Err(invalid(path, "missing 'type'")),
+    };
+
+    let data_type = match types.as_slice() {
+        [] => DataType::Null,
+        ["boolean"] => DataType::Boolean,
+        ["integer"] => DataType::Int64,
+        ["number"] => DataType::Float64,
+        ["string"] if property.get("enum").is_some() => {
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+        }
+        ["string"] => match property.get("format").and_then(|format| format.as_str()) {
+            Some("date") => DataType::Date32,
+            Some("date-time") => DataType::Timestamp(TimeUnit::Millisecond, None),
+            _ => DataType::Utf8,
+        },
+        ["array"] => {
+            let items = property
+                .get("items")
+                .ok_or_else(|| invalid(path, "array without 'items'"))?;
+            let (item_type, item_nullable) = property_to_type(items, &format!("{}[]", path))?;
+            DataType::List(Box::new(Field::new("item", item_type, item_nullable)))
+        }
+        ["object"] => match object_to_fields(property, path)? {
+            Some(fields) => DataType::Struct(fields),
+            None => return Err(invalid(path, "object without 'properties'")),
+        },
+        [ty] => return Err(invalid(path, &format!("unsupported type '{}'", ty))),
+        _ => return Err(invalid(path, "more than one non-null type")),
+    };
+    Ok((data_type, nullable))
+}
+
+fn invalid(path: &str, message: &str) -> SchemaError {
+    SchemaError::InvalidInput(format!("JSON Schema {}: {}", path, message))
+}
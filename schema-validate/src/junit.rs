@@ -0,0 +1,236 @@
+//! JUnit XML reports, so CI systems can show validation results like test results
+
+use arrow::datatypes::Schema;
+
+use crate::constraints::ConstraintReport;
+use crate::error::Result;
+use crate::records::RecordReport;
+use crate::{Severity, ValidationReport};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub classname: String,
+    pub name: String,
+    /// Failure message and details, `None` when the case passed
+    pub failure: Option<(String, String)>,
+    /// Printed as `system-out`, e.g. warnings of a passing case
+    pub output: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestSuite {
+    pub name: String,
+    pub cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    pub fn failures(&self) -> usize {
+        self.cases.iter().filter(|case| case.failure.is_some()).count()
+    }
+}
+
+/// One test case per field of either schema; a field fails when it breaks a compatibility
+/// rule, other changes of the field are reported as output
+pub fn schema_suite(name: &str, expected: &Schema, actual: &Schema, report: &ValidationReport) -> TestSuite {
+    let mut fields: Vec<&String> = expected.fields().iter().map(|field| field.name()).collect();
+    for field in actual.fields() {
+        if !fields.contains(&field.name()) {
+            fields.push(field.name());
+        }
+    }
+
+    let cases = fields
+        .into_iter()
+        .map(|field| {
+            let violations: Vec<String> = report
+                .violations
+                .iter()
+                .filter(|violation| top_level_field(&violation.field) == field.as_str())
+                .map(|violation| violation.to_string())
+                .collect();
+            let output = report
+                .diff
+                .changes
+                .iter()
+                .filter(|change| &change.field == field && change.severity > Severity::Info)
+                .map(|change| format!("{}: {}", change.severity, change.kind))
+                .collect();
+            TestCase {
+                classname: format!("{}.schema", name),
+                name: field.clone(),
+                failure: failure(&format!("incompatible ({})", report.compatibility), violations),
+                output,
+            }
+        })
+        .collect();
+    TestSuite {
+        name: format!("{} schema", name),
+        cases,
+    }
+}
+
+/// One test case per field of the schema, failing with the lines that do not fit its type
+pub fn records_suite(name: &str, schema: &Schema, report: &RecordReport) -> TestSuite {
+    let mut fields: Vec<String> = schema.fields().iter().map(|field| field.name().clone()).collect();
+    for violation in &report.violations {
+        let field = top_level_field(&violation.field).to_owned();
+        if !fields.contains(&field) {
+            fields.push(field);
+        }
+    }
+
+    let cases = fields
+        .into_iter()
+        .map(|field| {
+            let violations = report
+                .violations
+                .iter()
+                .filter(|violation| top_level_field(&violation.field) == field)
+                .map(|violation| violation.to_string())
+                .collect();
+            TestCase {
+                classname: format!("{}.records", name),
+                name: if field.is_empty() { "<record>".to_owned() } else { field },
+                failure: failure("invalid values", violations),
+                output: vec![],
+            }
+        })
+        .collect();
+    TestSuite {
+        name: format!("{} records", name),
+        cases,
+    }
+}
+
+/// One test case per constrained field
+pub fn constraints_suite<'a>(
+    name: &str,
+    fields: impl IntoIterator<Item = &'a String>,
+    report: &ConstraintReport,
+) -> TestSuite {
+    let cases = fields
+        .into_iter()
+        .map(|field| {
+            let violations = report
+                .violations
+                .iter()
+                .filter(|violation| &violation.field == field)
+                .map(|violation| violation.to_string())
+                .collect();
+            TestCase {
+                classname: format!("{}.constraints", name),
+                name: field.clone(),
+                failure: failure("constraint violations", violations),
+                output: vec![],
+            }
+        })
+        .collect();
+    TestSuite {
+        name: format!("{} constraints", name),
+        cases,
+    }
+}
+
+/// One test case per file, files that could not be read fail with their error
+pub fn files_suite(name: &str, reports: &[(String, Result<ValidationReport>)]) -> TestSuite {
+    let cases = reports
+        .iter()
+        .map(|(file, report)| match report {
+            Ok(report) => TestCase {
+                classname: name.to_owned(),
+                name: file.clone(),
+                failure: failure(
+                    &format!("incompatible ({})", report.compatibility),
+                    report.violations.iter().map(|violation| violation.to_string()).collect(),
+                ),
+                output: report
+                    .diff
+                    .changes
+                    .iter()
+                    .filter(|change| change.severity > Severity::Info)
+                    .map(|change| format!("{} {}: {}", change.severity, change.field, change.kind))
+                    .collect(),
+            },
+            Err(err) => TestCase {
+                classname: name.to_owned(),
+                name: file.clone(),
+                failure: failure("unreadable", vec![err.to_string()]),
+                output: vec![],
+            },
+        })
+        .collect();
+    TestSuite {
+        name: name.to_owned(),
+        cases,
+    }
+}
+
+/// Renders the suites as a `testsuites` document
+pub fn to_xml(suites: &[TestSuite]) -> String {
+    let tests: usize = suites.iter().map(|suite| suite.cases.len()).sum();
+    let failures: usize = suites.iter().map(|suite| suite.failures()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"schema-validate\" tests=\"{}\" failures=\"{}\">\n",
+        tests, failures
+    ));
+    for suite in suites {
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+            escape(&suite.name),
+            suite.cases.len(),
+            suite.failures()
+        ));
+        for case in &suite.cases {
+            xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\"",
+                escape(&case.classname),
+                escape(&case.name)
+            ));
+            if case.failure.is_none() && case.output.is_empty() {
+                xml.push_str("/>\n");
+                continue;
+            }
+            xml.push_str(">\n");
+            if let Some((message, details)) = &case.failure {
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\">{}</failure>\n",
+                    escape(message),
+                    escape(details)
+                ));
+            }
+            if !case.output.is_empty() {
+                xml.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    escape(&case.output.join("\n"))
+                ));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn failure(message: &str, details: Vec<String>) -> Option<(String, String)> {
+    if details.is_empty() {
+        None
+    } else {
+        Some((message.to_owned(), details.join("\n")))
+    }
+}
+
+/// `payload` for `payload.id` or `payload[]`
+fn top_level_field(path: &str) -> &str {
+    path.split(['.', '[']).next().unwrap_or(path)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,211 @@
+//! Infer, store and validate Arrow schemas of newline delimited JSON files
+
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use arrow::datatypes::Schema;
+use arrow::json::reader::infer_json_schema;
+use serde::Serialize;
+
+pub mod codegen;
+pub mod compat;
+pub mod constraints;
+pub mod ddl;
+pub mod diff;
+pub mod error;
+pub mod history;
+pub mod json_schema;
+pub mod junit;
+pub mod merge;
+pub mod parquet_footer;
+pub mod records;
+pub mod registry;
+pub mod repair;
+pub mod text_format;
+pub use crate::compat::{Compatibility, Violation};
+pub use crate::constraints::{ConstraintReport, ConstraintSet, Constraints};
+pub use crate::diff::{SchemaDiff, Severity};
+pub use crate::error::{Result, SchemaError};
+pub use crate::parquet_footer::{infer_parquet, validate_parquet_dir};
+pub use crate::records::{validate_records, RecordReport, RecordViolation};
+pub use crate::registry::{Registry, SchemaVersion};
+pub use crate::repair::{repair_records, RepairReport};
+
+/// Infers the schema of a newline delimited JSON stream
+pub fn infer<R: Read>(reader: &mut BufReader<R>) -> Result<Schema> {
+    Ok(infer_json_schema(reader, None)?)
+}
+
+/// Infers the schema of the newline delimited JSON file at `path`
+pub fn infer_file<P: AsRef<Path>>(path: P) -> Result<Schema> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+    infer(&mut reader)
+}
+
+/// Writes `schema` to `path`, replacing any existing file. Paths ending in `.json` get
+/// serde JSON, all others the [text format](text_format).
+pub fn save<P: AsRef<Path>>(schema: &Schema, path: P) -> Result<()> {
+    let is_json = path.as_ref().extension().is_some_and(|ext| ext == "json");
+    let mut schema_file = File::create(path)?;
+    if is_json {
+        write!(schema_file, "{}", serde_json::to_string(schema)?)?;
+    } else {
+        write!(schema_file, "{}", text_format::to_text(schema))?;
+    }
+    Ok(())
+}
+
+/// Reads a schema previously written by [`save`], in either format, or a JSON Schema contract
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Schema> {
+    let schema_file = File::open(path)?;
+    let mut reader = BufReader::new(schema_file);
+    let mut jsonstr = String::new();
+    reader.read_to_string(&mut jsonstr)?;
+    if text_format::is_text_format(&jsonstr) {
+        return text_format::from_text(&jsonstr);
+    }
+    let value: serde_json::Value = serde_json::from_str(&jsonstr)?;
+    if json_schema::is_json_schema(&value) {
+        json_schema::from_json_schema(&value)
+    } else {
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// Checks every record of the newline delimited JSON file at `path` against `schema`
+pub fn validate_records_file<P: AsRef<Path>>(path: P, schema: &Schema, max_errors: usize) -> Result<RecordReport> {
+    let file = File::open(path)?;
+    validate_records(BufReader::new(file), schema, max_errors)
+}
+
+/// Repairs the newline delimited JSON file at `path`, see [`repair`]. Fails when `output`
+/// or `quarantine` is the input file, which would be truncated before it is read.
+pub fn repair_file<P: AsRef<Path>, O: AsRef<Path>, Q: AsRef<Path>>(
+    path: P,
+    schema: &Schema,
+    output: O,
+    quarantine: Q,
+) -> Result<RepairReport> {
+    let input = path.as_ref().canonicalize()?;
+    for target in [output.as_ref(), quarantine.as_ref()] {
+        if target.canonicalize().is_ok_and(|target| target == input) {
+            return Err(SchemaError::InvalidInput(format!(
+                "'{}' is the input file, it would be overwritten",
+                target.display()
+            )));
+        }
+    }
+    let file = File::open(path)?;
+    repair_records(
+        BufReader::new(file),
+        schema,
+        BufWriter::new(File::create(output)?),
+        BufWriter::new(File::create(quarantine)?),
+    )
+}
+
+/// Checks every record of the newline delimited JSON file at `path` against `constraints`
+pub fn check_constraints_file<P: AsRef<Path>>(
+    path: P,
+    constraints: &ConstraintSet,
+    max_errors: usize,
+) -> Result<ConstraintReport> {
+    let file = File::open(path)?;
+    constraints::check_records(BufReader::new(file), constraints, max_errors)
+}
+
+/// Outcome of [`validate`]; valid when the candidate breaks no rule of the compatibility mode
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationReport {
+    pub compatibility: Compatibility,
+    pub diff: SchemaDiff,
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Incompatible when a rule is broken, otherwise passed with warnings when the diff
+    /// contains more than informational changes
+    pub fn outcome(&self) -> Outcome {
+        if !self.is_valid() {
+            Outcome::Incompatible
+        } else if self.diff.max_severity() > Some(Severity::Info) {
+            Outcome::Warnings
+        } else {
+            Outcome::Pass
+        }
+    }
+}
+
+/// Overall result of a validation run, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Pass,
+    /// Compatible, but the schemas differ in ways worth a look
+    Warnings,
+    Incompatible,
+}
+
+/// Exit code for runs that failed with an error, e.g. an unreadable input file
+pub const ERROR_EXIT_CODE: i32 = 3;
+
+impl Outcome {
+    /// `0` pass, `1` compatible with warnings, `2` incompatible, see also [`ERROR_EXIT_CODE`]
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Pass => 0,
+            Outcome::Warnings => 1,
+            Outcome::Incompatible => 2,
+        }
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.diff.is_empty() {
+            writeln!(f, "schemas are identical")?;
+        } else {
+            write!(f, "{}", self.diff.to_table())?;
+        }
+        if self.is_valid() {
+            writeln!(f, "compatible ({})", self.compatibility)
+        } else {
+            writeln!(f, "incompatible ({}):", self.compatibility)?;
+            for violation in &self.violations {
+                writeln!(f, "  {}", violation)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Judges `actual` against `expected` with the default [`Compatibility`] mode
+pub fn validate(expected: &Schema, actual: &Schema) -> ValidationReport {
+    validate_with(expected, actual, Compatibility::default())
+}
+
+/// Judges the `candidate` schema against a stored `baseline` using the rules of `mode`.
+/// Against a JSON Schema contract, inferred types are first read as the contract's, see
+/// [`json_schema::to_contract_types`].
+pub fn validate_with(baseline: &Schema, candidate: &Schema, mode: Compatibility) -> ValidationReport {
+    let contract_typed;
+    let candidate = if json_schema::is_contract(baseline) {
+        contract_typed = json_schema::to_contract_types(baseline, candidate);
+        &contract_typed
+    } else {
+        candidate
+    };
+    ValidationReport {
+        compatibility: mode,
+        diff: diff::diff(baseline, candidate),
+        violations: compat::check(baseline, candidate, mode),
+    }
+}
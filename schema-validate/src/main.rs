@@ -1,44 +1,609 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use std::fs::File;
-use std::io::{BufReader, SeekFrom, Seek};
-use arrow::json::reader::infer_json_schema;
 use arrow::datatypes::Schema;
-use std::io::prelude::*;
+use schema_validate::codegen::rust_structs;
+use schema_validate::constraints;
+use schema_validate::ddl::{self, Dialect, ExternalLocation};
+use schema_validate::history::{DriftPolicy, History};
+use schema_validate::json_schema::to_json_schema;
+use schema_validate::junit;
+use schema_validate::merge::merge_dir;
+use schema_validate::text_format::to_text;
+use schema_validate::{
+    check_constraints_file, infer_file, load, repair_file, save, validate_parquet_dir, validate_records_file,
+    validate_with, Compatibility, Outcome, Registry, Result, SchemaError, ERROR_EXIT_CODE,
+};
+
+const USAGE: &str = "usage:
+  schema-validate [validate] [--expected <schema file>] [--input <ndjson file>]
+                  [--registry <dir> --subject <name>] [--compat <none|backward|forward|full>]
+                  [--records] [--constraints <file>] [--max-errors <n>] [--report <text|json|junit>]
+  schema-validate save <ndjson file> [<schema file>]
+  schema-validate repair <ndjson file> [--expected <schema file> | --registry <dir> --subject <name>]
+                  [--output <ndjson file>] [--quarantine <ndjson file>] [--report <text|json>]
+  schema-validate merge <dir> [--output <schema file> | --registry <dir> --subject <name>]
+                  [--report <text|json>]
+  schema-validate parquet <dir or file> [--expected <schema file>]
+                  [--compat <none|backward|forward|full>] [--report <text|json|junit>]
+  schema-validate ddl <schema file> --table <name> [--dialect <datafusion|postgres|sqlite>]
+                  [--location <path or url>] [--format <parquet|csv|ndjson>]
+  schema-validate codegen <schema file> [--name <struct name>]
+  schema-validate convert <schema file> [<schema file>]
+  schema-validate constraints attach <schema file> <constraints file> --output <schema file>
+  schema-validate json-schema export <schema file>
+  schema-validate json-schema import <json schema file> [<schema file>]
+  schema-validate history <dir> record <ndjson file>...
+  schema-validate history <dir> timeline [--policy <file>] [--report <text|json>]
+  schema-validate registry <dir> subjects
+  schema-validate registry <dir> versions <subject>
+  schema-validate registry <dir> register <subject> <ndjson file>
+  schema-validate registry <dir> get <subject> [<version>]
+  schema-validate registry <dir> delete <subject> <version>
+  schema-validate registry <dir> compat <subject> [<none|backward|forward|full>]
+
+`--json` is short for `--report json`. Schema files ending in `.json` are written as JSON,
+all others in the readable text format; both are read wherever a schema file is expected.
+
+exit codes:
+  0  pass
+  1  compatible with warnings, e.g. new fields, conflicts while merging or repaired values
+  2  incompatible schema, invalid records, broken constraints, quarantined records or
+     drift beyond the policy
+  3  error, e.g. an unreadable file or invalid arguments";
+
+/// Flags that do not take a value
+const SWITCHES: &[&str] = &["--json", "--records"];
 
 fn main() {
-    // read input file
-    let mut file = File::open("./data/input1.json").unwrap();
-    // file's cursor's offset at 0
-    let reader = BufReader::new(&file);
-    // write the input schema to schema.txt file.
-    write_schema_to_file(reader, "./data/schema.txt");
-    // seek back to start so that the original file is usable again
-    file.seek(SeekFrom::Start(0)).unwrap();
-
-    // Validation Flow
-    // read second input file
-    let second_file = File::open("./data/input2.json").unwrap();
-    // file's cursor's offset at 0
-    let mut second_reader = BufReader::new(&second_file);
-    let input_schema = infer_json_schema(&mut second_reader, None).unwrap();
-    //read_schema_from_file("schema.txt");
-    let schema = read_schema_from_file("./data/schema.txt");
-    assert_eq!(schema.to_json(), input_schema.to_json())
-}
-
-fn write_schema_to_file(mut reader: std::io::BufReader<&std::fs::File>, file_path: &str) {
-    let inferred_schema = infer_json_schema(&mut reader, None).unwrap();  
-    let schema_file = File::create(file_path).unwrap();
-    //println!("{}", serde_json::to_string(&inferred_schema).unwrap());
-    write!(&schema_file, "{}", serde_json::to_string(&inferred_schema).unwrap());
-}
-
-fn read_schema_from_file(file_path: &str)  -> arrow::datatypes::Schema {
-    let schema_file = File::open(file_path).unwrap();
-    let mut reader = BufReader::new(schema_file);
-    let mut jsonstr = String::new();
-    reader.read_to_string(&mut jsonstr).unwrap();
-    //println!("{}", jsonstr);
-    let schema: Schema = serde_json::from_str(&jsonstr).unwrap();
-    schema
-}
\ No newline at end of file
+    match run() {
+        Ok(outcome) => process::exit(outcome.exit_code()),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(ERROR_EXIT_CODE);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReportFormat {
+    Text,
+    Json,
+    Junit,
+}
+
+/// Positional arguments and `--flag [value]` pairs of the command line
+struct Args {
+    positional: Vec<String>,
+    flags: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let mut positional = vec![];
+        let mut flags = HashMap::new();
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if SWITCHES.contains(&arg.as_str()) {
+                flags.insert(arg, String::new());
+            } else if arg.starts_with("--") {
+                flags.insert(arg, args.next().unwrap_or_default());
+            } else {
+                positional.push(arg);
+            }
+        }
+        Self { positional, flags }
+    }
+
+    fn flag(&self, name: &str) -> Option<&str> {
+        self.flags.get(name).map(|value| value.as_str())
+    }
+
+    fn switch(&self, name: &str) -> bool {
+        self.flags.contains_key(name)
+    }
+
+    fn positional(&self, index: usize, name: &str) -> Result<&str> {
+        self.positional
+            .get(index)
+            .map(|value| value.as_str())
+            .ok_or_else(|| SchemaError::InvalidInput(format!("missing <{}>\n{}", name, USAGE)))
+    }
+
+    fn report_format(&self) -> Result<ReportFormat> {
+        if self.switch("--json") {
+            return Ok(ReportFormat::Json);
+        }
+        match self.flag("--report").unwrap_or("text") {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "junit" => Ok(ReportFormat::Junit),
+            format => Err(SchemaError::InvalidInput(format!(
+                "unknown report format '{}', expected one of text, json, junit",
+                format
+            ))),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&self, value: &str, name: &str) -> Result<T> {
+        value
+            .parse()
+            .map_err(|_| SchemaError::InvalidInput(format!("{} expects a number, got '{}'", name, value)))
+    }
+}
+
+fn run() -> Result<Outcome> {
+    let args = Args::parse();
+    match args.positional.first().map(|command| command.as_str()) {
+        None | Some("validate") => validate(&args),
+        Some("save") => {
+            let schema = infer_file(args.positional(1, "ndjson file")?)?;
+            save(&schema, args.positional.get(2).map_or("./data/schema.txt", |path| path.as_str()))?;
+            Ok(Outcome::Pass)
+        }
+        Some("repair") => repair(&args),
+        Some("registry") => registry(&args),
+        Some("history") => history(&args),
+        Some("parquet") => parquet(&args),
+        Some("merge") => merge(&args),
+        Some("json-schema") => json_schema(&args),
+        Some("constraints") => constraints(&args),
+        Some("ddl") => ddl(&args),
+        Some("codegen") => {
+            let schema = load(args.positional(1, "schema file")?)?;
+            print!("{}", rust_structs(&schema, args.flag("--name").unwrap_or("Record"))?);
+            Ok(Outcome::Pass)
+        }
+        Some("convert") => {
+            let schema = load(args.positional(1, "schema file")?)?;
+            match args.positional.get(2) {
+                Some(path) => save(&schema, path)?,
+                None => print!("{}", to_text(&schema)),
+            }
+            Ok(Outcome::Pass)
+        }
+        Some(command) => Err(SchemaError::InvalidInput(format!(
+            "unknown command '{}'\n{}",
+            command, USAGE
+        ))),
+    }
+}
+
+/// Judges the schema of an input file against the stored schema, either `--expected`
+/// (default `./data/schema.txt`) or the latest version of a registry subject
+fn validate(args: &Args) -> Result<Outcome> {
+    let input = args.flag("--input").unwrap_or("./data/input2.json");
+    let format = args.report_format()?;
+
+    let (schema, mut compatibility) = expected_schema(args)?;
+    if let Some(mode) = args.flag("--compat") {
+        compatibility = mode.parse()?;
+    }
+    let max_errors = match args.flag("--max-errors") {
+        Some(value) => args.number(value, "--max-errors")?,
+        None => 100,
+    };
+
+    let input_schema = infer_file(input)?;
+    let report = validate_with(&schema, &input_schema, compatibility);
+    let mut outcome = report.outcome();
+
+    let record_report = if args.switch("--records") {
+        Some(validate_records_file(input, &schema, max_errors)?)
+    } else {
+        None
+    };
+
+    // constraints stored in the schema, overridden per field by the sidecar file
+    let mut field_constraints = constraints::from_schema(&schema)?;
+    if let Some(path) = args.flag("--constraints") {
+        field_constraints.extend(constraints::load_sidecar(path)?);
+    }
+    let constraint_report = if field_constraints.is_empty() {
+        None
+    } else {
+        Some(check_constraints_file(input, &field_constraints, max_errors)?)
+    };
+
+    if record_report.as_ref().is_some_and(|report| !report.is_valid())
+        || constraint_report.as_ref().is_some_and(|report| !report.is_valid())
+    {
+        outcome = Outcome::Incompatible;
+    }
+
+    match format {
+        ReportFormat::Text => {
+            print!("{}", report);
+            if let Some(record_report) = &record_report {
+                print!("{}", record_report);
+            }
+            if let Some(constraint_report) = &constraint_report {
+                print!("{}", constraint_report);
+            }
+        }
+        ReportFormat::Json => {
+            let json = serde_json::json!({
+                "input": input,
+                "outcome": outcome,
+                "schema": report,
+                "records": record_report,
+                "constraints": constraint_report,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+        ReportFormat::Junit => {
+            let mut suites = vec![junit::schema_suite(input, &schema, &input_schema, &report)];
+            if let Some(record_report) = &record_report {
+                suites.push(junit::records_suite(input, &schema, record_report));
+            }
+            if let Some(constraint_report) = &constraint_report {
+                suites.push(junit::constraints_suite(input, field_constraints.keys(), constraint_report));
+            }
+            print!("{}", junit::to_xml(&suites));
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// The schema to validate against and its compatibility mode, either `--expected`
+/// (default `./data/schema.txt`) or the latest version of a registry subject
+fn expected_schema(args: &Args) -> Result<(Schema, Compatibility)> {
+    match (args.flag("--registry"), args.flag("--subject")) {
+        (Some(root), Some(subject)) => {
+            let registry = Registry::open(root)?;
+            Ok((registry.latest(subject)?.schema, registry.compatibility(subject)?))
+        }
+        (None, None) => Ok((
+            load(args.flag("--expected").unwrap_or("./data/schema.txt"))?,
+            Compatibility::default(),
+        )),
+        _ => Err(SchemaError::InvalidInput(
+            "--registry and --subject must be used together".to_owned(),
+        )),
+    }
+}
+
+/// Coerces drifted values of an NDJSON file to the expected schema. Repaired records go to
+/// `--output` (default `<input>.repaired.json`), records that cannot be repaired to
+/// `--quarantine` (default `<input>.quarantine.json`).
+fn repair(args: &Args) -> Result<Outcome> {
+    let input = args.positional(1, "ndjson file")?;
+    let format = args.report_format()?;
+    let (schema, _) = expected_schema(args)?;
+    let output = match args.flag("--output") {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(input).with_extension("repaired.json"),
+    };
+    let quarantine = match args.flag("--quarantine") {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(input).with_extension("quarantine.json"),
+    };
+
+    let report = repair_file(input, &schema, &output, &quarantine)?;
+    let outcome = if !report.quarantined.is_empty() {
+        Outcome::Incompatible
+    } else if !report.coercions.is_empty() {
+        Outcome::Warnings
+    } else {
+        Outcome::Pass
+    };
+
+    match format {
+        ReportFormat::Text => {
+            print!("{}", report);
+            println!("repaired records written to {}", output.display());
+            if !report.quarantined.is_empty() {
+                println!("quarantined records written to {}", quarantine.display());
+            }
+        }
+        ReportFormat::Json => {
+            let json = serde_json::json!({
+                "input": input,
+                "outcome": outcome,
+                "output": output,
+                "quarantine": quarantine,
+                "repair": report,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+        ReportFormat::Junit => {
+            return Err(SchemaError::InvalidInput(
+                "repair reports are only available as text or json".to_owned(),
+            ))
+        }
+    }
+    Ok(outcome)
+}
+
+/// Merges the schemas of all NDJSON files in a directory, except `repair` outputs, and
+/// stores the result in `--output` (default `./data/schema.txt`) or as a new version of a
+/// registry subject. A directory without NDJSON files is an error, nothing is written.
+fn merge(args: &Args) -> Result<Outcome> {
+    let merged = merge_dir(args.positional(1, "dir")?)?;
+    match args.report_format()? {
+        ReportFormat::Text => {
+            println!("merged {} file(s), {} conflict(s)", merged.files.len(), merged.conflicts.len());
+            for conflict in &merged.conflicts {
+                println!("  {}", conflict);
+            }
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&merged.conflicts)?),
+        ReportFormat::Junit => {
+            return Err(SchemaError::InvalidInput(
+                "merge reports are only available as text or json".to_owned(),
+            ))
+        }
+    }
+
+    match (args.flag("--registry"), args.flag("--subject")) {
+        (Some(root), Some(subject)) => {
+            match Registry::open(root)?.register(subject, &merged.schema) {
+                Ok(version) => eprintln!("registered {} version {}", subject, version.version),
+                // the merged schema is still reported, the subject just does not accept it
+                Err(SchemaError::Incompatible(desc)) => {
+                    eprintln!("{} rejected: {}", subject, desc);
+                    return Ok(Outcome::Incompatible);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        (None, None) => save(&merged.schema, args.flag("--output").unwrap_or("./data/schema.txt"))?,
+        _ => {
+            return Err(SchemaError::InvalidInput(
+                "--registry and --subject must be used together".to_owned(),
+            ))
+        }
+    }
+    if merged.conflicts.is_empty() {
+        Ok(Outcome::Pass)
+    } else {
+        Ok(Outcome::Warnings)
+    }
+}
+
+/// Judges the footer schema of every Parquet file against `--expected`, or against the
+/// first file when no schema is given
+fn parquet(args: &Args) -> Result<Outcome> {
+    let expected = match args.flag("--expected") {
+        Some(path) => Some(load(path)?),
+        None => None,
+    };
+    let compatibility = match args.flag("--compat") {
+        Some(mode) => mode.parse()?,
+        None => Compatibility::default(),
+    };
+
+    let reports: Vec<(String, _)> =
+        validate_parquet_dir(args.positional(1, "dir or file")?, expected.as_ref(), compatibility)?
+            .into_iter()
+            .map(|(path, report)| (path.display().to_string(), report))
+            .collect();
+    let outcome = reports
+        .iter()
+        .filter_map(|(_, report)| report.as_ref().ok())
+        .map(|report| report.outcome())
+        .max()
+        .unwrap_or(Outcome::Pass);
+    let unreadable = reports.iter().filter(|(_, report)| report.is_err()).count();
+
+    match args.report_format()? {
+        ReportFormat::Text => {
+            for (file, report) in &reports {
+                println!("== {}", file);
+                match report {
+                    Ok(report) => print!("{}", report),
+                    Err(err) => println!("unreadable: {}", err),
+                }
+            }
+        }
+        ReportFormat::Json => {
+            let files: Vec<_> = reports
+                .iter()
+                .map(|(file, report)| match report {
+                    Ok(report) => serde_json::json!({ "file": file, "outcome": report.outcome(), "report": report }),
+                    Err(err) => serde_json::json!({ "file": file, "error": err.to_string() }),
+                })
+                .collect();
+            let json = serde_json::json!({ "outcome": outcome, "unreadable": unreadable, "files": files });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+        ReportFormat::Junit => print!("{}", junit::to_xml(&[junit::files_suite("parquet", &reports)])),
+    }
+    // every readable file is reported before failing with the error exit code
+    if unreadable > 0 {
+        return Err(SchemaError::Parquet(format!(
+            "{} of {} file(s) could not be read",
+            unreadable,
+            reports.len()
+        )));
+    }
+    Ok(outcome)
+}
+
+fn registry(args: &Args) -> Result<Outcome> {
+    let registry = Registry::open(args.positional(1, "dir")?)?;
+    match args.positional(2, "command")? {
+        "subjects" => {
+            for subject in registry.subjects()? {
+                println!("{}", subject);
+            }
+        }
+        "versions" => {
+            let subject = args.positional(3, "subject")?;
+            for version in registry.versions(subject)? {
+                let version = registry.get(subject, version)?;
+                println!("{}\t{}\t{}", version.version, version.registered_at, version.fingerprint);
+            }
+        }
+        "register" => {
+            let subject = args.positional(3, "subject")?;
+            let schema = infer_file(args.positional(4, "ndjson file")?)?;
+            match registry.register(subject, &schema) {
+                Ok(version) => println!("{} version {} ({})", subject, version.version, version.fingerprint),
+                Err(SchemaError::Incompatible(desc)) => {
+                    println!("{} rejected: {}", subject, desc);
+                    return Ok(Outcome::Incompatible);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        "get" => {
+            let subject = args.positional(3, "subject")?;
+            let version = match args.positional.get(4) {
+                Some(version) => registry.get(subject, args.number(version, "<version>")?)?,
+                None => registry.latest(subject)?,
+            };
+            print_schema(&version.schema)?;
+        }
+        "delete" => {
+            let subject = args.positional(3, "subject")?;
+            let version = args.number(args.positional(4, "version")?, "<version>")?;
+            registry.delete(subject, version)?;
+        }
+        "compat" => {
+            let subject = args.positional(3, "subject")?;
+            match args.positional.get(4) {
+                Some(mode) => registry.set_compatibility(subject, mode.parse()?)?,
+                None => println!("{}", registry.compatibility(subject)?),
+            }
+        }
+        command => {
+            return Err(SchemaError::InvalidInput(format!(
+                "unknown registry command '{}'\n{}",
+                command, USAGE
+            )))
+        }
+    }
+    Ok(Outcome::Pass)
+}
+
+/// Records inferred schemas in a drift history, or prints the drift timeline. With
+/// `--policy`, the timeline fails when the drift exceeds the policy and passes otherwise;
+/// without a policy any drift is a warning.
+fn history(args: &Args) -> Result<Outcome> {
+    let history = History::open(args.positional(1, "dir")?)?;
+    match args.positional(2, "command")? {
+        "record" => {
+            let files = args.positional.get(3..).unwrap_or_default();
+            if files.is_empty() {
+                return Err(SchemaError::InvalidInput(format!("missing <ndjson file>\n{}", USAGE)));
+            }
+            for file in files {
+                let entry = history.record(file, &infer_file(file)?)?;
+                println!("{}\t{}\t{}", entry.recorded_at, entry.fingerprint, entry.file);
+            }
+            Ok(Outcome::Pass)
+        }
+        "timeline" => {
+            let format = args.report_format()?;
+            let timeline = history.timeline()?;
+            let policy = args.flag("--policy").map(DriftPolicy::load).transpose()?;
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default();
+            let breaches = policy.as_ref().map_or_else(Vec::new, |policy| policy.check(&timeline, now));
+
+            let outcome = match policy {
+                Some(_) if breaches.is_empty() => Outcome::Pass,
+                Some(_) => Outcome::Incompatible,
+                None if timeline.events.is_empty() => Outcome::Pass,
+                None => Outcome::Warnings,
+            };
+            match format {
+                ReportFormat::Text => {
+                    print!("{}", timeline);
+                    for breach in &breaches {
+                        println!("policy exceeded: {}", breach);
+                    }
+                }
+                ReportFormat::Json => {
+                    let json = serde_json::json!({
+                        "outcome": outcome,
+                        "timeline": timeline,
+                        "breaches": breaches,
+                    });
+                    println!("{}", serde_json::to_string_pretty(&json)?);
+                }
+                ReportFormat::Junit => {
+                    return Err(SchemaError::InvalidInput(
+                        "drift timelines are only available as text or json".to_owned(),
+                    ))
+                }
+            }
+            Ok(outcome)
+        }
+        command => Err(SchemaError::InvalidInput(format!(
+            "unknown history command '{}'\n{}",
+            command, USAGE
+        ))),
+    }
+}
+
+fn ddl(args: &Args) -> Result<Outcome> {
+    let schema = load(args.positional(1, "schema file")?)?;
+    let table = args
+        .flag("--table")
+        .ok_or_else(|| SchemaError::InvalidInput(format!("missing --table\n{}", USAGE)))?;
+    let dialect: Dialect = args.flag("--dialect").unwrap_or("datafusion").parse()?;
+    let location = args.flag("--location").map(|location| ExternalLocation {
+        format: args.flag("--format").unwrap_or("parquet").to_owned(),
+        location: location.to_owned(),
+    });
+    print!("{}", ddl::create_table(&schema, table, dialect, location.as_ref())?);
+    Ok(Outcome::Pass)
+}
+
+fn constraints(args: &Args) -> Result<Outcome> {
+    match args.positional(1, "command")? {
+        "attach" => {
+            let schema = load(args.positional(2, "schema file")?)?;
+            let field_constraints = constraints::load_sidecar(args.positional(3, "constraints file")?)?;
+            // the input may be a JSON Schema contract, so it is never rewritten in place
+            let output = args
+                .flag("--output")
+                .ok_or_else(|| SchemaError::InvalidInput(format!("missing --output\n{}", USAGE)))?;
+            save(&constraints::attach(&schema, &field_constraints)?, output)?;
+        }
+        command => {
+            return Err(SchemaError::InvalidInput(format!(
+                "unknown constraints command '{}'\n{}",
+                command, USAGE
+            )))
+        }
+    }
+    Ok(Outcome::Pass)
+}
+
+fn json_schema(args: &Args) -> Result<Outcome> {
+    match args.positional(1, "command")? {
+        "export" => {
+            let schema = load(args.positional(2, "schema file")?)?;
+            println!("{}", serde_json::to_string_pretty(&to_json_schema(&schema))?);
+        }
+        "import" => {
+            // `load` already understands JSON Schema documents
+            let schema = load(args.positional(2, "json schema file")?)?;
+            match args.positional.get(3) {
+                Some(path) => save(&schema, path)?,
+                None => print_schema(&schema)?,
+            }
+        }
+        command => {
+            return Err(SchemaError::InvalidInput(format!(
+                "unknown json-schema command '{}'\n{}",
+                command, USAGE
+            )))
+        }
+    }
+    Ok(Outcome::Pass)
+}
+
+fn print_schema(schema: &Schema) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(schema)?);
+    Ok(())
+}
@@ -0,0 +1,218 @@
+//! Merging the schemas of many sample files into one superset schema.
+//!
+//! Fields keep the order in which they are first seen. A field is nullable when it is
+//! nullable or missing in any file. Structs are merged field by field and lists by their
+//! items. When files disagree on a type, the conflict is reported and the merged field gets
+//! the narrowest type all of them can be promoted to (see [`crate::compat::can_promote`]),
+//! `Float64` for mixed numbers and `Utf8` otherwise. `Null` types, i.e. fields that were
+//! only ever null in a file, never conflict.
+
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use arrow::datatypes::{DataType, Field, Schema};
+use serde::Serialize;
+
+use crate::compat::can_promote;
+use crate::error::{Result, SchemaError};
+use crate::infer_file;
+
+/// A field whose type differs between files
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Conflict {
+    /// Dotted path of the field, list items are marked with `[]`
+    pub field: String,
+    /// Every type of the field, with the files it occurred in
+    pub types: Vec<(DataType, Vec<String>)>,
+    /// The type of the field in the merged schema
+    pub merged: DataType,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let types: Vec<String> = self
+            .types
+            .iter()
+            .map(|(data_type, files)| format!("{:?} in {}", data_type, files.join(", ")))
+            .collect();
+        write!(f, "{}: {} (merged as {:?})", self.field, types.join(", "), self.merged)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MergedSchema {
+    pub schema: Schema,
+    pub conflicts: Vec<Conflict>,
+    /// Names of the merged files, in merge order
+    pub files: Vec<String>,
+}
+
+/// Suffixes of the files written by `repair`, which are never merged
+const REPAIR_OUTPUTS: [&str; 2] = [".repaired.json", ".quarantine.json"];
+
+/// Newline delimited JSON files (`.json`, `.ndjson`, `.jsonl`) in `dir`, sorted by path.
+/// Outputs of `repair` (`*.repaired.json`, `*.quarantine.json`) are skipped.
+pub fn ndjson_files<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_ndjson = path
+            .extension()
+            .is_some_and(|ext| ext == "json" || ext == "ndjson" || ext == "jsonl");
+        let is_repair_output = path
+            .file_name()
+            .is_some_and(|name| REPAIR_OUTPUTS.iter().any(|suffix| name.to_string_lossy().ends_with(suffix)));
+        if path.is_file() && is_ndjson && !is_repair_output {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Infers the schema of every newline delimited JSON file in `dir` and merges them.
+/// Fails when `dir` has no such files.
+pub fn merge_dir<P: AsRef<Path>>(dir: P) -> Result<MergedSchema> {
+    let files = ndjson_files(&dir)?;
+    if files.is_empty() {
+        return Err(SchemaError::InvalidInput(format!(
+            "no newline delimited JSON files in '{}'",
+            dir.as_ref().display()
+        )));
+    }
+    let mut schemas = vec![];
+    for path in files {
+        let name = path
+            .file_name()
+            .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+        schemas.push((name, infer_file(&path)?));
+    }
+    Ok(merge(&schemas))
+}
+
+/// Merges schemas that are labeled with the file they were inferred from
+pub fn merge(schemas: &[(String, Schema)]) -> MergedSchema {
+    let inputs: Vec<(&str, &[Field])> = schemas
+        .iter()
+        .map(|(file, schema)| (file.as_str(), schema.fields().as_slice()))
+        .collect();
+    let mut conflicts = vec![];
+    let fields = merge_fields(&inputs, "", &mut conflicts);
+    MergedSchema {
+        schema: Schema::new(fields),
+        conflicts,
+        files: schemas.iter().map(|(file, _)| file.clone()).collect(),
+    }
+}
+
+fn merge_fields(inputs: &[(&str, &[Field])], prefix: &str, conflicts: &mut Vec<Conflict>) -> Vec<Field> {
+    let mut names: Vec<&String> = vec![];
+    for (_, fields) in inputs {
+        for field in fields.iter() {
+            if !names.contains(&field.name()) {
+                names.push(field.name());
+            }
+        }
+    }
+
+    names
+        .into_iter()
+        .map(|name| {
+            let occurrences: Vec<(&str, &Field)> = inputs
+                .iter()
+                .filter_map(|(file, fields)| {
+                    fields.iter().find(|field| field.name() == name).map(|field| (*file, field))
+                })
+                .collect();
+            let missing = occurrences.len() < inputs.len();
+            merge_field(name, &format!("{}{}", prefix, name), &occurrences, missing, conflicts)
+        })
+        .collect()
+}
+
+fn merge_field(
+    name: &str,
+    path: &str,
+    occurrences: &[(&str, &Field)],
+    missing: bool,
+    conflicts: &mut Vec<Conflict>,
+) -> Field {
+    let nullable = missing || occurrences.iter().any(|(_, field)| field.is_nullable());
+    let typed: Vec<(&str, &Field)> = occurrences
+        .iter()
+        .filter(|(_, field)| field.data_type() != &DataType::Null)
+        .copied()
+        .collect();
+
+    let data_type = if typed.is_empty() {
+        DataType::Null
+    } else if typed.iter().all(|(_, field)| matches!(field.data_type(), DataType::Struct(_))) {
+        let inputs: Vec<(&str, &[Field])> = typed
+            .iter()
+            .filter_map(|(file, field)| match field.data_type() {
+                DataType::Struct(fields) => Some((*file, fields.as_slice())),
+                _ => None,
+            })
+            .collect();
+        DataType::Struct(merge_fields(&inputs, &format!("{}.", path), conflicts))
+    } else if typed.iter().all(|(_, field)| matches!(field.data_type(), DataType::List(_))) {
+        let items: Vec<(&str, &Field)> = typed
+            .iter()
+            .filter_map(|(file, field)| match field.data_type() {
+                DataType::List(item) => Some((*file, item.as_ref())),
+                _ => None,
+            })
+            .collect();
+        let item_name = items[0].1.name().clone();
+        let item = merge_field(&item_name, &format!("{}[]", path), &items, false, conflicts);
+        DataType::List(Box::new(item))
+    } else {
+        let mut types: Vec<(DataType, Vec<String>)> = vec![];
+        for (file, field) in &typed {
+            match types.iter_mut().find(|(data_type, _)| data_type == field.data_type()) {
+                Some((_, files)) => files.push(file.to_string()),
+                None => types.push((field.data_type().clone(), vec![file.to_string()])),
+            }
+        }
+        let merged = supertype(types.iter().map(|(data_type, _)| data_type));
+        if types.len() > 1 {
+            conflicts.push(Conflict {
+                field: path.to_owned(),
+                types,
+                merged: merged.clone(),
+            });
+        }
+        merged
+    };
+
+    Field::new(name, data_type, nullable)
+}
+
+fn supertype<'a>(types: impl Iterator<Item = &'a DataType> + Clone) -> DataType {
+    let candidate = types
+        .clone()
+        .find(|candidate| types.clone().all(|data_type| can_promote(data_type, candidate)));
+    match candidate {
+        Some(data_type) => data_type.clone(),
+        None if types.clone().all(is_numeric) => DataType::Float64,
+        None => DataType::Utf8,
+    }
+}
+
+fn is_numeric(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float16
+            | DataType::Float32
+            | DataType::Float64
+    )
+}
@@ -0,0 +1,63 @@
+//! Arrow schemas of Parquet files, read from the footer without scanning any data
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use arrow::datatypes::Schema;
+use parquet::arrow::parquet_to_arrow_schema;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+
+use crate::compat::Compatibility;
+use crate::error::Result;
+use crate::{validate_with, ValidationReport};
+
+/// Reads the Arrow schema from the footer of the Parquet file at `path`
+pub fn infer_parquet<P: AsRef<Path>>(path: P) -> Result<Schema> {
+    let file = File::open(path)?;
+    // only the footer is read when the reader is created
+    let reader = SerializedFileReader::new(file)?;
+    let file_metadata = reader.metadata().file_metadata();
+    Ok(parquet_to_arrow_schema(
+        file_metadata.schema_descr(),
+        file_metadata.key_value_metadata(),
+    )?)
+}
+
+/// All `.parquet` files in `dir`, sorted by path. A single file is returned as is.
+pub fn parquet_files<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    if dir.is_file() {
+        return Ok(vec![dir.to_path_buf()]);
+    }
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "parquet") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Judges the footer schema of every Parquet file in `dir` against `expected`.
+///
+/// Without an expected schema the first readable file is the baseline, so drift between the
+/// files of one table is caught before a query over all of them fails. A file whose footer
+/// cannot be read gets its error as report, the other files are still checked.
+pub fn validate_parquet_dir<P: AsRef<Path>>(
+    dir: P,
+    expected: Option<&Schema>,
+    mode: Compatibility,
+) -> Result<Vec<(PathBuf, Result<ValidationReport>)>> {
+    let mut baseline = expected.cloned();
+    let mut reports = vec![];
+    for path in parquet_files(dir)? {
+        let report = infer_parquet(&path).map(|schema| {
+            let baseline = baseline.get_or_insert_with(|| schema.clone());
+            validate_with(baseline, &schema, mode)
+        });
+        reports.push((path, report));
+    }
+    Ok(reports)
+}
@@ -0,0 +1,264 @@
+//! Record-level validation of newline delimited JSON against a stored schema
+
+use std::fmt::{Display, Formatter};
+use std::io::BufRead;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::Result;
+
+/// A single value that does not fit the schema
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordViolation {
+    /// 1-based line number in the input
+    pub line: usize,
+    /// Name of the offending top-level field, empty when the whole line is invalid
+    pub field: String,
+    /// JSON path of the offending value, e.g. `$.payload.features[1]`, `$` for the whole line
+    pub path: String,
+    /// Type declared in the schema, `None` when the field is not part of the schema
+    pub expected: Option<DataType>,
+    pub actual: Value,
+}
+
+impl Display for RecordViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.expected {
+            Some(expected) => write!(
+                f,
+                "line {}: {}: expected {:?}, found {}",
+                self.line, self.path, expected, self.actual
+            ),
+            None => write!(f, "line {}: {}: unknown field, found {}", self.line, self.path, self.actual),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct RecordReport {
+    /// Number of non-empty lines that were checked
+    pub records: usize,
+    pub violations: Vec<RecordViolation>,
+    /// True when checking stopped early because `max_errors` was reached
+    pub truncated: bool,
+}
+
+impl RecordReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty() && !self.truncated
+    }
+}
+
+impl Display for RecordReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for violation in &self.violations {
+            writeln!(f, "{}", violation)?;
+        }
+        write!(f, "{} record(s) checked, {} violation(s)", self.records, self.violations.len())?;
+        if self.truncated {
+            write!(f, ", stopped after reaching the error limit")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Checks every line of `reader` against `schema`, stopping after `max_errors` violations.
+///
+/// Missing fields count as null. Structs and lists are checked value by value, so a
+/// violation carries the JSON path and value of the innermost member that does not fit,
+/// e.g. `$.payload.features[1]`. Members that are not part of a struct are reported as
+/// unknown fields, like fields that are not part of the schema.
+pub fn validate_records<R: BufRead>(reader: R, schema: &Schema, max_errors: usize) -> Result<RecordReport> {
+    let mut report = RecordReport::default();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        report.records += 1;
+
+        let mut violations = vec![];
+        check_line(index + 1, &line, schema, &mut violations);
+        for violation in violations {
+            if report.violations.len() == max_errors {
+                report.truncated = true;
+                return Ok(report);
+            }
+            report.violations.push(violation);
+        }
+    }
+
+    Ok(report)
+}
+
+fn check_line(line_number: usize, line: &str, schema: &Schema, violations: &mut Vec<RecordViolation>) {
+    let record = match serde_json::from_str::<Value>(line) {
+        Ok(Value::Object(record)) => record,
+        Ok(value) => {
+            violations.push(RecordViolation {
+                line: line_number,
+                field: String::new(),
+                path: "$".to_owned(),
+                expected: Some(DataType::Struct(schema.fields().clone())),
+                actual: value,
+            });
+            return;
+        }
+        Err(_) => {
+            violations.push(RecordViolation {
+                line: line_number,
+                field: String::new(),
+                path: "$".to_owned(),
+                expected: Some(DataType::Struct(schema.fields().clone())),
+                actual: Value::String(line.to_owned()),
+            });
+            return;
+        }
+    };
+
+    let mut check = Check {
+        line: line_number,
+        field: "",
+        violations,
+    };
+    for field in schema.fields() {
+        check.field = field.name().as_str();
+        let value = record.get(field.name()).unwrap_or(&Value::Null);
+        check.value(&json_path("$", field.name()), field, value);
+    }
+
+    for (name, value) in &record {
+        if schema.field_with_name(name).is_err() {
+            check.field = name.as_str();
+            check.unknown(json_path("$", name), value);
+        }
+    }
+}
+
+/// Collects the violations of one top-level field of a record
+struct Check<'a> {
+    line: usize,
+    field: &'a str,
+    violations: &'a mut Vec<RecordViolation>,
+}
+
+impl Check<'_> {
+    /// Checks `value` against `field`, descending into structs and lists so the violation
+    /// names the innermost value that does not fit
+    fn value(&mut self, path: &str, field: &Field, value: &Value) {
+        match (field.data_type(), value) {
+            (_, Value::Null) => {
+                if !field.is_nullable() {
+                    self.violation(path, field.data_type(), value);
+                }
+            }
+            (DataType::Struct(fields), Value::Object(object)) => {
+                for child in fields {
+                    let value = object.get(child.name()).unwrap_or(&Value::Null);
+                    self.value(&json_path(path, child.name()), child, value);
+                }
+                for (name, value) in object {
+                    if !fields.iter().any(|child| child.name() == name) {
+                        self.unknown(json_path(path, name), value);
+                    }
+                }
+            }
+            (DataType::List(item), Value::Array(values)) | (DataType::LargeList(item), Value::Array(values)) => {
+                for (index, value) in values.iter().enumerate() {
+                    self.value(&format!("{}[{}]", path, index), item, value);
+                }
+            }
+            (data_type, value) => {
+                if !value_matches(data_type, value) {
+                    self.violation(path, data_type, value);
+                }
+            }
+        }
+    }
+
+    fn violation(&mut self, path: &str, expected: &DataType, actual: &Value) {
+        self.violations.push(RecordViolation {
+            line: self.line,
+            field: self.field.to_owned(),
+            path: path.to_owned(),
+            expected: Some(expected.clone()),
+            actual: actual.clone(),
+        });
+    }
+
+    fn unknown(&mut self, path: String, actual: &Value) {
+        self.violations.push(RecordViolation {
+            line: self.line,
+            field: self.field.to_owned(),
+            path,
+            expected: None,
+            actual: actual.clone(),
+        });
+    }
+}
+
+/// JSON path of member `key` of the object at `parent`, e.g. `$.payload` or `$['user agent']`
+pub fn json_path(parent: &str, key: &str) -> String {
+    let plain = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        format!("{}.{}", parent, key)
+    } else {
+        format!("{}['{}']", parent, key.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+fn field_matches(field: &Field, value: &Value) -> bool {
+    if value.is_null() {
+        field.is_nullable()
+    } else {
+        value_matches(field.data_type(), value)
+    }
+}
+
+/// Whether a non-null JSON value can be read as `data_type` by the Arrow JSON reader
+pub fn value_matches(data_type: &DataType, value: &Value) -> bool {
+    match data_type {
+        DataType::Null => value.is_null(),
+        DataType::Boolean => value.is_boolean(),
+        DataType::Int8 => fits_i64(value, i8::MIN as i64, i8::MAX as i64),
+        DataType::Int16 => fits_i64(value, i16::MIN as i64, i16::MAX as i64),
+        DataType::Int32 => fits_i64(value, i32::MIN as i64, i32::MAX as i64),
+        DataType::Int64 => value.is_i64(),
+        DataType::UInt8 => fits_u64(value, u8::MAX as u64),
+        DataType::UInt16 => fits_u64(value, u16::MAX as u64),
+        DataType::UInt32 => fits_u64(value, u32::MAX as u64),
+        DataType::UInt64 => value.is_u64(),
+        DataType::Float16 | DataType::Float32 | DataType::Float64 | DataType::Decimal(_, _) => {
+            value.is_number()
+        }
+        DataType::Utf8 | DataType::LargeUtf8 => value.is_string(),
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) => {
+            value.is_string() || value.is_i64()
+        }
+        DataType::List(item) | DataType::LargeList(item) => match value {
+            Value::Array(values) => values.iter().all(|value| field_matches(item, value)),
+            _ => false,
+        },
+        DataType::Struct(fields) => match value {
+            Value::Object(object) => fields
+                .iter()
+                .all(|field| field_matches(field, object.get(field.name()).unwrap_or(&Value::Null))),
+            _ => false,
+        },
+        DataType::Dictionary(_, values) => value_matches(values, value),
+        // types the JSON reader cannot produce are not checked
+        _ => true,
+    }
+}
+
+fn fits_i64(value: &Value, min: i64, max: i64) -> bool {
+    value.as_i64().is_some_and(|v| v >= min && v <= max)
+}
+
+fn fits_u64(value: &Value, max: u64) -> bool {
+    value.as_u64().is_some_and(|v| v <= max)
+}
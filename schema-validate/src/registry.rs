@@ -0,0 +1,230 @@
+//! A directory-backed schema registry with named subjects and numbered versions.
+//!
+//! Layout on disk:
+//!
+//! ```text
+//! <root>/<subject>/config.json   compatibility mode and highest version of the subject
+//! <root>/<subject>/v1.json       first registered version
+//! <root>/<subject>/v2.json       ...
+//! ```
+//!
+//! New versions are only checked against the latest version, not against all previous ones.
+//! Version numbers are never reused, not even after the latest version is deleted.
+
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use arrow::datatypes::Schema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::compat::{self, Compatibility};
+use crate::error::{Result, SchemaError};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaVersion {
+    pub subject: String,
+    pub version: u32,
+    /// See [`fingerprint`]
+    pub fingerprint: String,
+    /// Seconds since the Unix epoch
+    pub registered_at: u64,
+    pub schema: Schema,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+struct SubjectConfig {
+    #[serde(default)]
+    compatibility: Compatibility,
+    /// Highest version ever registered, deleted versions included
+    #[serde(default)]
+    last_version: u32,
+}
+
+/// Hex encoded SHA-256 of the schema's serde JSON
+pub fn fingerprint(schema: &Schema) -> Result<String> {
+    let json = serde_json::to_string(schema)?;
+    Ok(format!("{:x}", Sha256::digest(json.as_bytes())))
+}
+
+#[derive(Debug, Clone)]
+pub struct Registry {
+    root: PathBuf,
+}
+
+impl Registry {
+    /// Opens the registry at `root`. The directory is created by the first registration.
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<Self> {
+        Ok(Self {
+            root: root.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Names of all subjects with at least one version, sorted
+    pub fn subjects(&self) -> Result<Vec<String>> {
+        let mut subjects = vec![];
+        if !self.root.exists() {
+            return Ok(subjects);
+        }
+        for entry in fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                let subject = entry.file_name().to_string_lossy().into_owned();
+                if !self.versions(&subject)?.is_empty() {
+                    subjects.push(subject);
+                }
+            }
+        }
+        subjects.sort();
+        Ok(subjects)
+    }
+
+    /// Compatibility mode of `subject`, the default mode unless one was configured
+    pub fn compatibility(&self, subject: &str) -> Result<Compatibility> {
+        Ok(self.config(subject)?.compatibility)
+    }
+
+    pub fn set_compatibility(&self, subject: &str, compatibility: Compatibility) -> Result<()> {
+        let config = SubjectConfig {
+            compatibility,
+            ..self.config(subject)?
+        };
+        self.write_config(subject, &config)
+    }
+
+    /// Registers `schema` as the next version of `subject`.
+    ///
+    /// Returns the latest version unchanged when it already has the same fingerprint, and
+    /// fails with [`SchemaError::Incompatible`] when the schema breaks the subject's
+    /// compatibility mode.
+    pub fn register(&self, subject: &str, schema: &Schema) -> Result<SchemaVersion> {
+        let fingerprint = fingerprint(schema)?;
+        let latest = self.versions(subject)?.last().copied();
+
+        if let Some(latest) = latest {
+            let latest = self.get(subject, latest)?;
+            if latest.fingerprint == fingerprint {
+                return Ok(latest);
+            }
+            let mode = self.compatibility(subject)?;
+            let violations = compat::check(&latest.schema, schema, mode);
+            if !violations.is_empty() {
+                let violations: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                return Err(SchemaError::Incompatible(format!(
+                    "{} version {} ({}): {}",
+                    subject,
+                    latest.version,
+                    mode,
+                    violations.join("; ")
+                )));
+            }
+        }
+
+        let registered_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let mut config = self.config(subject)?;
+        // versions registered before the counter existed are only known from their files
+        config.last_version = config.last_version.max(latest.unwrap_or_default()) + 1;
+        let version = SchemaVersion {
+            subject: subject.to_owned(),
+            version: config.last_version,
+            fingerprint,
+            registered_at,
+            schema: schema.clone(),
+        };
+
+        self.write_config(subject, &config)?;
+        fs::write(
+            self.version_path(subject, version.version)?,
+            serde_json::to_string_pretty(&version)?,
+        )?;
+        Ok(version)
+    }
+
+    pub fn get(&self, subject: &str, version: u32) -> Result<SchemaVersion> {
+        let path = self.version_path(subject, version)?;
+        if !path.exists() {
+            return Err(SchemaError::NotFound(format!("{} version {}", subject, version)));
+        }
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    pub fn latest(&self, subject: &str) -> Result<SchemaVersion> {
+        match self.versions(subject)?.last() {
+            Some(version) => self.get(subject, *version),
+            None => Err(SchemaError::NotFound(format!("subject {}", subject))),
+        }
+    }
+
+    /// Registered version numbers of `subject` in ascending order
+    pub fn versions(&self, subject: &str) -> Result<Vec<u32>> {
+        let dir = self.subject_dir(subject)?;
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut versions = vec![];
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            let version = name
+                .strip_prefix('v')
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|version| version.parse().ok());
+            if let Some(version) = version {
+                versions.push(version);
+            }
+        }
+        versions.sort_unstable();
+        Ok(versions)
+    }
+
+    /// Deletes a single version. Later registrations are checked against the highest
+    /// remaining version, but numbered after the highest version ever registered.
+    pub fn delete(&self, subject: &str, version: u32) -> Result<()> {
+        let path = self.version_path(subject, version)?;
+        if !path.exists() {
+            return Err(SchemaError::NotFound(format!("{} version {}", subject, version)));
+        }
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    fn config(&self, subject: &str) -> Result<SubjectConfig> {
+        let path = self.subject_dir(subject)?.join("config.json");
+        if !path.exists() {
+            return Ok(SubjectConfig::default());
+        }
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    /// Writes the config of `subject`, creating the registry and subject directories
+    fn write_config(&self, subject: &str, config: &SubjectConfig) -> Result<()> {
+        let dir = self.subject_dir(subject)?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("config.json"), serde_json::to_string_pretty(config)?)?;
+        Ok(())
+    }
+
+    fn subject_dir(&self, subject: &str) -> Result<PathBuf> {
+        let valid = !subject.is_empty()
+            && subject
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            && subject != "."
+            && subject != "..";
+        if !valid {
+            return Err(SchemaError::InvalidInput(format!(
+                "subject '{}' may only contain letters, digits, '-', '_' and '.'",
+                subject
+            )));
+        }
+        Ok(self.root.join(subject))
+    }
+
+    fn version_path(&self, subject: &str, version: u32) -> Result<PathBuf> {
+        Ok(self.subject_dir(subject)?.join(format!("v{}.json", version)))
+    }
+}
@@ -0,0 +1,294 @@
+//! Repair of newline delimited JSON records whose values drifted from the stored schema.
+//!
+//! Values that do not fit their field are coerced where the intent is unambiguous: numeric
+//! strings become numbers, `"true"` and `"false"` become booleans, integral floats become
+//! integers and numbers and booleans become strings. Epoch values of dates and timestamps
+//! given as floats become integers, which are read in the unit of the type. Strings are only
+//! accepted for dates and timestamps in ISO 8601 form, e.g. `2021-01-01` and
+//! `2021-01-01T00:00:00Z`; other strings, including numeric ones, are not coerced.
+//! Structs and lists are repaired value by value. A record with a value that cannot be
+//! coerced, or a required value that is missing, is quarantined unchanged. Fields that are
+//! not part of the schema are kept.
+
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, Write};
+
+use arrow::compute::kernels::cast_utils::string_to_timestamp_nanos;
+use arrow::datatypes::{DataType, Field, Schema};
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+use crate::error::Result;
+use crate::records::{json_path, value_matches};
+
+/// A value that was changed to fit the schema
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Coercion {
+    /// 1-based line number in the input
+    pub line: usize,
+    /// JSON path of the value, e.g. `$.payload.scores[1]`
+    pub field: String,
+    pub expected: DataType,
+    pub from: Value,
+    pub to: Value,
+}
+
+impl Display for Coercion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: {}: {} -> {} ({:?})",
+            self.line, self.field, self.from, self.to, self.expected
+        )
+    }
+}
+
+/// A record that was written to the quarantine instead of the output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuarantinedRecord {
+    /// 1-based line number in the input
+    pub line: usize,
+    /// JSON paths of the values that could not be coerced, empty when the line is not a JSON object
+    pub fields: Vec<String>,
+}
+
+impl Display for QuarantinedRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.fields.is_empty() {
+            write!(f, "line {}: quarantined, not a JSON object", self.line)
+        } else {
+            write!(f, "line {}: quarantined, cannot coerce {}", self.line, self.fields.join(", "))
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct RepairReport {
+    /// Number of non-empty lines that were read
+    pub records: usize,
+    /// Number of records written to the output, repaired or not
+    pub written: usize,
+    pub coercions: Vec<Coercion>,
+    pub quarantined: Vec<QuarantinedRecord>,
+}
+
+impl RepairReport {
+    /// Whether every record already fit the schema
+    pub fn is_clean(&self) -> bool {
+        self.coercions.is_empty() && self.quarantined.is_empty()
+    }
+}
+
+impl Display for RepairReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for coercion in &self.coercions {
+            writeln!(f, "{}", coercion)?;
+        }
+        for record in &self.quarantined {
+            writeln!(f, "{}", record)?;
+        }
+        writeln!(
+            f,
+            "{} record(s) read, {} written, {} value(s) coerced, {} quarantined",
+            self.records,
+            self.written,
+            self.coercions.len(),
+            self.quarantined.len()
+        )
+    }
+}
+
+/// Repairs every line of `reader`, writing records that fit `schema` after coercion to
+/// `output` and the original lines of all other records to `quarantine`
+pub fn repair_records<R: BufRead, W: Write, Q: Write>(
+    reader: R,
+    schema: &Schema,
+    mut output: W,
+    mut quarantine: Q,
+) -> Result<RepairReport> {
+    let mut report = RepairReport::default();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        report.records += 1;
+
+        let mut record = match serde_json::from_str::<Value>(&line) {
+            Ok(Value::Object(record)) => record,
+            _ => {
+                writeln!(quarantine, "{}", line)?;
+                report.quarantined.push(QuarantinedRecord {
+                    line: index + 1,
+                    fields: vec![],
+                });
+                continue;
+            }
+        };
+
+        let mut repair = Repair {
+            line: index + 1,
+            coercions: vec![],
+            failures: vec![],
+        };
+        for field in schema.fields() {
+            let path = json_path("$", field.name());
+            match record.get_mut(field.name()) {
+                Some(value) => repair.field(&path, field, value),
+                None if field.is_nullable() => {}
+                None => repair.failures.push(path),
+            }
+        }
+
+        if repair.failures.is_empty() {
+            writeln!(output, "{}", Value::Object(record))?;
+            report.written += 1;
+            report.coercions.extend(repair.coercions);
+        } else {
+            writeln!(quarantine, "{}", line)?;
+            report.quarantined.push(QuarantinedRecord {
+                line: index + 1,
+                fields: repair.failures,
+            });
+        }
+    }
+
+    output.flush()?;
+    quarantine.flush()?;
+    Ok(report)
+}
+
+/// Coercions and failures of a single record
+struct Repair {
+    line: usize,
+    coercions: Vec<Coercion>,
+    failures: Vec<String>,
+}
+
+impl Repair {
+    fn field(&mut self, path: &str, field: &Field, value: &mut Value) {
+        if value.is_null() {
+            if !field.is_nullable() {
+                self.failures.push(path.to_owned());
+            }
+            return;
+        }
+
+        match (field.data_type(), value) {
+            (DataType::Struct(fields), Value::Object(object)) => {
+                for child in fields {
+                    let child_path = json_path(path, child.name());
+                    match object.get_mut(child.name()) {
+                        Some(value) => self.field(&child_path, child, value),
+                        None if child.is_nullable() => {}
+                        None => self.failures.push(child_path),
+                    }
+                }
+            }
+            (DataType::List(item), Value::Array(values)) | (DataType::LargeList(item), Value::Array(values)) => {
+                for (index, value) in values.iter_mut().enumerate() {
+                    self.field(&format!("{}[{}]", path, index), item, value);
+                }
+            }
+            (data_type, value) if value_matches(data_type, value) && !is_non_iso_date(data_type, value) => {}
+            (data_type, value) => match coerce(data_type, value) {
+                Some(coerced) => {
+                    self.coercions.push(Coercion {
+                        line: self.line,
+                        field: path.to_owned(),
+                        expected: data_type.clone(),
+                        from: std::mem::replace(value, coerced.clone()),
+                        to: coerced,
+                    });
+                }
+                None => self.failures.push(path.to_owned()),
+            },
+        }
+    }
+}
+
+/// The value `value` stands for as `data_type`, if it is unambiguous
+fn coerce(data_type: &DataType, value: &Value) -> Option<Value> {
+    let coerced = match (data_type, value) {
+        (DataType::Boolean, Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => return None,
+        },
+        (DataType::Date32, Value::String(_))
+        | (DataType::Date64, Value::String(_))
+        | (DataType::Timestamp(_, _), Value::String(_)) => return None,
+        (DataType::Int8, _)
+        | (DataType::Int16, _)
+        | (DataType::Int32, _)
+        | (DataType::Int64, _)
+        | (DataType::Date32, _)
+        | (DataType::Date64, _)
+        | (DataType::Timestamp(_, _), _) => Value::from(as_i64(value)?),
+        (DataType::UInt8, _) | (DataType::UInt16, _) | (DataType::UInt32, _) | (DataType::UInt64, _) => {
+            Value::from(as_u64(value)?)
+        }
+        (DataType::Float16, Value::String(s))
+        | (DataType::Float32, Value::String(s))
+        | (DataType::Float64, Value::String(s))
+        | (DataType::Decimal(_, _), Value::String(s)) => {
+            Value::Number(Number::from_f64(s.trim().parse().ok()?)?)
+        }
+        (DataType::Utf8, Value::Number(_))
+        | (DataType::Utf8, Value::Bool(_))
+        | (DataType::LargeUtf8, Value::Number(_))
+        | (DataType::LargeUtf8, Value::Bool(_)) => Value::String(value.to_string()),
+        (DataType::Dictionary(_, values), _) => coerce(values, value)?,
+        _ => return None,
+    };
+    // e.g. integers that are out of range for the type
+    if value_matches(data_type, &coerced) {
+        Some(coerced)
+    } else {
+        None
+    }
+}
+
+/// Dates and timestamps are read from strings only in ISO 8601 form, so that e.g. `"20210101"`
+/// is not taken for an epoch value
+fn is_non_iso_date(data_type: &DataType, value: &Value) -> bool {
+    match (data_type, value) {
+        (DataType::Date32, Value::String(s)) | (DataType::Date64, Value::String(s)) => {
+            s.parse::<NaiveDate>().is_err()
+        }
+        (DataType::Timestamp(_, _), Value::String(s)) => string_to_timestamp_nanos(s).is_err(),
+        _ => false,
+    }
+}
+
+/// Integral numbers, floats without a fraction and strings of either
+fn as_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().and_then(integral_f64)),
+        Value::String(s) => {
+            let s = s.trim();
+            s.parse().ok().or_else(|| s.parse::<f64>().ok().and_then(integral_f64))
+        }
+        _ => None,
+    }
+}
+
+/// `f` as an integer when it has no fraction and is in range; `as` would saturate.
+/// `i64::MAX as f64` rounds up to 2^63, which is out of range.
+fn integral_f64(f: f64) -> Option<i64> {
+    if f.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(&f) {
+        Some(f as i64)
+    } else {
+        None
+    }
+}
+
+fn as_u64(value: &Value) -> Option<u64> {
+    as_i64(value).and_then(|v| u64::try_from(v).ok()).or_else(|| match value {
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
@@ -0,0 +1,577 @@
+//! A readable, line based schema format for schema files under review.
+//!
+//! ```text
+//! # schema-validate schema v1
+//! @owner "data-platform"
+//! id int64
+//!   @description "Primary key"
+//!   @constraints {"required":true,"unique":true}
+//! country dictionary<int32, utf8>?
+//! created timestamp<ms, "UTC">?
+//! tags list<utf8?>?
+//! payload struct?
+//!   name utf8
+//!   "score %" float64?
+//! ```
+//!
+//! Every field is one line of name, type and a trailing `?` when it is nullable, followed by
+//! `dict_id=<n>` and `ordered` for dictionary fields that set them. The lines indented below
+//! a field hold its metadata (`@key value`) and, for structs and lists of structs, its
+//! children. Metadata lines before the first field belong to the schema. Names that are not
+//! plain identifiers and metadata values are JSON strings, values that are canonical JSON
+//! objects or arrays, like constraints, are written unquoted. Types without a short form are
+//! written as `arrow<...>` holding their serde JSON, so every schema converts losslessly,
+//! except that empty field metadata is read back as no metadata.
+
+use std::collections::{BTreeMap, HashMap};
+
+use arrow::datatypes::{DataType, Field, IntervalUnit, Schema, TimeUnit};
+use serde_json::Value;
+
+use crate::error::{Result, SchemaError};
+
+pub const HEADER: &str = "# schema-validate schema v1";
+
+const INDENT: &str = "  ";
+
+/// Whether `contents` is in this format, i.e. its first line is the [`HEADER`]
+pub fn is_text_format(contents: &str) -> bool {
+    contents.trim_start().lines().next().map(str::trim_end) == Some(HEADER)
+}
+
+/// Renders `schema` in the text format
+pub fn to_text(schema: &Schema) -> String {
+    let mut text = String::new();
+    text.push_str(HEADER);
+    text.push('\n');
+    let metadata: BTreeMap<&String, &String> = schema.metadata().iter().collect();
+    for (key, value) in metadata {
+        text.push_str(&metadata_line(key, value));
+        text.push('\n');
+    }
+    write_fields(&mut text, schema.fields(), 0);
+    text
+}
+
+fn write_fields(text: &mut String, fields: &[Field], depth: usize) {
+    for field in fields {
+        let indent = INDENT.repeat(depth);
+        let data_type = type_text(field.data_type());
+        text.push_str(&format!("{}{} {}", indent, name_text(field.name()), data_type));
+        if field.is_nullable() {
+            text.push('?');
+        }
+        if let Some(dict_id) = field.dict_id().filter(|dict_id| *dict_id != 0) {
+            text.push_str(&format!(" dict_id={}", dict_id));
+        }
+        if field.dict_is_ordered() == Some(true) {
+            text.push_str(" ordered");
+        }
+        text.push('\n');
+
+        if let Some(metadata) = field.metadata() {
+            for (key, value) in metadata {
+                text.push_str(&format!("{}{}{}\n", indent, INDENT, metadata_line(key, value)));
+            }
+        }
+        if !data_type.starts_with("arrow<") {
+            if let Some(children) = struct_children(field.data_type()) {
+                write_fields(text, children, depth + 1);
+            }
+        }
+    }
+}
+
+fn metadata_line(key: &str, value: &str) -> String {
+    format!("@{} {}", name_text(key), value_text(value))
+}
+
+fn name_text(name: &str) -> String {
+    let plain = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        name.to_owned()
+    } else {
+        Value::String(name.to_owned()).to_string()
+    }
+}
+
+/// Canonical JSON objects and arrays are written as they are, everything else as a string
+fn value_text(value: &str) -> String {
+    match serde_json::from_str::<Value>(value) {
+        Ok(json @ Value::Object(_)) | Ok(json @ Value::Array(_))
+            if serde_json::to_string(&json).is_ok_and(|canonical| canonical == value) =>
+        {
+            value.to_owned()
+        }
+        _ => Value::String(value.to_owned()).to_string(),
+    }
+}
+
+/// Children of a struct, or of the struct at the end of a chain of lists
+fn struct_children(data_type: &DataType) -> Option<&Vec<Field>> {
+    match data_type {
+        DataType::Struct(fields) => Some(fields),
+        DataType::List(item) | DataType::LargeList(item) | DataType::FixedSizeList(item, _) => {
+            struct_children(item.data_type())
+        }
+        _ => None,
+    }
+}
+
+fn type_text(data_type: &DataType) -> String {
+    short_type_text(data_type).unwrap_or_else(|| {
+        format!(
+            "arrow<{}>",
+            serde_json::to_string(data_type).expect("DataType is always serializable")
+        )
+    })
+}
+
+fn short_type_text(data_type: &DataType) -> Option<String> {
+    let text = match data_type {
+        DataType::Null => "null".to_owned(),
+        DataType::Boolean => "boolean".to_owned(),
+        DataType::Int8 => "int8".to_owned(),
+        DataType::Int16 => "int16".to_owned(),
+        DataType::Int32 => "int32".to_owned(),
+        DataType::Int64 => "int64".to_owned(),
+        DataType::UInt8 => "uint8".to_owned(),
+        DataType::UInt16 => "uint16".to_owned(),
+        DataType::UInt32 => "uint32".to_owned(),
+        DataType::UInt64 => "uint64".to_owned(),
+        DataType::Float16 => "float16".to_owned(),
+        DataType::Float32 => "float32".to_owned(),
+        DataType::Float64 => "float64".to_owned(),
+        DataType::Utf8 => "utf8".to_owned(),
+        DataType::LargeUtf8 => "large_utf8".to_owned(),
+        DataType::Binary => "binary".to_owned(),
+        DataType::LargeBinary => "large_binary".to_owned(),
+        DataType::FixedSizeBinary(size) => format!("fixed_size_binary<{}>", size),
+        DataType::Date32 => "date32".to_owned(),
+        DataType::Date64 => "date64".to_owned(),
+        DataType::Time32(unit) => format!("time32<{}>", unit_text(unit)),
+        DataType::Time64(unit) => format!("time64<{}>", unit_text(unit)),
+        DataType::Timestamp(unit, None) => format!("timestamp<{}>", unit_text(unit)),
+        DataType::Timestamp(unit, Some(tz)) => {
+            format!("timestamp<{}, {}>", unit_text(unit), Value::String(tz.clone()))
+        }
+        DataType::Duration(unit) => format!("duration<{}>", unit_text(unit)),
+        DataType::Interval(IntervalUnit::YearMonth) => "interval<year_month>".to_owned(),
+        DataType::Interval(IntervalUnit::DayTime) => "interval<day_time>".to_owned(),
+        DataType::Decimal(precision, scale) => format!("decimal<{}, {}>", precision, scale),
+        DataType::List(item) => format!("list<{}>", item_text(item)?),
+        DataType::LargeList(item) => format!("large_list<{}>", item_text(item)?),
+        DataType::FixedSizeList(item, size) => format!("fixed_size_list<{}, {}>", item_text(item)?, size),
+        DataType::Struct(_) => "struct".to_owned(),
+        DataType::Dictionary(keys, values) if struct_children(values).is_none() => {
+            format!("dictionary<{}, {}>", short_type_text(keys)?, short_type_text(values)?)
+        }
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// List items named `item` are written as their type only; items with metadata or
+/// dictionary settings have no short form
+fn item_text(item: &Field) -> Option<String> {
+    if item.metadata().is_some() || item.dict_id().unwrap_or(0) != 0 || item.dict_is_ordered() == Some(true) {
+        return None;
+    }
+    let mut text = short_type_text(item.data_type())?;
+    if item.is_nullable() {
+        text.push('?');
+    }
+    if item.name() != "item" {
+        text = format!("{}: {}", name_text(item.name()), text);
+    }
+    Some(text)
+}
+
+fn unit_text(unit: &TimeUnit) -> &'static str {
+    match unit {
+        TimeUnit::Second => "s",
+        TimeUnit::Millisecond => "ms",
+        TimeUnit::Microsecond => "us",
+        TimeUnit::Nanosecond => "ns",
+    }
+}
+
+/// Parses a schema in the text format; a schema needs at least one field
+pub fn from_text(text: &str) -> Result<Schema> {
+    let mut lines = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let indent = &line[..line.len() - content.len()];
+        let line = Line {
+            number: index + 1,
+            depth: indent.len() / INDENT.len(),
+            content: content.trim_end(),
+        };
+        if indent.contains('\t') {
+            return Err(line.error("indentation must use spaces, not tabs"));
+        }
+        if indent.len() % INDENT.len() != 0 {
+            return Err(line.error(&format!("indentation must be a multiple of {} spaces", INDENT.len())));
+        }
+        lines.push(line);
+    }
+
+    let mut position = 0;
+    let mut metadata = HashMap::new();
+    while position < lines.len() && lines[position].content.starts_with('@') {
+        let line = &lines[position];
+        if line.depth != 0 {
+            return Err(line.error("schema metadata must not be indented"));
+        }
+        let (key, value) = parse_metadata(line)?;
+        metadata.insert(key, value);
+        position += 1;
+    }
+
+    let fields = parse_fields(&lines, &mut position, 0)?;
+    if let Some(line) = lines.get(position) {
+        return Err(line.error("unexpected indentation"));
+    }
+    if fields.is_empty() {
+        return Err(SchemaError::InvalidInput("schema has no fields".to_owned()));
+    }
+    Ok(Schema::new_with_metadata(fields, metadata))
+}
+
+struct Line<'a> {
+    number: usize,
+    depth: usize,
+    content: &'a str,
+}
+
+impl Line<'_> {
+    fn error(&self, message: &str) -> SchemaError {
+        SchemaError::InvalidInput(format!("schema line {}: {}", self.number, message))
+    }
+}
+
+fn parse_fields(lines: &[Line], position: &mut usize, depth: usize) -> Result<Vec<Field>> {
+    let mut fields = vec![];
+    while let Some(line) = lines.get(*position) {
+        if line.depth < depth {
+            break;
+        }
+        if line.depth > depth || line.content.starts_with('@') {
+            return Err(line.error("unexpected indentation"));
+        }
+        *position += 1;
+
+        let mut cursor = Cursor::new(line.content);
+        let name = cursor.name().map_err(|e| line.error(&e))?;
+        // `arrow<...>` types are complete, their children are not on the following lines
+        let verbatim = cursor.rest().trim_start().starts_with("arrow<");
+        let data_type = cursor.data_type().map_err(|e| line.error(&e))?;
+        let nullable = cursor.eat('?');
+        let mut dict_id = 0;
+        let mut dict_is_ordered = false;
+        loop {
+            cursor.skip_whitespace();
+            if cursor.is_done() {
+                break;
+            } else if cursor.eat_word("dict_id=") {
+                dict_id = cursor.number().map_err(|e| line.error(&e))? as i64;
+            } else if cursor.eat_word("ordered") {
+                dict_is_ordered = true;
+            } else {
+                return Err(line.error(&format!("unexpected '{}'", cursor.rest())));
+            }
+        }
+
+        let mut metadata = BTreeMap::new();
+        while let Some(child) = lines.get(*position) {
+            if child.depth != depth + 1 || !child.content.starts_with('@') {
+                break;
+            }
+            let (key, value) = parse_metadata(child)?;
+            metadata.insert(key, value);
+            *position += 1;
+        }
+
+        let data_type = if !verbatim && struct_children(&data_type).is_some() {
+            let children = parse_fields(lines, position, depth + 1)?;
+            with_struct_children(data_type, children)
+        } else {
+            data_type
+        };
+
+        let mut field = Field::new_dict(&name, data_type, nullable, dict_id, dict_is_ordered);
+        if !metadata.is_empty() {
+            field.set_metadata(Some(metadata));
+        }
+        fields.push(field);
+    }
+    Ok(fields)
+}
+
+/// Replaces the (empty) struct at the end of a chain of lists with one of `children`
+fn with_struct_children(data_type: DataType, children: Vec<Field>) -> DataType {
+    let item = |item: &Field, children| {
+        Box::new(Field::new_dict(
+            item.name(),
+            with_struct_children(item.data_type().clone(), children),
+            item.is_nullable(),
+            item.dict_id().unwrap_or(0),
+            item.dict_is_ordered().unwrap_or(false),
+        ))
+    };
+    match data_type {
+        DataType::Struct(_) => DataType::Struct(children),
+        DataType::List(list_item) => DataType::List(item(&list_item, children)),
+        DataType::LargeList(list_item) => DataType::LargeList(item(&list_item, children)),
+        DataType::FixedSizeList(list_item, size) => DataType::FixedSizeList(item(&list_item, children), size),
+        data_type => data_type,
+    }
+}
+
+fn parse_metadata(line: &Line) -> Result<(String, String)> {
+    let mut cursor = Cursor::new(&line.content[1..]);
+    let key = cursor.name().map_err(|e| line.error(&e))?;
+    cursor.skip_whitespace();
+    let value = match cursor.json().map_err(|e| line.error(&e))? {
+        Value::String(value) => value,
+        value => value.to_string(),
+    };
+    cursor.skip_whitespace();
+    if !cursor.is_done() {
+        return Err(line.error(&format!("unexpected '{}'", cursor.rest())));
+    }
+    Ok((key, value))
+}
+
+/// Reads the tokens of a single line
+struct Cursor<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, position: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn is_done(&self) -> bool {
+        self.rest().is_empty()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if self.rest().starts_with(word) {
+            self.position += word.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> std::result::Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at '{}'", c, self.rest()))
+        }
+    }
+
+    fn identifier(&mut self) -> std::result::Result<&'a str, String> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let length = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(format!("expected a name at '{}'", rest));
+        }
+        self.position += length;
+        Ok(&rest[..length])
+    }
+
+    fn number(&mut self) -> std::result::Result<usize, String> {
+        let identifier = self.identifier()?;
+        identifier
+            .parse()
+            .map_err(|_| format!("expected a number, found '{}'", identifier))
+    }
+
+    fn json(&mut self) -> std::result::Result<Value, String> {
+        self.skip_whitespace();
+        let mut values = serde_json::Deserializer::from_str(self.rest()).into_iter::<Value>();
+        match values.next() {
+            Some(Ok(value)) => {
+                self.position += values.byte_offset();
+                Ok(value)
+            }
+            _ => Err(format!("expected a JSON value at '{}'", self.rest())),
+        }
+    }
+
+    /// A plain identifier or a JSON string
+    fn name(&mut self) -> std::result::Result<String, String> {
+        self.skip_whitespace();
+        if self.rest().starts_with('"') {
+            match self.json()? {
+                Value::String(name) => Ok(name),
+                _ => unreachable!("a JSON value starting with '\"' is a string"),
+            }
+        } else {
+            Ok(self.identifier()?.to_owned())
+        }
+    }
+
+    fn unit(&mut self) -> std::result::Result<TimeUnit, String> {
+        match self.identifier()? {
+            "s" => Ok(TimeUnit::Second),
+            "ms" => Ok(TimeUnit::Millisecond),
+            "us" => Ok(TimeUnit::Microsecond),
+            "ns" => Ok(TimeUnit::Nanosecond),
+            unit => Err(format!("unknown time unit '{}'", unit)),
+        }
+    }
+
+    fn item(&mut self) -> std::result::Result<Box<Field>, String> {
+        let start = self.position;
+        let name = match self.name() {
+            Ok(name) if self.eat(':') => name,
+            _ => {
+                self.position = start;
+                "item".to_owned()
+            }
+        };
+        let data_type = self.data_type()?;
+        let nullable = self.eat('?');
+        Ok(Box::new(Field::new(&name, data_type, nullable)))
+    }
+
+    /// Structs are returned without children, they follow on the next lines
+    fn data_type(&mut self) -> std::result::Result<DataType, String> {
+        let data_type = match self.identifier()? {
+            "null" => DataType::Null,
+            "boolean" => DataType::Boolean,
+            "int8" => DataType::Int8,
+            "int16" => DataType::Int16,
+            "int32" => DataType::Int32,
+            "int64" => DataType::Int64,
+            "uint8" => DataType::UInt8,
+            "uint16" => DataType::UInt16,
+            "uint32" => DataType::UInt32,
+            "uint64" => DataType::UInt64,
+            "float16" => DataType::Float16,
+            "float32" => DataType::Float32,
+            "float64" => DataType::Float64,
+            "utf8" => DataType::Utf8,
+            "large_utf8" => DataType::LargeUtf8,
+            "binary" => DataType::Binary,
+            "large_binary" => DataType::LargeBinary,
+            "date32" => DataType::Date32,
+            "date64" => DataType::Date64,
+            "struct" => DataType::Struct(vec![]),
+            "fixed_size_binary" => {
+                self.expect('<')?;
+                let size = self.number()? as i32;
+                self.expect('>')?;
+                DataType::FixedSizeBinary(size)
+            }
+            name @ "time32" | name @ "time64" | name @ "duration" => {
+                self.expect('<')?;
+                let unit = self.unit()?;
+                self.expect('>')?;
+                match name {
+                    "time32" => DataType::Time32(unit),
+                    "time64" => DataType::Time64(unit),
+                    _ => DataType::Duration(unit),
+                }
+            }
+            "timestamp" => {
+                self.expect('<')?;
+                let unit = self.unit()?;
+                let tz = if self.eat(',') {
+                    match self.json()? {
+                        Value::String(tz) => Some(tz),
+                        tz => return Err(format!("expected a time zone string, found {}", tz)),
+                    }
+                } else {
+                    None
+                };
+                self.expect('>')?;
+                DataType::Timestamp(unit, tz)
+            }
+            "interval" => {
+                self.expect('<')?;
+                let unit = match self.identifier()? {
+                    "year_month" => IntervalUnit::YearMonth,
+                    "day_time" => IntervalUnit::DayTime,
+                    unit => return Err(format!("unknown interval unit '{}'", unit)),
+                };
+                self.expect('>')?;
+                DataType::Interval(unit)
+            }
+            "decimal" => {
+                self.expect('<')?;
+                let precision = self.number()?;
+                self.expect(',')?;
+                let scale = self.number()?;
+                self.expect('>')?;
+                DataType::Decimal(precision, scale)
+            }
+            name @ "list" | name @ "large_list" => {
+                self.expect('<')?;
+                let item = self.item()?;
+                self.expect('>')?;
+                if name == "list" {
+                    DataType::List(item)
+                } else {
+                    DataType::LargeList(item)
+                }
+            }
+            "fixed_size_list" => {
+                self.expect('<')?;
+                let item = self.item()?;
+                self.expect(',')?;
+                let size = self.number()? as i32;
+                self.expect('>')?;
+                DataType::FixedSizeList(item, size)
+            }
+            "dictionary" => {
+                self.expect('<')?;
+                let keys = self.data_type()?;
+                self.expect(',')?;
+                let values = self.data_type()?;
+                self.expect('>')?;
+                DataType::Dictionary(Box::new(keys), Box::new(values))
+            }
+            "arrow" => {
+                self.expect('<')?;
+                let data_type = serde_json::from_value(self.json()?).map_err(|e| e.to_string())?;
+                self.expect('>')?;
+                data_type
+            }
+            name => return Err(format!("unknown type '{}'", name)),
+        };
+        Ok(data_type)
+    }
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn run(args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_rust-scratch-space"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+fn path(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+#[test]
+fn rejected_registrations_exit_as_incompatible() {
+    let dir = common::scratch_dir("cli-register");
+    let registry = dir.join("registry");
+    fs::write(dir.join("v1.json"), "{\"a\": 1}\n").unwrap();
+    fs::write(dir.join("v2.json"), "{\"a\": \"one\"}\n").unwrap();
+
+    assert_eq!(run(&["registry", path(&registry), "register", "events", path(&dir.join("v1.json"))]), 0);
+    assert_eq!(run(&["registry", path(&registry), "register", "events", path(&dir.join("v2.json"))]), 2);
+    assert_eq!(run(&["registry", path(&registry), "register", "events", path(&dir.join("missing.json"))]), 3);
+}
+
+#[test]
+fn rejected_merges_exit_as_incompatible() {
+    let dir = common::scratch_dir("cli-merge");
+    let samples = dir.join("samples");
+    let registry = dir.join("registry");
+    fs::create_dir(&samples).unwrap();
+    fs::write(samples.join("a.json"), "{\"a\": 1}\n").unwrap();
+    let merge = |samples: &Path| run(&["merge", path(samples), "--registry", path(&registry), "--subject", "events"]);
+
+    assert_eq!(merge(&samples), 0);
+    fs::write(samples.join("a.json"), "{\"a\": \"one\"}\n").unwrap();
+    assert_eq!(merge(&samples), 2);
+    assert_eq!(merge(&dir.join("empty")), 3);
+}
@@ -0,0 +1,109 @@
+//! The generated code is checked in under `tests/generated`, so it is compiled with this test.
+//! `generated_code_is_up_to_date` fails when the generator output drifts from it.
+
+use std::fs;
+
+use arrow::array::{Array, BooleanArray, Float64Array, Int64Array, ListArray, StringArray, StructArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::codegen::rust_structs;
+
+mod record {
+    include!("generated/record.rs");
+}
+
+mod event {
+    include!("generated/event.rs");
+}
+
+fn event_schema() -> Schema {
+    let feature = DataType::Struct(vec![
+        Field::new("key", DataType::Utf8, true),
+        Field::new("value", DataType::Int64, true),
+    ]);
+    Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("type", DataType::Utf8, false),
+        Field::new("userAgent", DataType::Utf8, true),
+        Field::new("tags", DataType::List(Box::new(Field::new("item", DataType::Utf8, true))), true),
+        Field::new(
+            "payload",
+            DataType::Struct(vec![
+                Field::new("name", DataType::Utf8, false),
+                Field::new("score", DataType::Float64, true),
+                Field::new("features", DataType::List(Box::new(Field::new("item", feature, true))), true),
+            ]),
+            true,
+        ),
+    ])
+}
+
+#[test]
+fn generated_code_is_up_to_date() {
+    let schema = schema_validate::load("data/schema.txt").unwrap();
+    assert_eq!(
+        rust_structs(&schema, "record").unwrap(),
+        fs::read_to_string("tests/generated/record.rs").unwrap()
+    );
+    assert_eq!(
+        rust_structs(&event_schema(), "event").unwrap(),
+        fs::read_to_string("tests/generated/event.rs").unwrap()
+    );
+}
+
+#[test]
+fn flat_structs_to_record_batch() {
+    let records = vec![
+        record::Record {
+            a: Some(1),
+            b: Some(2.0),
+            c: Some(false),
+            d: Some(4),
+        },
+        record::Record {
+            a: None,
+            b: Some(0.5),
+            c: Some(true),
+            d: None,
+        },
+    ];
+    let batch = record::to_record_batch(&records).unwrap();
+
+    assert_eq!(batch.schema().as_ref(), &record::schema());
+    assert_eq!(batch.num_rows(), 2);
+    let a = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(a.value(0), 1);
+    assert!(a.is_null(1));
+    let b = batch.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(b.value(1), 0.5);
+    let c = batch.column(2).as_any().downcast_ref::<BooleanArray>().unwrap();
+    assert!(c.value(1));
+}
+
+#[test]
+fn nested_structs_to_record_batch() {
+    let records = vec![event::Event {
+        id: 7,
+        type_: "click".to_owned(),
+        user_agent: None,
+        tags: Some(vec![Some("serde".to_owned()), Some("json".to_owned())]),
+        payload: Some(event::EventPayload {
+            name: "button".to_owned(),
+            score: Some(0.25),
+            features: Some(vec![Some(event::EventPayloadFeatures {
+                key: Some("color".to_owned()),
+                value: Some(3),
+            })]),
+        }),
+    }];
+    let batch = event::to_record_batch(&records).unwrap();
+
+    assert_eq!(batch.schema().as_ref(), &event::schema());
+    assert_eq!(batch.num_rows(), 1);
+    let kind = batch.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(kind.value(0), "click");
+    let payload = batch.column(4).as_any().downcast_ref::<StructArray>().unwrap();
+    let name = payload.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(name.value(0), "button");
+    let tags = batch.column(3).as_any().downcast_ref::<ListArray>().unwrap();
+    assert_eq!(tags.value_length(0), 2);
+}
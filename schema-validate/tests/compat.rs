@@ -0,0 +1,150 @@
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::compat::{can_promote, check};
+use schema_validate::{validate_with, Compatibility, Outcome, SchemaError};
+
+/// The fields of the violations of `candidate` against `baseline`, by direction
+fn violations(baseline: &Schema, candidate: &Schema, mode: Compatibility) -> Vec<(String, Compatibility)> {
+    check(baseline, candidate, mode)
+        .into_iter()
+        .map(|violation| (violation.field, violation.direction))
+        .collect()
+}
+
+#[test]
+fn modes_parse_case_insensitively() {
+    assert_eq!("FULL".parse::<Compatibility>().unwrap(), Compatibility::Full);
+    assert_eq!("none".parse::<Compatibility>().unwrap(), Compatibility::None);
+    assert!(matches!("sideways".parse::<Compatibility>(), Err(SchemaError::InvalidInput(_))));
+    assert_eq!(Compatibility::default(), Compatibility::Backward);
+}
+
+#[test]
+fn numeric_widening_is_backward_compatible() {
+    let baseline = Schema::new(vec![Field::new("n", DataType::Int32, true)]);
+    let widened = Schema::new(vec![Field::new("n", DataType::Int64, true)]);
+    assert!(violations(&baseline, &widened, Compatibility::Backward).is_empty());
+    assert_eq!(
+        violations(&baseline, &widened, Compatibility::Forward),
+        vec![("n".to_owned(), Compatibility::Forward)]
+    );
+    assert_eq!(
+        violations(&baseline, &widened, Compatibility::Full),
+        vec![("n".to_owned(), Compatibility::Forward)]
+    );
+    assert!(violations(&baseline, &widened, Compatibility::None).is_empty());
+}
+
+#[test]
+fn nullable_additions_are_fully_compatible() {
+    let baseline = Schema::new(vec![Field::new("a", DataType::Int64, false)]);
+    let nullable = Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("b", DataType::Utf8, true),
+    ]);
+    assert!(violations(&baseline, &nullable, Compatibility::Full).is_empty());
+
+    let required = Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("b", DataType::Utf8, false),
+    ]);
+    assert_eq!(
+        violations(&baseline, &required, Compatibility::Full),
+        vec![("b".to_owned(), Compatibility::Backward)]
+    );
+}
+
+#[test]
+fn nullable_fields_cannot_become_required() {
+    let baseline = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+    let required = Schema::new(vec![Field::new("a", DataType::Int64, false)]);
+    assert_eq!(
+        violations(&baseline, &required, Compatibility::Backward),
+        vec![("a".to_owned(), Compatibility::Backward)]
+    );
+    assert!(violations(&baseline, &required, Compatibility::Forward).is_empty());
+}
+
+#[test]
+fn dictionaries_compare_by_value_type() {
+    let plain = Schema::new(vec![Field::new("s", DataType::Utf8, true)]);
+    let dictionary = Schema::new(vec![Field::new(
+        "s",
+        DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+        true,
+    )]);
+    assert!(violations(&plain, &dictionary, Compatibility::Full).is_empty());
+
+    let numbers = Schema::new(vec![Field::new(
+        "s",
+        DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Int64)),
+        true,
+    )]);
+    assert_eq!(violations(&plain, &numbers, Compatibility::Backward).len(), 1);
+}
+
+#[test]
+fn nested_fields_are_reported_by_path() {
+    let item = |nullable| Box::new(Field::new("item", DataType::Int32, nullable));
+    let baseline = Schema::new(vec![Field::new(
+        "payload",
+        DataType::Struct(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("tags", DataType::List(item(true)), true),
+        ]),
+        true,
+    )]);
+    let candidate = Schema::new(vec![Field::new(
+        "payload",
+        DataType::Struct(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("tags", DataType::List(item(false)), true),
+        ]),
+        true,
+    )]);
+    assert_eq!(
+        violations(&baseline, &candidate, Compatibility::Backward),
+        vec![
+            ("payload.id".to_owned(), Compatibility::Backward),
+            ("payload.tags[]".to_owned(), Compatibility::Backward),
+        ]
+    );
+}
+
+#[test]
+fn promotion_rules() {
+    use DataType::*;
+
+    assert!(can_promote(&Int8, &Float32));
+    assert!(can_promote(&UInt32, &Int64));
+    assert!(!can_promote(&UInt64, &Int64));
+    assert!(!can_promote(&Int64, &Float64));
+    assert!(!can_promote(&Int64, &Int32));
+    assert!(can_promote(&Utf8, &LargeUtf8));
+    assert!(can_promote(&Date32, &Date64));
+    assert!(can_promote(&Null, &Utf8));
+    assert!(can_promote(&Decimal(10, 2), &Decimal(12, 3)));
+    assert!(!can_promote(&Decimal(10, 2), &Decimal(10, 3)));
+}
+
+#[test]
+fn validation_outcomes_follow_the_mode() {
+    let baseline = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+    let candidate = Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Utf8, true),
+    ]);
+    let report = validate_with(&baseline, &candidate, Compatibility::Full);
+    assert!(report.is_valid());
+    assert_eq!(report.outcome(), Outcome::Warnings);
+
+    let narrowed = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+    assert_eq!(
+        validate_with(&baseline, &narrowed, Compatibility::Backward).outcome(),
+        Outcome::Incompatible
+    );
+    // no rules in mode none, the type change is still worth a warning
+    assert_eq!(
+        validate_with(&baseline, &narrowed, Compatibility::None).outcome(),
+        Outcome::Warnings
+    );
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::constraints::{attach, check_records, from_schema, load_sidecar, CONSTRAINTS_METADATA_KEY};
+use schema_validate::json_schema::ENUM_METADATA_KEY;
+use schema_validate::{ConstraintSet, Constraints, SchemaError};
+use serde_json::json;
+
+fn constraints() -> ConstraintSet {
+    serde_json::from_value(json!({
+        "id": { "required": true, "unique": true },
+        "score": { "min": 0, "max": 100 },
+        "code": { "pattern": "^[A-Z]{2}$", "min_length": 2, "max_length": 3 },
+        "status": { "enum": ["open", "closed"] }
+    }))
+    .unwrap()
+}
+
+/// `(line, field, constraint)` of every violation in `input`
+fn violations(input: &str, max_errors: usize) -> Vec<(usize, String, String)> {
+    check_records(input.as_bytes(), &constraints(), max_errors)
+        .unwrap()
+        .violations
+        .into_iter()
+        .map(|violation| (violation.line, violation.field, violation.constraint))
+        .collect()
+}
+
+#[test]
+fn every_constraint_is_checked() {
+    let input = r#"{"id": 1, "score": 50, "code": "AB", "status": "open"}
+{"id": 1, "score": 101, "code": "abcd", "status": "gone"}
+{"score": -1, "code": "A"}
+"#;
+    let expected: Vec<(usize, String, String)> = vec![
+        (2, "code", "max_length 3"),
+        (2, "code", "pattern ^[A-Z]{2}$"),
+        (2, "id", "unique (first seen on line 1)"),
+        (2, "score", "max 100"),
+        (2, "status", "enum [\"open\",\"closed\"]"),
+        (3, "code", "min_length 2"),
+        (3, "code", "pattern ^[A-Z]{2}$"),
+        (3, "id", "required"),
+        (3, "score", "min 0"),
+    ]
+    .into_iter()
+    .map(|(line, field, constraint)| (line, field.to_owned(), constraint.to_owned()))
+    .collect();
+    assert_eq!(violations(input, 100), expected);
+}
+
+#[test]
+fn values_of_other_types_are_left_to_type_checks() {
+    let input = r#"{"id": "x", "score": "high", "code": 12, "status": "open"}"#;
+    assert!(violations(input, 100).is_empty());
+}
+
+#[test]
+fn stops_at_the_error_limit() {
+    let input = "{\"score\": 500}\n".repeat(5);
+    let report = check_records(input.as_bytes(), &constraints(), 2).unwrap();
+    assert_eq!(report.violations.len(), 2);
+    assert!(report.truncated);
+}
+
+#[test]
+fn a_zero_error_limit_still_fails_violating_input() {
+    let report = check_records("{\"id\": 1, \"score\": 500}\n".as_bytes(), &constraints(), 0).unwrap();
+    assert!(report.violations.is_empty());
+    assert!(!report.is_valid());
+    assert!(check_records("{\"id\": 1, \"score\": 5}\n".as_bytes(), &constraints(), 0).unwrap().is_valid());
+}
+
+#[test]
+fn invalid_patterns_are_input_errors() {
+    let constraints: ConstraintSet = serde_json::from_value(json!({ "a": { "pattern": "(" } })).unwrap();
+    assert!(matches!(
+        check_records("{}".as_bytes(), &constraints, 10),
+        Err(SchemaError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn constraints_round_trip_through_field_metadata() {
+    let mut status = Field::new("status", DataType::Utf8, true);
+    status.set_metadata(Some(BTreeMap::from([(
+        ENUM_METADATA_KEY.to_owned(),
+        "[\"open\",\"closed\"]".to_owned(),
+    )])));
+    let schema = Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("score", DataType::Float64, true),
+        Field::new("code", DataType::Utf8, true),
+        status,
+    ]);
+    // enums imported from JSON Schema count as constraints
+    assert_eq!(
+        from_schema(&schema).unwrap().get("status").unwrap().values,
+        Some(vec![json!("open"), json!("closed")])
+    );
+
+    let attached = attach(&schema, &constraints()).unwrap();
+    assert!(attached
+        .field_with_name("id")
+        .unwrap()
+        .metadata()
+        .as_ref()
+        .unwrap()
+        .contains_key(CONSTRAINTS_METADATA_KEY));
+    assert_eq!(from_schema(&attached).unwrap(), constraints());
+
+    // attaching again replaces the stored constraints
+    let only_id: ConstraintSet = BTreeMap::from([("id".to_owned(), Constraints {
+        unique: true,
+        ..Constraints::default()
+    })]);
+    let replaced = from_schema(&attach(&attached, &only_id).unwrap()).unwrap();
+    assert_eq!(replaced.len(), 2);
+    assert!(replaced.get("id").unwrap().unique && !replaced.get("id").unwrap().required);
+    assert!(!replaced.contains_key("score"));
+}
+
+#[test]
+fn unknown_fields_and_keys_are_rejected() {
+    let schema = Schema::new(vec![Field::new("id", DataType::Int64, false)]);
+    assert!(matches!(attach(&schema, &constraints()), Err(SchemaError::InvalidInput(_))));
+
+    let path = common::scratch_dir("constraints-sidecar").join("constraints.json");
+    fs::write(&path, r#"{"id": {"minimum": 1}}"#).unwrap();
+    assert!(matches!(load_sidecar(&path), Err(SchemaError::Serde(_))));
+    fs::write(&path, r#"{"id": {"min": 1}}"#).unwrap();
+    assert_eq!(load_sidecar(&path).unwrap().get("id").unwrap().min, Some(1.0));
+}
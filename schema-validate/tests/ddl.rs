@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use schema_validate::ddl::{create_table, Dialect, ExternalLocation};
+use schema_validate::json_schema::DESCRIPTION_METADATA_KEY;
+use schema_validate::SchemaError;
+
+fn schema() -> Schema {
+    let mut id = Field::new("id", DataType::Int64, false);
+    id.set_metadata(Some(BTreeMap::from([(
+        DESCRIPTION_METADATA_KEY.to_owned(),
+        "the user's id".to_owned(),
+    )])));
+    Schema::new(vec![
+        id,
+        Field::new("Email", DataType::Utf8, true),
+        Field::new("seen", DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".to_owned())), true),
+        Field::new("score", DataType::Decimal(10, 2), true),
+    ])
+}
+
+fn location(format: &str) -> ExternalLocation {
+    ExternalLocation {
+        format: format.to_owned(),
+        location: "./data/".to_owned(),
+    }
+}
+
+#[test]
+fn postgres_keeps_nullability_and_comments() {
+    assert_eq!(
+        create_table(&schema(), "users", Dialect::Postgres, None).unwrap(),
+        "CREATE TABLE users (
+  id BIGINT NOT NULL, -- the user's id
+  \"Email\" TEXT,
+  seen TIMESTAMPTZ,
+  score NUMERIC(10,2)
+);
+COMMENT ON COLUMN users.id IS 'the user''s id';
+"
+    );
+}
+
+#[test]
+fn sqlite_uses_storage_classes() {
+    assert_eq!(
+        create_table(&schema(), "users", Dialect::Sqlite, None).unwrap(),
+        "CREATE TABLE users (
+  id INTEGER NOT NULL, -- the user's id
+  \"Email\" TEXT,
+  seen TEXT,
+  score NUMERIC
+);
+"
+    );
+}
+
+#[test]
+fn datafusion_declares_external_tables() {
+    assert_eq!(
+        create_table(&schema(), "users", Dialect::DataFusion, Some(&location("csv"))).unwrap(),
+        "CREATE EXTERNAL TABLE users (
+  id BIGINT NOT NULL, -- the user's id
+  \"Email\" VARCHAR,
+  seen TIMESTAMP,
+  score DECIMAL(10,2)
+)
+STORED AS CSV
+WITH HEADER ROW
+LOCATION './data/';
+"
+    );
+    // Parquet tables take their columns from the files
+    assert_eq!(
+        create_table(&schema(), "users", Dialect::DataFusion, Some(&location("parquet"))).unwrap(),
+        "CREATE EXTERNAL TABLE users
+-- id BIGINT NOT NULL -- the user's id
+-- \"Email\" VARCHAR
+-- seen TIMESTAMP
+-- score DECIMAL(10,2)
+STORED AS PARQUET
+LOCATION './data/';
+"
+    );
+}
+
+#[test]
+fn unsupported_types_and_missing_locations_are_errors() {
+    assert!(matches!(
+        create_table(&schema(), "users", Dialect::DataFusion, None),
+        Err(SchemaError::InvalidInput(_))
+    ));
+    let nested = Schema::new(vec![Field::new(
+        "tags",
+        DataType::List(Box::new(Field::new("item", DataType::Utf8, true))),
+        true,
+    )]);
+    assert!(matches!(
+        create_table(&nested, "t", Dialect::DataFusion, Some(&location("csv"))),
+        Err(SchemaError::InvalidInput(_))
+    ));
+    assert!(create_table(&nested, "t", Dialect::Postgres, None).unwrap().contains("tags TEXT[]"));
+    assert!(matches!("oracle".parse::<Dialect>(), Err(SchemaError::InvalidInput(_))));
+}
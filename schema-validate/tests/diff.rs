@@ -0,0 +1,146 @@
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::diff::{diff, ChangeKind};
+use schema_validate::{infer_file, load, Severity};
+
+fn schema(fields: &[(&str, DataType, bool)]) -> Schema {
+    Schema::new(
+        fields
+            .iter()
+            .map(|(name, data_type, nullable)| Field::new(name, data_type.clone(), *nullable))
+            .collect(),
+    )
+}
+
+#[test]
+fn identical_schemas_have_no_changes() {
+    let expected = load("data/schema.txt").unwrap();
+    let report = diff(&expected, &expected);
+    assert!(report.is_empty());
+    assert_eq!(report.max_severity(), None);
+}
+
+#[test]
+fn bundled_inputs_differ_in_the_type_of_d() {
+    let report = diff(&load("data/schema.txt").unwrap(), &infer_file("data/input2.json").unwrap());
+    assert_eq!(report.changes.len(), 1);
+    let change = &report.changes[0];
+    assert_eq!(change.field, "d");
+    assert_eq!(change.severity, Severity::Error);
+    assert_eq!(
+        change.kind,
+        ChangeKind::TypeChanged {
+            expected: DataType::Int64,
+            actual: DataType::Utf8
+        }
+    );
+}
+
+#[test]
+fn every_kind_of_change_has_a_severity() {
+    let expected = schema(&[
+        ("id", DataType::Int64, false),
+        ("name", DataType::Utf8, true),
+        ("score", DataType::Float64, true),
+        ("gone", DataType::Utf8, true),
+    ]);
+    let actual = schema(&[
+        ("name", DataType::Utf8, false),
+        ("id", DataType::Int64, true),
+        ("score", DataType::Int64, true),
+        ("new", DataType::Boolean, true),
+    ]);
+    let report = diff(&expected, &actual);
+    let changes: Vec<(&str, Severity, &ChangeKind)> = report
+        .changes
+        .iter()
+        .map(|change| (change.field.as_str(), change.severity, &change.kind))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (
+                "id",
+                Severity::Error,
+                &ChangeKind::NullabilityChanged {
+                    expected: false,
+                    actual: true
+                }
+            ),
+            ("id", Severity::Info, &ChangeKind::Reordered { expected: 0, actual: 1 }),
+            (
+                "name",
+                Severity::Info,
+                &ChangeKind::NullabilityChanged {
+                    expected: true,
+                    actual: false
+                }
+            ),
+            ("name", Severity::Info, &ChangeKind::Reordered { expected: 1, actual: 0 }),
+            (
+                "score",
+                Severity::Error,
+                &ChangeKind::TypeChanged {
+                    expected: DataType::Float64,
+                    actual: DataType::Int64
+                }
+            ),
+            (
+                "gone",
+                Severity::Error,
+                &ChangeKind::Removed {
+                    data_type: DataType::Utf8,
+                    nullable: true
+                }
+            ),
+            (
+                "new",
+                Severity::Warning,
+                &ChangeKind::Added {
+                    data_type: DataType::Boolean,
+                    nullable: true
+                }
+            ),
+        ]
+    );
+    assert_eq!(report.max_severity(), Some(Severity::Error));
+}
+
+#[test]
+fn added_and_removed_fields_do_not_reorder_the_others() {
+    let expected = schema(&[
+        ("a", DataType::Int64, true),
+        ("b", DataType::Int64, true),
+        ("c", DataType::Int64, true),
+    ]);
+    let actual = schema(&[
+        ("new", DataType::Int64, true),
+        ("a", DataType::Int64, true),
+        ("c", DataType::Int64, true),
+    ]);
+    let report = diff(&expected, &actual);
+    assert!(report
+        .changes
+        .iter()
+        .all(|change| !matches!(change.kind, ChangeKind::Reordered { .. })));
+}
+
+#[test]
+fn renders_as_table_and_json() {
+    let report = diff(&load("data/schema.txt").unwrap(), &infer_file("data/input2.json").unwrap());
+    assert_eq!(
+        report.to_table(),
+        "SEVERITY  FIELD  CHANGE\nerror     d      type changed from Int64 to Utf8\n"
+    );
+    assert_eq!(
+        report.to_json(),
+        serde_json::json!({
+            "changes": [{
+                "field": "d",
+                "severity": "error",
+                "kind": "type_changed",
+                "expected": "Int64",
+                "actual": "Utf8",
+            }]
+        })
+    );
+}
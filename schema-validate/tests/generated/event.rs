@@ -0,0 +1,60 @@
+// Generated by schema-validate, do not edit.
+
+use std::io::Cursor;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::{ArrowError, Result};
+use arrow::json::Reader;
+use arrow::record_batch::RecordBatch;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    pub tags: Option<Vec<Option<String>>>,
+    pub payload: Option<EventPayload>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventPayload {
+    pub name: String,
+    pub score: Option<f64>,
+    pub features: Option<Vec<Option<EventPayloadFeatures>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventPayloadFeatures {
+    pub key: Option<String>,
+    pub value: Option<i64>,
+}
+
+/// The Arrow schema the records were generated from
+pub fn schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("type", DataType::Utf8, false),
+        Field::new("userAgent", DataType::Utf8, true),
+        Field::new("tags", DataType::List(Box::new(Field::new("item", DataType::Utf8, true))), true),
+        Field::new("payload", DataType::Struct(vec![Field::new("name", DataType::Utf8, false), Field::new("score", DataType::Float64, true), Field::new("features", DataType::List(Box::new(Field::new("item", DataType::Struct(vec![Field::new("key", DataType::Utf8, true), Field::new("value", DataType::Int64, true)]), true))), true)]), true),
+    ])
+}
+
+/// Builds a record batch with [`schema`] from `records`
+pub fn to_record_batch(records: &[Event]) -> Result<RecordBatch> {
+    let mut ndjson = vec![];
+    for record in records {
+        serde_json::to_writer(&mut ndjson, record).map_err(|e| ArrowError::JsonError(e.to_string()))?;
+        ndjson.push(b'\n');
+    }
+    let schema = Arc::new(schema());
+    let mut reader = Reader::new(Cursor::new(ndjson), schema.clone(), records.len().max(1), None);
+    match reader.next()? {
+        Some(batch) => Ok(batch),
+        None => Ok(RecordBatch::new_empty(schema)),
+    }
+}
@@ -0,0 +1,43 @@
+// Generated by schema-validate, do not edit.
+
+use std::io::Cursor;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::{ArrowError, Result};
+use arrow::json::Reader;
+use arrow::record_batch::RecordBatch;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub a: Option<i64>,
+    pub b: Option<f64>,
+    pub c: Option<bool>,
+    pub d: Option<i64>,
+}
+
+/// The Arrow schema the records were generated from
+pub fn schema() -> Schema {
+    Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Float64, true),
+        Field::new("c", DataType::Boolean, true),
+        Field::new("d", DataType::Int64, true),
+    ])
+}
+
+/// Builds a record batch with [`schema`] from `records`
+pub fn to_record_batch(records: &[Record]) -> Result<RecordBatch> {
+    let mut ndjson = vec![];
+    for record in records {
+        serde_json::to_writer(&mut ndjson, record).map_err(|e| ArrowError::JsonError(e.to_string()))?;
+        ndjson.push(b'\n');
+    }
+    let schema = Arc::new(schema());
+    let mut reader = Reader::new(Cursor::new(ndjson), schema.clone(), records.len().max(1), None);
+    match reader.next()? {
+        Some(batch) => Ok(batch),
+        None => Ok(RecordBatch::new_empty(schema)),
+    }
+}
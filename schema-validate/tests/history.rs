@@ -0,0 +1,160 @@
+mod common;
+
+use std::fs;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::diff::{ChangeKind, FieldChange};
+use schema_validate::history::{DriftEvent, DriftPolicy, DriftTimeline, History};
+use schema_validate::{SchemaError, Severity};
+
+fn schema(fields: &[(&str, DataType, bool)]) -> Schema {
+    Schema::new(
+        fields
+            .iter()
+            .map(|(name, data_type, nullable)| Field::new(name, data_type.clone(), *nullable))
+            .collect(),
+    )
+}
+
+#[test]
+fn changes_are_attributed_to_the_first_file_showing_them() {
+    let history = History::open(common::scratch_dir("history-timeline")).unwrap();
+    let v1 = schema(&[("a", DataType::Int64, true), ("b", DataType::Utf8, true)]);
+    let v2 = schema(&[("a", DataType::Utf8, true), ("c", DataType::Boolean, true)]);
+    // nullability changes are not drift
+    let v3 = schema(&[("a", DataType::Utf8, false), ("c", DataType::Boolean, true)]);
+
+    history.record("day1.json", &v1).unwrap();
+    history.record("day2.json", &v1).unwrap();
+    let entry = history.record("day3.json", &v2).unwrap();
+    history.record("day4.json", &v3).unwrap();
+
+    let entries = history.entries().unwrap();
+    let files: Vec<&str> = entries.iter().map(|entry| entry.file.as_str()).collect();
+    assert_eq!(files, vec!["day1.json", "day2.json", "day3.json", "day4.json"]);
+    assert_eq!(entries[0].fingerprint, entries[1].fingerprint);
+    assert_eq!(history.schema(&entry.fingerprint).unwrap(), v2);
+
+    let timeline = history.timeline().unwrap();
+    assert_eq!(timeline.entries, 4);
+    let events: Vec<String> = timeline
+        .events
+        .iter()
+        .map(|event| format!("{} {} {} {}", event.file, event.previous_file, event.change.field, event.change.kind))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            "day3.json day2.json a type changed from Int64 to Utf8",
+            "day3.json day2.json b removed Utf8 (nullable: true)",
+            "day3.json day2.json c added Boolean (nullable: true)",
+        ]
+    );
+    assert!(timeline.events.iter().all(|event| event.recorded_at == entry.recorded_at));
+}
+
+#[test]
+fn empty_histories_have_no_events() {
+    let history = History::open(common::scratch_dir("history-empty")).unwrap();
+    assert!(history.entries().unwrap().is_empty());
+    assert_eq!(history.timeline().unwrap(), DriftTimeline::default());
+    assert!(matches!(history.schema("0000"), Err(SchemaError::NotFound(_))));
+}
+
+#[test]
+fn reading_a_missing_history_creates_nothing() {
+    let root = common::scratch_dir("history-read-only").join("history");
+    let history = History::open(&root).unwrap();
+    assert_eq!(history.timeline().unwrap(), DriftTimeline::default());
+    assert!(!root.exists());
+
+    history.record("day1.json", &schema(&[("a", DataType::Int64, true)])).unwrap();
+    assert!(root.join("schemas").is_dir());
+}
+
+fn event(recorded_at: u64, kind: ChangeKind) -> DriftEvent {
+    DriftEvent {
+        recorded_at,
+        file: "new.json".to_owned(),
+        previous_file: "old.json".to_owned(),
+        change: FieldChange {
+            field: "f".to_owned(),
+            severity: Severity::Warning,
+            kind,
+        },
+    }
+}
+
+#[test]
+fn policies_limit_the_drift_within_their_window() {
+    let added = || ChangeKind::Added {
+        data_type: DataType::Int64,
+        nullable: true,
+    };
+    let timeline = DriftTimeline {
+        entries: 3,
+        events: vec![
+            event(100, added()),
+            event(
+                900,
+                ChangeKind::TypeChanged {
+                    expected: DataType::Int64,
+                    actual: DataType::Utf8,
+                },
+            ),
+            event(950, added()),
+            event(
+                950,
+                ChangeKind::Removed {
+                    data_type: DataType::Int64,
+                    nullable: true,
+                },
+            ),
+        ],
+    };
+    let policy = DriftPolicy {
+        max_appeared: Some(1),
+        max_disappeared: Some(0),
+        max_type_changes: None,
+        window_secs: None,
+    };
+    assert_eq!(
+        policy.check(&timeline, 1000),
+        vec![
+            "2 field(s) appeared, the policy allows 1",
+            "1 field(s) disappeared, the policy allows 0",
+        ]
+    );
+
+    let windowed = DriftPolicy {
+        window_secs: Some(200),
+        max_type_changes: Some(0),
+        ..policy
+    };
+    assert_eq!(
+        windowed.check(&timeline, 1000),
+        vec![
+            "1 field(s) disappeared, the policy allows 0",
+            "1 field(s) changed type, the policy allows 0",
+        ]
+    );
+    assert!(DriftPolicy::default().check(&timeline, 1000).is_empty());
+}
+
+#[test]
+fn policies_are_read_strictly() {
+    let dir = common::scratch_dir("history-policy");
+    let path = dir.join("policy.json");
+    fs::write(&path, "{\"max_appeared\": 5, \"window_secs\": 604800}").unwrap();
+    assert_eq!(
+        DriftPolicy::load(&path).unwrap(),
+        DriftPolicy {
+            max_appeared: Some(5),
+            window_secs: Some(604800),
+            ..DriftPolicy::default()
+        }
+    );
+
+    fs::write(&path, "{\"max_apeared\": 5}").unwrap();
+    assert!(matches!(DriftPolicy::load(&path), Err(SchemaError::InvalidInput(_))));
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use schema_validate::json_schema::{
+    from_json_schema, to_json_schema, DESCRIPTION_METADATA_KEY, DRAFT_2020_12, ENUM_METADATA_KEY,
+};
+use schema_validate::{infer_file, load, validate, validate_records_file, SchemaError};
+use serde_json::json;
+
+fn contract() -> serde_json::Value {
+    json!({
+        "$schema": DRAFT_2020_12,
+        "type": "object",
+        "properties": {
+            "id": { "type": "integer", "description": "primary key" },
+            "score": { "type": ["number", "null"] },
+            "born": { "type": "string", "format": "date" },
+            "seen": { "type": "string", "format": "date-time" },
+            "status": { "enum": ["active", "closed"] },
+            "tags": { "type": "array", "items": { "type": "string" } },
+            "payload": {
+                "type": "object",
+                "properties": { "name": { "type": "string" } },
+                "required": ["name"]
+            },
+            "note": { "anyOf": [{ "type": "string" }, { "type": "null" }] }
+        },
+        "required": ["id", "born", "seen", "status", "tags", "payload", "note"]
+    })
+}
+
+#[test]
+fn imports_types_formats_enums_and_nesting() {
+    let schema = from_json_schema(&contract()).unwrap();
+    let fields: Vec<(&str, &DataType, bool)> = schema
+        .fields()
+        .iter()
+        .map(|field| (field.name().as_str(), field.data_type(), field.is_nullable()))
+        .collect();
+    let dictionary = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+    let tags = DataType::List(Box::new(Field::new("item", DataType::Utf8, false)));
+    let payload = DataType::Struct(vec![Field::new("name", DataType::Utf8, false)]);
+    let timestamp = DataType::Timestamp(TimeUnit::Millisecond, None);
+    assert_eq!(
+        fields,
+        vec![
+            ("id", &DataType::Int64, false),
+            ("score", &DataType::Float64, true),
+            ("born", &DataType::Date32, false),
+            ("seen", &timestamp, false),
+            ("status", &dictionary, false),
+            ("tags", &tags, false),
+            ("payload", &payload, false),
+            ("note", &DataType::Utf8, true),
+        ]
+    );
+
+    let metadata = |name: &str| schema.field_with_name(name).unwrap().metadata().clone().unwrap_or_default();
+    assert_eq!(metadata("id").get(DESCRIPTION_METADATA_KEY).unwrap(), "primary key");
+    assert_eq!(metadata("status").get(ENUM_METADATA_KEY).unwrap(), "[\"active\",\"closed\"]");
+}
+
+#[test]
+fn exports_required_nullable_and_formats() {
+    let mut id = Field::new("id", DataType::UInt32, false);
+    id.set_metadata(Some(BTreeMap::from([(
+        DESCRIPTION_METADATA_KEY.to_owned(),
+        "primary key".to_owned(),
+    )])));
+    let schema = Schema::new(vec![
+        id,
+        Field::new("seen", DataType::Timestamp(TimeUnit::Second, None), true),
+        Field::new("tags", DataType::List(Box::new(Field::new("item", DataType::Utf8, true))), true),
+    ]);
+    assert_eq!(
+        to_json_schema(&schema),
+        json!({
+            "$schema": DRAFT_2020_12,
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer", "minimum": 0, "description": "primary key" },
+                "seen": { "type": ["string", "null"], "format": "date-time" },
+                "tags": { "type": ["array", "null"], "items": { "type": ["string", "null"] } }
+            },
+            "required": ["id"]
+        })
+    );
+}
+
+#[test]
+fn imported_contracts_round_trip() {
+    let schema = from_json_schema(&contract()).unwrap();
+    assert_eq!(from_json_schema(&to_json_schema(&schema)).unwrap(), schema);
+}
+
+#[test]
+fn invalid_contracts_name_the_property() {
+    let error = from_json_schema(&json!({
+        "type": "object",
+        "properties": { "tags": { "type": "array" } }
+    }))
+    .unwrap_err();
+    assert_eq!(
+        error,
+        SchemaError::InvalidInput("JSON Schema $.tags: array without 'items'".to_owned())
+    );
+    assert!(matches!(from_json_schema(&json!({ "type": "string" })), Err(SchemaError::InvalidInput(_))));
+    assert!(matches!(
+        from_json_schema(&json!({ "properties": { "a": { "type": ["integer", "string"] } } })),
+        Err(SchemaError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn json_schema_files_are_accepted_as_the_expected_contract() {
+    let path = common::scratch_dir("json-schema-contract").join("contract.json");
+    let contract = json!({
+        "$schema": DRAFT_2020_12,
+        "type": "object",
+        "properties": {
+            "a": { "type": "integer" },
+            "b": { "type": "number" },
+            "c": { "type": "boolean" },
+            "d": { "type": "integer" }
+        }
+    });
+    fs::write(&path, contract.to_string()).unwrap();
+    let expected = load(&path).unwrap();
+    assert!(validate(&expected, &infer_file("data/input1.json").unwrap()).is_valid());
+    assert!(!validate(&expected, &infer_file("data/input2.json").unwrap()).is_valid());
+}
+
+#[test]
+fn contracts_accept_json_carried_formats_enums_and_whole_numbers() {
+    let dir = common::scratch_dir("json-schema-formats");
+    let contract = json!({
+        "$schema": DRAFT_2020_12,
+        "type": "object",
+        "properties": {
+            "id": { "type": "integer" },
+            "seen": { "type": "string", "format": "date-time" },
+            "status": { "enum": ["a", "b"] },
+            "score": { "type": "number" }
+        }
+    });
+    fs::write(dir.join("contract.json"), contract.to_string()).unwrap();
+    fs::write(
+        dir.join("data.json"),
+        "{\"id\":1,\"seen\":\"2021-01-01T00:00:00Z\",\"status\":\"a\",\"score\":2}\n",
+    )
+    .unwrap();
+    fs::write(dir.join("bad.json"), "{\"id\":1,\"seen\":\"2021-01-01\",\"status\":\"a\",\"score\":\"2\"}\n").unwrap();
+    let expected = load(dir.join("contract.json")).unwrap();
+
+    let report = validate(&expected, &infer_file(dir.join("data.json")).unwrap());
+    assert!(report.is_valid(), "{}", report);
+    assert!(validate_records_file(dir.join("data.json"), &expected, 10).unwrap().is_valid());
+    assert!(!validate(&expected, &infer_file(dir.join("bad.json")).unwrap()).is_valid());
+    assert!(!validate_records_file(dir.join("bad.json"), &expected, 10).unwrap().is_valid());
+}
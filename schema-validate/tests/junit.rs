@@ -0,0 +1,59 @@
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::junit::{schema_suite, to_xml, TestCase, TestSuite};
+use schema_validate::{validate, Outcome, ERROR_EXIT_CODE};
+
+#[test]
+fn exit_codes_follow_the_outcome() {
+    assert_eq!(Outcome::Pass.exit_code(), 0);
+    assert_eq!(Outcome::Warnings.exit_code(), 1);
+    assert_eq!(Outcome::Incompatible.exit_code(), 2);
+    assert_eq!(ERROR_EXIT_CODE, 3);
+    assert!(Outcome::Pass < Outcome::Warnings && Outcome::Warnings < Outcome::Incompatible);
+}
+
+#[test]
+fn names_and_messages_are_escaped() {
+    let suite = TestSuite {
+        name: "a<b>".to_owned(),
+        cases: vec![TestCase {
+            classname: "\"quoted\"".to_owned(),
+            name: "it's".to_owned(),
+            failure: Some(("x & y".to_owned(), "<tag>".to_owned())),
+            output: vec!["1 < 2".to_owned()],
+        }],
+    };
+    assert_eq!(
+        to_xml(&[suite]),
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<testsuites name=\"schema-validate\" tests=\"1\" failures=\"1\">
+  <testsuite name=\"a&lt;b&gt;\" tests=\"1\" failures=\"1\">
+    <testcase classname=\"&quot;quoted&quot;\" name=\"it&apos;s\">
+      <failure message=\"x &amp; y\">&lt;tag&gt;</failure>
+      <system-out>1 &lt; 2</system-out>
+    </testcase>
+  </testsuite>
+</testsuites>
+"
+    );
+}
+
+#[test]
+fn schema_suites_have_a_case_per_field() {
+    let expected = Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Int64, true),
+    ]);
+    let actual = Schema::new(vec![
+        Field::new("a", DataType::Utf8, true),
+        Field::new("b", DataType::Int64, true),
+        Field::new("c", DataType::Utf8, true),
+    ]);
+    let report = validate(&expected, &actual);
+    let suite = schema_suite("schema", &expected, &actual, &report);
+
+    let names: Vec<&str> = suite.cases.iter().map(|case| case.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b", "c"]);
+    assert_eq!(suite.failures(), 1);
+    assert!(suite.cases[0].failure.is_some());
+    assert!(!suite.cases[2].output.is_empty());
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::fs;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::merge::{merge, merge_dir, ndjson_files};
+use schema_validate::SchemaError;
+
+#[test]
+fn fields_are_unioned_in_first_seen_order() {
+    let first = Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+    ]);
+    let second = Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("score", DataType::Float64, false),
+    ]);
+    let merged = merge(&[("a.json".to_owned(), first), ("b.json".to_owned(), second)]);
+
+    assert_eq!(
+        merged.schema,
+        Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            // missing in one of the files
+            Field::new("name", DataType::Utf8, true),
+            Field::new("score", DataType::Float64, true),
+        ])
+    );
+    assert!(merged.conflicts.is_empty());
+    assert_eq!(merged.files, vec!["a.json", "b.json"]);
+}
+
+#[test]
+fn conflicting_types_are_reported_and_widened() {
+    let schema = |data_type| Schema::new(vec![Field::new("v", data_type, true)]);
+    let merged = merge(&[
+        ("a.json".to_owned(), schema(DataType::Int64)),
+        ("b.json".to_owned(), schema(DataType::Float64)),
+        ("c.json".to_owned(), schema(DataType::Null)),
+    ]);
+    assert_eq!(merged.schema.field(0).data_type(), &DataType::Float64);
+    assert_eq!(merged.conflicts.len(), 1);
+    assert_eq!(
+        merged.conflicts[0].to_string(),
+        "v: Int64 in a.json, Float64 in b.json (merged as Float64)"
+    );
+
+    let merged = merge(&[
+        ("a.json".to_owned(), schema(DataType::Int64)),
+        ("b.json".to_owned(), schema(DataType::Boolean)),
+    ]);
+    assert_eq!(merged.schema.field(0).data_type(), &DataType::Utf8);
+}
+
+#[test]
+fn nested_fields_are_merged_by_path() {
+    let payload = |fields| Schema::new(vec![Field::new("payload", DataType::Struct(fields), true)]);
+    let merged = merge(&[
+        ("a.json".to_owned(), payload(vec![Field::new("id", DataType::Int64, true)])),
+        ("b.json".to_owned(), payload(vec![Field::new("id", DataType::Utf8, true)])),
+    ]);
+    assert_eq!(merged.conflicts[0].field, "payload.id");
+}
+
+#[test]
+fn directories_skip_repair_outputs() {
+    let dir = common::scratch_dir("merge-dir");
+    fs::write(dir.join("a.json"), "{\"id\": 1}\n").unwrap();
+    fs::write(dir.join("b.jsonl"), "{\"id\": 2, \"name\": \"x\"}\n").unwrap();
+    fs::write(dir.join("a.repaired.json"), "{\"other\": true}\n").unwrap();
+    fs::write(dir.join("a.quarantine.json"), "{\"other\": true}\n").unwrap();
+    fs::write(dir.join("notes.txt"), "not a sample").unwrap();
+
+    let files: Vec<_> = ndjson_files(&dir)
+        .unwrap()
+        .into_iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(files, vec!["a.json", "b.jsonl"]);
+
+    let merged = merge_dir(&dir).unwrap();
+    let names: Vec<&String> = merged.schema.fields().iter().map(|field| field.name()).collect();
+    assert_eq!(names, vec!["id", "name"]);
+}
+
+#[test]
+fn empty_directories_are_an_error() {
+    let dir = common::scratch_dir("merge-empty");
+    fs::write(dir.join("a.repaired.json"), "{\"id\": 1}\n").unwrap();
+    assert!(matches!(merge_dir(&dir), Err(SchemaError::InvalidInput(_))));
+}
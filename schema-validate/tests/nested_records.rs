@@ -0,0 +1,97 @@
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::records::json_path;
+use schema_validate::validate_records;
+use serde_json::json;
+
+fn schema() -> Schema {
+    let features = DataType::List(Box::new(Field::new("item", DataType::Float64, false)));
+    let tag = DataType::Struct(vec![
+        Field::new("key", DataType::Utf8, false),
+        Field::new("weight", DataType::Int32, true),
+    ]);
+    Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new(
+            "payload",
+            DataType::Struct(vec![
+                Field::new("features", features, true),
+                Field::new("tags", DataType::List(Box::new(Field::new("item", tag, true))), true),
+                Field::new("source", DataType::Utf8, false),
+            ]),
+            true,
+        ),
+    ])
+}
+
+fn violations(input: &str) -> Vec<String> {
+    validate_records(input.as_bytes(), &schema(), 100)
+        .unwrap()
+        .violations
+        .iter()
+        .map(|violation| format!("{} {}", violation.field, violation))
+        .collect()
+}
+
+#[test]
+fn valid_nested_records_pass() {
+    let input = concat!(
+        "{\"id\": 1, \"payload\": {\"features\": [0.5, 1], \"tags\": [{\"key\": \"a\"}, null], \"source\": \"x\"}}\n",
+        "{\"id\": 2, \"payload\": null}\n",
+        "{\"id\": 3, \"payload\": {\"source\": \"x\"}}\n",
+    );
+    assert!(validate_records(input.as_bytes(), &schema(), 100).unwrap().is_valid());
+}
+
+#[test]
+fn violations_name_the_innermost_value() {
+    let input = concat!(
+        "{\"id\": 1, \"payload\": {\"features\": [0.5, \"high\", null], \"source\": \"x\"}}\n",
+        "{\"id\": 2, \"payload\": {\"tags\": [{\"key\": \"a\", \"weight\": 1e10}, {\"weight\": 1}]}}\n",
+        "{\"id\": 3, \"payload\": {\"source\": \"x\", \"user agent\": \"curl\", \"tags\": [{\"key\": \"a\", \"extra\": 1}]}}\n",
+        "{\"id\": 4, \"payload\": {\"source\": \"x\", \"features\": 0.5, \"tags\": {\"key\": \"a\"}}}\n",
+        "{\"id\": 5, \"payload\": [1]}\n",
+    );
+    let payload = schema().field(1).data_type().clone();
+    let member = |name: &str| match &payload {
+        DataType::Struct(fields) => fields.iter().find(|field| field.name() == name).unwrap().data_type().clone(),
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        violations(input),
+        vec![
+            "payload line 1: $.payload.features[1]: expected Float64, found \"high\"".to_owned(),
+            "payload line 1: $.payload.features[2]: expected Float64, found null".to_owned(),
+            "payload line 2: $.payload.tags[0].weight: expected Int32, found 10000000000.0".to_owned(),
+            "payload line 2: $.payload.tags[1].key: expected Utf8, found null".to_owned(),
+            "payload line 2: $.payload.source: expected Utf8, found null".to_owned(),
+            "payload line 3: $.payload.tags[0].extra: unknown field, found 1".to_owned(),
+            "payload line 3: $.payload['user agent']: unknown field, found \"curl\"".to_owned(),
+            format!("payload line 4: $.payload.features: expected {:?}, found 0.5", member("features")),
+            format!("payload line 4: $.payload.tags: expected {:?}, found {{\"key\":\"a\"}}", member("tags")),
+            format!("payload line 5: $.payload: expected {:?}, found [1]", payload),
+        ]
+    );
+}
+
+#[test]
+fn reports_serialize_paths() {
+    let report = validate_records("{\"id\": 1, \"payload\": {\"source\": 1}}".as_bytes(), &schema(), 100).unwrap();
+    assert_eq!(
+        serde_json::to_value(&report.violations[0]).unwrap(),
+        json!({
+            "line": 1,
+            "field": "payload",
+            "path": "$.payload.source",
+            "expected": "Utf8",
+            "actual": 1,
+        })
+    );
+}
+
+#[test]
+fn paths_quote_keys_that_are_not_identifiers() {
+    assert_eq!(json_path("$", "payload"), "$.payload");
+    assert_eq!(json_path("$.payload", "_id2"), "$.payload._id2");
+    assert_eq!(json_path("$", "2nd"), "$['2nd']");
+    assert_eq!(json_path("$", "it's"), "$['it\\'s']");
+}
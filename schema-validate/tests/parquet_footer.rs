@@ -0,0 +1,62 @@
+mod common;
+
+use std::fs;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::parquet_footer::parquet_files;
+use schema_validate::{infer_parquet, validate_parquet_dir, Compatibility, Outcome, SchemaError};
+
+const USERDATA: &str = "../datafusion-parquet/data";
+
+#[test]
+fn reads_the_schema_from_the_footer() {
+    let schema = infer_parquet(format!("{}/userdata1.parquet", USERDATA)).unwrap();
+    assert_eq!(schema.fields().len(), 13);
+    assert_eq!(schema.field_with_name("email").unwrap().data_type(), &DataType::Utf8);
+    assert!(schema.field_with_name("registration_dttm").is_ok());
+}
+
+#[test]
+fn userdata_files_share_one_schema() {
+    let reports = validate_parquet_dir(USERDATA, None, Compatibility::Full).unwrap();
+    let files: Vec<String> = reports
+        .iter()
+        .map(|(path, _)| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(files, vec!["userdata1.parquet", "userdata2.parquet", "userdata3.parquet"]);
+    for (_, report) in &reports {
+        assert_eq!(report.as_ref().unwrap().outcome(), Outcome::Pass);
+    }
+}
+
+#[test]
+fn drift_against_an_expected_schema() {
+    let mut fields = infer_parquet(format!("{}/userdata1.parquet", USERDATA))
+        .unwrap()
+        .fields()
+        .clone();
+    fields.push(Field::new("required_column", DataType::Int64, false));
+    let expected = Schema::new(fields);
+    let path = format!("{}/userdata2.parquet", USERDATA);
+    let reports = validate_parquet_dir(path, Some(&expected), Compatibility::Forward).unwrap();
+    assert_eq!(reports.len(), 1);
+    let report = reports[0].1.as_ref().unwrap();
+    assert_eq!(report.outcome(), Outcome::Incompatible);
+    assert_eq!(report.violations[0].field, "required_column");
+}
+
+#[test]
+fn unreadable_files_are_reported_per_entry() {
+    let dir = common::scratch_dir("parquet-unreadable");
+    fs::copy(format!("{}/userdata1.parquet", USERDATA), dir.join("a.parquet")).unwrap();
+    fs::write(dir.join("b.parquet"), "not parquet").unwrap();
+    fs::copy(format!("{}/userdata2.parquet", USERDATA), dir.join("c.parquet")).unwrap();
+    fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+    assert_eq!(parquet_files(&dir).unwrap().len(), 3);
+    let reports = validate_parquet_dir(&dir, None, Compatibility::Backward).unwrap();
+    assert_eq!(reports.len(), 3);
+    assert!(reports[0].1.as_ref().unwrap().is_valid());
+    assert!(matches!(reports[1].1, Err(SchemaError::Parquet(_))));
+    assert!(reports[2].1.as_ref().unwrap().is_valid());
+}
@@ -0,0 +1,95 @@
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::records::value_matches;
+use schema_validate::{load, validate_records, validate_records_file};
+use serde_json::json;
+
+fn schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("small", DataType::Int8, true),
+        Field::new("name", DataType::Utf8, true),
+    ])
+}
+
+#[test]
+fn reports_line_field_type_and_value() {
+    let input = r#"{"id": 1, "small": 1, "name": "a"}
+
+{"id": "2", "small": 300, "name": "b"}
+not json
+{"small": 1, "extra": true}
+"#;
+    let report = validate_records(input.as_bytes(), &schema(), 100).unwrap();
+    assert_eq!(report.records, 4);
+    assert!(!report.truncated);
+    let violations: Vec<(usize, &str, Option<&DataType>, &serde_json::Value)> = report
+        .violations
+        .iter()
+        .map(|violation| {
+            (
+                violation.line,
+                violation.field.as_str(),
+                violation.expected.as_ref(),
+                &violation.actual,
+            )
+        })
+        .collect();
+    let not_json = json!("not json");
+    assert_eq!(
+        violations,
+        vec![
+            (3, "id", Some(&DataType::Int64), &json!("2")),
+            (3, "small", Some(&DataType::Int8), &json!(300)),
+            (4, "", Some(&DataType::Struct(schema().fields().clone())), &not_json),
+            (5, "id", Some(&DataType::Int64), &json!(null)),
+            (5, "extra", None, &json!(true)),
+        ]
+    );
+    assert_eq!(
+        report.violations[0].to_string(),
+        "line 3: $.id: expected Int64, found \"2\""
+    );
+    assert_eq!(
+        report.violations[4].to_string(),
+        "line 5: $.extra: unknown field, found true"
+    );
+}
+
+#[test]
+fn stops_at_the_error_limit() {
+    let input = "{\"id\": \"x\"}\n".repeat(10);
+    let report = validate_records(input.as_bytes(), &schema(), 3).unwrap();
+    assert_eq!(report.violations.len(), 3);
+    assert!(report.truncated);
+    assert!(report.to_string().ends_with("stopped after reaching the error limit\n"));
+}
+
+#[test]
+fn a_zero_error_limit_still_fails_invalid_input() {
+    let report = validate_records("{\"id\": \"x\"}\n".as_bytes(), &schema(), 0).unwrap();
+    assert!(report.violations.is_empty());
+    assert!(report.truncated);
+    assert!(!report.is_valid());
+    assert!(validate_records("{\"id\": 1}\n".as_bytes(), &schema(), 0).unwrap().is_valid());
+}
+
+#[test]
+fn bundled_inputs_against_the_stored_schema() {
+    let schema = load("data/schema.txt").unwrap();
+    assert!(validate_records_file("data/input1.json", &schema, 100).unwrap().is_valid());
+
+    let report = validate_records_file("data/input2.json", &schema, 100).unwrap();
+    assert_eq!(report.violations.len(), 1);
+    assert_eq!(report.violations[0].line, 1);
+    assert_eq!(report.violations[0].field, "d");
+}
+
+#[test]
+fn values_match_json_reader_types() {
+    assert!(value_matches(&DataType::UInt8, &json!(255)));
+    assert!(!value_matches(&DataType::UInt8, &json!(-1)));
+    assert!(!value_matches(&DataType::Int64, &json!(1.5)));
+    assert!(value_matches(&DataType::Float32, &json!(1)));
+    assert!(value_matches(&DataType::Date32, &json!("2021-01-01")));
+    assert!(!value_matches(&DataType::Boolean, &json!("true")));
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::registry::fingerprint;
+use schema_validate::{Compatibility, Registry, SchemaError};
+
+fn schema(fields: &[(&str, DataType)]) -> Schema {
+    Schema::new(
+        fields
+            .iter()
+            .map(|(name, data_type)| Field::new(name, data_type.clone(), true))
+            .collect(),
+    )
+}
+
+#[test]
+fn versions_are_numbered_and_fingerprinted() {
+    let registry = Registry::open(common::scratch_dir("registry-versions")).unwrap();
+    let v1 = schema(&[("a", DataType::Int64)]);
+    let v2 = schema(&[("a", DataType::Int64), ("b", DataType::Utf8)]);
+
+    let first = registry.register("events", &v1).unwrap();
+    assert_eq!(first.version, 1);
+    assert_eq!(first.fingerprint, fingerprint(&v1).unwrap());
+    assert!(first.registered_at > 0);
+    // the same schema again is not a new version
+    assert_eq!(registry.register("events", &v1).unwrap().version, 1);
+
+    assert_eq!(registry.register("events", &v2).unwrap().version, 2);
+    assert_eq!(registry.versions("events").unwrap(), vec![1, 2]);
+    assert_eq!(registry.latest("events").unwrap().schema, v2);
+    assert_eq!(registry.get("events", 1).unwrap().schema, v1);
+    assert_eq!(registry.subjects().unwrap(), vec!["events".to_owned()]);
+}
+
+#[test]
+fn version_numbers_are_not_reused_after_delete() {
+    let registry = Registry::open(common::scratch_dir("registry-delete")).unwrap();
+    registry.register("events", &schema(&[("a", DataType::Int64)])).unwrap();
+    let v2 = registry
+        .register("events", &schema(&[("a", DataType::Int64), ("b", DataType::Utf8)]))
+        .unwrap();
+    registry.delete("events", v2.version).unwrap();
+    assert_eq!(registry.latest("events").unwrap().version, 1);
+    assert!(matches!(registry.get("events", 2), Err(SchemaError::NotFound(_))));
+    assert!(matches!(registry.delete("events", 2), Err(SchemaError::NotFound(_))));
+
+    let v3 = registry
+        .register("events", &schema(&[("a", DataType::Int64), ("c", DataType::Utf8)]))
+        .unwrap();
+    assert_eq!(v3.version, 3);
+    assert_eq!(registry.versions("events").unwrap(), vec![1, 3]);
+}
+
+#[test]
+fn incompatible_versions_are_rejected() {
+    let registry = Registry::open(common::scratch_dir("registry-compat")).unwrap();
+    registry.register("events", &schema(&[("a", DataType::Int64)])).unwrap();
+    let narrowed = schema(&[("a", DataType::Int32)]);
+    assert!(matches!(
+        registry.register("events", &narrowed),
+        Err(SchemaError::Incompatible(_))
+    ));
+
+    registry.set_compatibility("events", Compatibility::None).unwrap();
+    assert_eq!(registry.compatibility("events").unwrap(), Compatibility::None);
+    assert_eq!(registry.register("events", &narrowed).unwrap().version, 2);
+    // changing the mode keeps the version counter
+    registry.set_compatibility("events", Compatibility::Full).unwrap();
+    assert_eq!(registry.versions("events").unwrap(), vec![1, 2]);
+}
+
+#[test]
+fn reading_does_not_create_directories() {
+    let root = common::scratch_dir("registry-read-only").join("registry");
+    let registry = Registry::open(&root).unwrap();
+    assert!(registry.subjects().unwrap().is_empty());
+    assert!(registry.versions("events").unwrap().is_empty());
+    assert!(matches!(registry.latest("events"), Err(SchemaError::NotFound(_))));
+    assert_eq!(registry.compatibility("events").unwrap(), Compatibility::Backward);
+    assert!(!root.exists());
+
+    registry.register("events", &schema(&[("a", DataType::Int64)])).unwrap();
+    assert!(root.join("events").is_dir());
+}
+
+#[test]
+fn subjects_are_plain_names() {
+    let registry = Registry::open(common::scratch_dir("registry-names")).unwrap();
+    for subject in ["", "..", "a/b", "a b"] {
+        assert!(matches!(
+            registry.register(subject, &schema(&[("a", DataType::Int64)])),
+            Err(SchemaError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use std::fs;
+
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use schema_validate::{repair_file, repair_records, RepairReport, SchemaError};
+use serde_json::json;
+
+fn schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("active", DataType::Boolean, true),
+        Field::new("name", DataType::Utf8, true),
+        Field::new("small", DataType::UInt8, true),
+        Field::new("seen", DataType::Timestamp(TimeUnit::Second, None), true),
+        Field::new("born", DataType::Date32, true),
+        Field::new(
+            "payload",
+            DataType::Struct(vec![Field::new(
+                "scores",
+                DataType::List(Box::new(Field::new("item", DataType::Float64, true))),
+                true,
+            )]),
+            true,
+        ),
+    ])
+}
+
+fn repair(input: &str) -> (RepairReport, String, String) {
+    let mut output = vec![];
+    let mut quarantine = vec![];
+    let report = repair_records(input.as_bytes(), &schema(), &mut output, &mut quarantine).unwrap();
+    (
+        report,
+        String::from_utf8(output).unwrap(),
+        String::from_utf8(quarantine).unwrap(),
+    )
+}
+
+#[test]
+fn unambiguous_values_are_coerced() {
+    let (report, output, quarantine) = repair(concat!(
+        "{\"id\": \"7\", \"active\": \"TRUE\", \"name\": 12, \"small\": 3.0, \"seen\": 1600000000.0, ",
+        "\"payload\": {\"scores\": [1.5, \"2.5\"]}, \"extra\": 1}\n",
+        "\n",
+        "{\"id\": 8.0, \"seen\": \"2021-01-01T00:00:00\"}\n",
+    ));
+    assert_eq!(quarantine, "");
+    assert_eq!((report.records, report.written), (2, 2));
+    let records: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(
+        records[0],
+        json!({"id": 7, "active": true, "name": "12", "small": 3, "seen": 1600000000,
+               "payload": {"scores": [1.5, 2.5]}, "extra": 1})
+    );
+    // formatted timestamps already fit
+    assert_eq!(records[1], json!({"id": 8, "seen": "2021-01-01T00:00:00"}));
+
+    let fields: Vec<&str> = report.coercions.iter().map(|c| c.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec!["$.id", "$.active", "$.name", "$.small", "$.seen", "$.payload.scores[1]", "$.id"]
+    );
+    assert_eq!(report.coercions[0].to_string(), "line 1: $.id: \"7\" -> 7 (Int64)");
+    assert_eq!(report.coercions[6].line, 3);
+}
+
+#[test]
+fn records_that_cannot_be_repaired_are_quarantined_unchanged() {
+    let input = [
+        "{\"id\": \"seven\"}",
+        "{\"name\": \"no id\"}",
+        "{\"id\": 1, \"small\": 300}",
+        "{\"id\": 1, \"active\": \"yes\", \"payload\": {\"scores\": [\"high\"]}}",
+        "[1, 2]",
+        "not json",
+        "{\"id\": 1.5}",
+    ];
+    let (report, output, quarantine) = repair(&input.join("\n"));
+    assert_eq!(output, "");
+    assert_eq!(quarantine, format!("{}\n", input.join("\n")));
+    assert_eq!(report.written, 0);
+
+    let quarantined: Vec<String> = report.quarantined.iter().map(|record| record.to_string()).collect();
+    assert_eq!(
+        quarantined,
+        vec![
+            "line 1: quarantined, cannot coerce $.id",
+            "line 2: quarantined, cannot coerce $.id",
+            "line 3: quarantined, cannot coerce $.small",
+            "line 4: quarantined, cannot coerce $.active, $.payload.scores[0]",
+            "line 5: quarantined, not a JSON object",
+            "line 6: quarantined, not a JSON object",
+            "line 7: quarantined, cannot coerce $.id",
+        ]
+    );
+    assert!(report.coercions.is_empty());
+}
+
+#[test]
+fn only_iso_strings_are_read_as_dates_and_timestamps() {
+    let (report, _, _) = repair("{\"id\": 1, \"born\": \"2021-01-01\", \"seen\": \"2021-01-01T00:00:00Z\"}");
+    assert!(report.is_clean(), "{}", report);
+
+    for value in [
+        "\"born\": \"20210101\"",
+        "\"born\": \"01/02/2021\"",
+        "\"born\": \"2021-13-01\"",
+        "\"seen\": \"1600000000\"",
+        "\"seen\": \"yesterday\"",
+    ] {
+        let (report, output, _) = repair(&format!("{{\"id\": 1, {}}}", value));
+        assert_eq!(report.quarantined.len(), 1, "{}", value);
+        assert!(report.coercions.is_empty(), "{}", value);
+        assert_eq!(output, "", "{}", value);
+    }
+}
+
+#[test]
+fn floats_out_of_range_are_not_saturated() {
+    for value in ["1e19", "-1e19", "9223372036854775808.0", "\"1e300\""] {
+        let (report, output, _) = repair(&format!("{{\"id\": {}}}", value));
+        assert_eq!(report.quarantined.len(), 1, "{}", value);
+        assert_eq!(output, "", "{}", value);
+    }
+    let (report, output, _) = repair("{\"id\": -9223372036854775808.0}");
+    assert!(report.quarantined.is_empty());
+    assert_eq!(output, "{\"id\":-9223372036854775808}\n");
+}
+
+#[test]
+fn clean_input_is_copied() {
+    let (report, output, _) = repair("{\"id\": 1, \"name\": \"a\"}\n");
+    assert!(report.is_clean());
+    assert_eq!(output, "{\"id\":1,\"name\":\"a\"}\n");
+}
+
+#[test]
+fn files_are_never_repaired_in_place() {
+    let dir = common::scratch_dir("repair-in-place");
+    let input = dir.join("input.json");
+    let records = "{\"id\": \"1\"}\n";
+    fs::write(&input, records).unwrap();
+
+    let aliased = dir.join(".").join("input.json");
+    assert!(matches!(
+        repair_file(&input, &schema(), &aliased, dir.join("q.json")),
+        Err(SchemaError::InvalidInput(_))
+    ));
+    assert!(matches!(
+        repair_file(&input, &schema(), dir.join("out.json"), &input),
+        Err(SchemaError::InvalidInput(_))
+    ));
+    assert_eq!(fs::read_to_string(&input).unwrap(), records);
+
+    let report = repair_file(&input, &schema(), dir.join("out.json"), dir.join("q.json")).unwrap();
+    assert_eq!(report.coercions.len(), 1);
+    assert_eq!(fs::read_to_string(dir.join("out.json")).unwrap(), "{\"id\":1}\n");
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use arrow::datatypes::{DataType, Field, IntervalUnit, Schema, TimeUnit};
+use schema_validate::text_format::{from_text, is_text_format, to_text, HEADER};
+use schema_validate::{load, save, SchemaError};
+
+fn schema() -> Schema {
+    let mut id = Field::new("id", DataType::Int64, false);
+    id.set_metadata(Some(BTreeMap::from([
+        ("description".to_owned(), "Primary key".to_owned()),
+        ("constraints".to_owned(), "{\"required\":true}".to_owned()),
+        // not canonical JSON, kept as a string
+        ("note".to_owned(), "{ \"spaced\": 1 }".to_owned()),
+    ])));
+    let payload = DataType::Struct(vec![
+        Field::new("name", DataType::Utf8, false),
+        Field::new("score %", DataType::Float64, true),
+    ]);
+    Schema::new_with_metadata(
+        vec![
+            id,
+            Field::new_dict(
+                "country",
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+                true,
+                3,
+                true,
+            ),
+            Field::new("created", DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".to_owned())), true),
+            Field::new("span", DataType::Interval(IntervalUnit::DayTime), true),
+            Field::new("price", DataType::Decimal(12, 2), true),
+            Field::new("tags", DataType::List(Box::new(Field::new("item", DataType::Utf8, true))), true),
+            Field::new("payload", payload.clone(), true),
+            Field::new("history", DataType::List(Box::new(Field::new("entry", payload, false))), true),
+            Field::new(
+                "choice",
+                DataType::Union(vec![Field::new("n", DataType::Int32, true)]),
+                true,
+            ),
+        ],
+        HashMap::from([("owner".to_owned(), "data-platform".to_owned())]),
+    )
+}
+
+#[test]
+fn schemas_round_trip() {
+    let text = to_text(&schema());
+    assert!(text.starts_with(&format!("{}\n@owner \"data-platform\"\nid int64\n", HEADER)));
+    assert!(text.contains("\ncountry dictionary<int32, utf8>? dict_id=3 ordered\n"));
+    assert!(text.contains("\n  @constraints {\"required\":true}\n"));
+    assert!(text.contains("\n  \"score %\" float64?\n"));
+    assert!(text.contains("\nchoice arrow<"));
+    assert!(is_text_format(&text));
+    assert_eq!(from_text(&text).unwrap(), schema());
+}
+
+#[test]
+fn only_headed_text_is_the_text_format() {
+    assert!(!is_text_format("{\"fields\": []}"));
+    assert!(!is_text_format("a int64\n"));
+    assert!(!is_text_format(""));
+    assert!(is_text_format(&format!("\n{}\na int64\n", HEADER)));
+}
+
+#[test]
+fn invalid_text_is_rejected() {
+    let invalid = |text: &str| matches!(from_text(text), Err(SchemaError::InvalidInput(_)));
+    assert!(invalid(HEADER));
+    assert!(invalid(&format!("{}\n@owner \"me\"\n", HEADER)));
+    assert!(invalid("a int65\n"));
+    assert!(invalid("a int64\n    b int64\n"));
+    assert!(invalid("a int64 extra\n"));
+    assert!(invalid("a timestamp<hours>\n"));
+
+    // without the header a file is read as JSON
+    let dir = common::scratch_dir("text-format-invalid");
+    let path = dir.join("schema.txt");
+    fs::write(&path, "a int64\n").unwrap();
+    assert!(matches!(load(&path), Err(SchemaError::Serde(_))));
+}
+
+#[test]
+fn indentation_must_be_whole_levels_of_spaces() {
+    let error = |body: &str| match from_text(&format!("{}\n{}", HEADER, body)) {
+        Err(SchemaError::InvalidInput(message)) => message,
+        other => panic!("expected an input error, got {:?}", other),
+    };
+    assert_eq!(
+        error("p struct\n  a int64\n   b int64\n"),
+        "schema line 4: indentation must be a multiple of 2 spaces"
+    );
+    assert_eq!(error("p struct\n\ta int64\n"), "schema line 3: indentation must use spaces, not tabs");
+}
+
+#[test]
+fn saved_schemas_are_text_unless_json() {
+    let dir = common::scratch_dir("text-format-save");
+    for name in ["schema.txt", "schema", "schema.schema"] {
+        save(&schema(), dir.join(name)).unwrap();
+        assert!(fs::read_to_string(dir.join(name)).unwrap().starts_with(HEADER), "{}", name);
+    }
+    save(&schema(), dir.join("schema.json")).unwrap();
+    assert!(fs::read_to_string(dir.join("schema.json")).unwrap().starts_with('{'));
+    assert_eq!(load(dir.join("schema.json")).unwrap(), load(dir.join("schema.txt")).unwrap());
+}
+
+#[test]
+fn the_bundled_schema_is_text() {
+    assert!(is_text_format(&fs::read_to_string("data/schema.txt").unwrap()));
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::fs;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use schema_validate::{infer_file, load, save, validate, Compatibility, Outcome, SchemaError};
+
+fn bundled_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("a", DataType::Int64, true),
+        Field::new("b", DataType::Float64, true),
+        Field::new("c", DataType::Boolean, true),
+        Field::new("d", DataType::Int64, true),
+    ])
+}
+
+#[test]
+fn infers_the_bundled_inputs() {
+    assert_eq!(infer_file("data/input1.json").unwrap(), bundled_schema());
+    let input2 = infer_file("data/input2.json").unwrap();
+    assert_eq!(input2.field_with_name("d").unwrap().data_type(), &DataType::Utf8);
+}
+
+#[test]
+fn saved_schemas_load_unchanged() {
+    let dir = common::scratch_dir("save-load");
+    for name in ["schema.txt", "schema.json", "schema.schema"] {
+        let path = dir.join(name);
+        save(&bundled_schema(), &path).unwrap();
+        assert_eq!(load(&path).unwrap(), bundled_schema(), "{}", name);
+    }
+}
+
+#[test]
+fn validation_reports_instead_of_panicking() {
+    let expected = load("data/schema.txt").unwrap();
+
+    let report = validate(&expected, &infer_file("data/input1.json").unwrap());
+    assert!(report.is_valid());
+    assert!(report.diff.is_empty());
+    assert_eq!(report.outcome(), Outcome::Pass);
+
+    let report = validate(&expected, &infer_file("data/input2.json").unwrap());
+    assert!(!report.is_valid());
+    assert_eq!(report.compatibility, Compatibility::Backward);
+    assert_eq!(report.violations[0].field, "d");
+    assert_eq!(report.outcome(), Outcome::Incompatible);
+    assert!(report.to_string().contains("incompatible (backward)"));
+}
+
+#[test]
+fn errors_are_typed() {
+    assert!(matches!(infer_file("data/missing.json"), Err(SchemaError::Io(_))));
+    assert!(matches!(load("data/missing.txt"), Err(SchemaError::Io(_))));
+
+    let dir = common::scratch_dir("errors");
+    let path = dir.join("broken.json");
+    fs::write(&path, "{\"fields\": 1}").unwrap();
+    assert!(matches!(load(&path), Err(SchemaError::Serde(_))));
+}